@@ -1,68 +1,379 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt, path::Path, str::FromStr};
 
+use futures::{stream, StreamExt};
 use reqwest::Url;
-use time::Month;
+use time::{Duration, Month, OffsetDateTime};
+use tracing::{error, info};
 
 use crate::{
     config::config,
-    model::{CalendarBmc, ModelManager},
-    scraper::client::Client,
+    model::{CoverBmc, MusicBrainzBmc},
+    scraper::{
+        client::Client,
+        cover_art::{cover_file_name, fetch_cover},
+        links::LinkProvider,
+        musicbrainz::MusicBrainzApi,
+    },
 };
 
 pub type CalendarData = HashMap<Month, Releases>;
 
 type Day = u8;
 
+/// The number of days before a release missing from MusicBrainz is looked up again.
+const MUSICBRAINZ_MISS_DAYS: i64 = 30;
+
+/// The number of days before the cover of a release that had none is looked up again.
+const COVER_MISS_DAYS: i64 = 7;
+
+/// The number of releases whose links are generated at once.
+const LINK_CONCURRENCY: usize = 8;
+
+/// The number of releases between two progress messages.
+const PROGRESS_INTERVAL: usize = 100;
+
 pub type Releases = HashMap<Day, Vec<Release>>;
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Release {
+    /// The artist as credited on Wikipedia, e.g. "Artist A / Artist B".
     pub artist: String,
+    /// Every artist taking part in the release, in the order they are credited.
+    pub artists: Vec<Participant>,
     pub album: String,
+    pub release_type: ReleaseType,
+    /// The title of the credited artist's Wikipedia page, e.g. "Ghost_(Swedish_band)".
+    pub wikipedia_artist: Option<String>,
+    /// The title of the album's Wikipedia page.
+    pub wikipedia_album: Option<String>,
+    /// The URL of the source Wikipedia cites for the release, e.g. a press release.
+    pub source_url: Option<String>,
+    /// The names of the release sources that listed the release, e.g. `wikipedia`.
+    pub providers: Vec<String>,
+    /// The release as found on MusicBrainz, once enriched.
+    pub musicbrainz: Option<MusicBrainzRelease>,
+    /// The file name of the cover art's thumbnail in the covers folder.
+    pub cover: Option<String>,
     pub links: Vec<Link>,
+    /// The genre of the artists, e.g. "Melodic Death Metal", if a source tells it.
+    pub genre: Option<String>,
 }
 
 impl Release {
     pub fn new(artist: impl Into<String>, album: impl Into<String>) -> Self {
         let mut album: String = album.into();
         album = album.split_whitespace().collect::<Vec<&str>>().join(" ");
-        if album.contains("[") {
-            album = album.split_once('[').unwrap().0.to_string();
-        }
+        let (album, release_type) = ReleaseType::parse_title(album.trim());
+        let artist: String = artist.into();
 
         Self {
-            artist: artist.into(),
+            artists: vec![Participant::new(artist.clone(), None)],
+            artist,
             album,
+            release_type,
+            wikipedia_artist: None,
+            wikipedia_album: None,
+            source_url: None,
+            providers: Vec::new(),
+            musicbrainz: None,
+            cover: None,
             links: Vec::new(),
+            genre: None,
+        }
+    }
+
+    /// Sets the artists taking part in a split or a collaboration.
+    ///
+    /// The credited artist is kept when the list is empty.
+    pub fn with_artists(mut self, artists: Vec<Participant>) -> Self {
+        if !artists.is_empty() {
+            self.artists = artists;
         }
+        self
     }
 
-    pub async fn generate_links(&mut self, client: &impl Client) {
-        let mm = &mut ModelManager::new();
-        let conn = &mut mm.conn;
+    /// Sets the titles of the artist's and the album's Wikipedia pages.
+    ///
+    /// The artist's page is also given to the sole participant of the release.
+    pub fn with_wikipedia(mut self, artist: Option<String>, album: Option<String>) -> Self {
+        if let [participant] = self.artists.as_mut_slice() {
+            if participant.wikipedia.is_none() {
+                participant.wikipedia = artist.clone();
+            }
+        }
+        self.wikipedia_artist = artist;
+        self.wikipedia_album = album;
+        self
+    }
 
-        if CalendarBmc::get_links(conn, &self.artist).is_none() {
-            let query = format!("{} {} full album", self.artist, self.album);
-            let mut query_encoded = String::new();
-            url_escape::encode_query_to_string(query, &mut query_encoded);
+    /// Sets the type of the release when the source states it explicitly.
+    pub fn with_release_type(mut self, release_type: ReleaseType) -> Self {
+        self.release_type = release_type;
+        self
+    }
 
-            let yt_url = format!("https://www.youtube.com/results?search_query={query_encoded}");
-            let yt_url = Url::parse(&yt_url).unwrap();
+    /// Sets the genre of the release's artists.
+    pub fn with_genre(mut self, genre: Option<String>) -> Self {
+        self.genre = genre.filter(|genre| !genre.trim().is_empty());
+        self
+    }
 
-            self.links.push(Link::Youtube(yt_url));
-            if config().IS_PROD {
-                if let Some(url) = client.get_bandcamp_link(self.artist.clone()).await {
-                    self.links.push(Link::Bandcamp(url))
-                }
+    /// Sets the URL of the source cited for the release.
+    pub fn with_source(mut self, source_url: Option<String>) -> Self {
+        self.source_url = source_url;
+        self
+    }
+
+    /// The first credited artist, to whom the release's links are attached.
+    pub fn primary_artist(&self) -> &str {
+        self.artists
+            .first()
+            .map(|participant| participant.name.as_str())
+            .unwrap_or(&self.artist)
+    }
+
+    /// Attaches the link of every provider that has one to the release.
+    pub async fn generate_links(&mut self, providers: &[Box<dyn LinkProvider>]) {
+        for provider in providers.iter() {
+            if let Some(url) = provider.link(self).await {
+                self.links.push(Link::new(provider.kind(), url));
             }
         }
     }
 }
 
+/// An artist taking part in a release.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Participant {
+    pub name: String,
+    /// The title of the artist's Wikipedia page, used to tell apart
+    /// artists sharing the same name.
+    pub wikipedia: Option<String>,
+}
+
+impl Participant {
+    pub fn new(name: impl Into<String>, wikipedia: Option<String>) -> Self {
+        Self {
+            name: name.into(),
+            wikipedia,
+        }
+    }
+}
+
+/// The identifiers and details of a release found on MusicBrainz.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MusicBrainzRelease {
+    /// The MBID of the release group, which gathers every edition of an album.
+    pub release_group_id: String,
+    /// The MBID of the edition that matched the search.
+    pub release_id: String,
+    pub track_count: Option<i32>,
+    /// The name of the label that published the release.
+    pub label: Option<String>,
+}
+
+/// Builds the URL of a Wikipedia page from its title.
+pub fn wikipedia_url(title: &str) -> String {
+    let mut url = String::from("https://en.wikipedia.org/wiki/");
+    url_escape::encode_path_to_string(title, &mut url);
+    url
+}
+
+/// The kind of a release, as annotated by the suffix Wikipedia
+/// appends to the album's title, e.g. "(EP)" or "(live album)".
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ReleaseType {
+    #[default]
+    Album,
+    Ep,
+    Live,
+    Compilation,
+    Demo,
+    Single,
+    Reissue,
+    BoxSet,
+}
+
+impl ReleaseType {
+    pub const ALL: [ReleaseType; 8] = [
+        ReleaseType::Album,
+        ReleaseType::Ep,
+        ReleaseType::Live,
+        ReleaseType::Compilation,
+        ReleaseType::Demo,
+        ReleaseType::Single,
+        ReleaseType::Reissue,
+        ReleaseType::BoxSet,
+    ];
+
+    /// The identifier stored in the database and used in query strings.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReleaseType::Album => "album",
+            ReleaseType::Ep => "ep",
+            ReleaseType::Live => "live",
+            ReleaseType::Compilation => "compilation",
+            ReleaseType::Demo => "demo",
+            ReleaseType::Single => "single",
+            ReleaseType::Reissue => "reissue",
+            ReleaseType::BoxSet => "box_set",
+        }
+    }
+
+    /// Splits the trailing type annotations off an album title.
+    ///
+    /// Annotations are removed from the end of the title for as long as they
+    /// are recognized. The type is taken from the last annotation, so
+    /// "Unspoken Names (Demo 1991) (EP)" is an EP titled "Unspoken Names".
+    pub fn parse_title(title: &str) -> (String, ReleaseType) {
+        let mut title = title.trim();
+        let mut release_type = None;
+
+        while let Some(rest) = title.strip_suffix(')') {
+            let Some((head, annotation)) = rest.rsplit_once(" (") else {
+                break;
+            };
+            let Some(kind) = Self::from_annotation(annotation) else {
+                break;
+            };
+
+            release_type.get_or_insert(kind);
+            title = head.trim_end();
+        }
+
+        (title.to_string(), release_type.unwrap_or_default())
+    }
+
+    fn from_annotation(annotation: &str) -> Option<ReleaseType> {
+        let annotation = annotation.trim().to_lowercase();
+
+        let kind = match annotation.as_str() {
+            "ep" | "mini-album" | "mini album" | "split ep" => ReleaseType::Ep,
+            "album" | "studio album" | "covers album" | "cover album" | "split album" => {
+                ReleaseType::Album
+            }
+            "single" | "digital single" => ReleaseType::Single,
+            "box set" | "boxset" | "box-set" => ReleaseType::BoxSet,
+            s if s.starts_with("live") => ReleaseType::Live,
+            s if s.contains("compilation") || s.starts_with("best of") => ReleaseType::Compilation,
+            s if s.starts_with("demo") => ReleaseType::Demo,
+            s if s.contains("reissue") || s.contains("re-release") || s.contains("remaster") => {
+                ReleaseType::Reissue
+            }
+            s if s.contains("box set") => ReleaseType::BoxSet,
+            _ => return None,
+        };
+        Some(kind)
+    }
+}
+
+impl fmt::Display for ReleaseType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ReleaseType::Album => "Album",
+            ReleaseType::Ep => "EP",
+            ReleaseType::Live => "Live",
+            ReleaseType::Compilation => "Compilation",
+            ReleaseType::Demo => "Demo",
+            ReleaseType::Single => "Single",
+            ReleaseType::Reissue => "Reissue",
+            ReleaseType::BoxSet => "Box set",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for ReleaseType {
+    type Err = String;
+
+    fn from_str(s: &str) -> core::result::Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase().replace([' ', '-'], "_");
+        ReleaseType::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == s || (s == "boxset" && *kind == ReleaseType::BoxSet))
+            .ok_or_else(|| format!("unknown release type `{s}`"))
+    }
+}
+
+/// A link attached to a release, e.g. where to listen to it.
 #[derive(Clone, Debug, PartialEq)]
-pub enum Link {
-    Bandcamp(Url),
-    Youtube(Url),
+pub struct Link {
+    pub kind: LinkKind,
+    pub url: Url,
+}
+
+impl Link {
+    pub fn new(kind: LinkKind, url: Url) -> Self {
+        Self { kind, url }
+    }
+}
+
+/// The kind of website a [`Link`] points to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LinkKind {
+    Youtube,
+    Bandcamp,
+    Spotify,
+    AppleMusic,
+    Deezer,
+    Tidal,
+    MetalArchives,
+    /// The shop of the label that published the release.
+    LabelShop,
+}
+
+impl LinkKind {
+    pub const ALL: [LinkKind; 8] = [
+        LinkKind::Youtube,
+        LinkKind::Bandcamp,
+        LinkKind::Spotify,
+        LinkKind::AppleMusic,
+        LinkKind::Deezer,
+        LinkKind::Tidal,
+        LinkKind::MetalArchives,
+        LinkKind::LabelShop,
+    ];
+
+    /// The identifier stored in the database and used in the configuration.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LinkKind::Youtube => "youtube",
+            LinkKind::Bandcamp => "bandcamp",
+            LinkKind::Spotify => "spotify",
+            LinkKind::AppleMusic => "apple_music",
+            LinkKind::Deezer => "deezer",
+            LinkKind::Tidal => "tidal",
+            LinkKind::MetalArchives => "metal_archives",
+            LinkKind::LabelShop => "label_shop",
+        }
+    }
+}
+
+impl fmt::Display for LinkKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            LinkKind::Youtube => "Youtube",
+            LinkKind::Bandcamp => "Bandcamp",
+            LinkKind::Spotify => "Spotify",
+            LinkKind::AppleMusic => "Apple Music",
+            LinkKind::Deezer => "Deezer",
+            LinkKind::Tidal => "Tidal",
+            LinkKind::MetalArchives => "Metal Archives",
+            LinkKind::LabelShop => "Label shop",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for LinkKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> core::result::Result<Self, Self::Err> {
+        LinkKind::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == s)
+            .ok_or_else(|| format!("unknown link kind `{s}`"))
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -95,9 +406,9 @@ impl Calendar {
     pub fn add_release(&mut self, month: Month, day: Day, release: Release) {
         self.data
             .entry(month)
-            .or_insert_with(Releases::new)
+            .or_default()
             .entry(day)
-            .or_insert_with(Vec::new)
+            .or_default()
             .push(release);
     }
 
@@ -105,13 +416,128 @@ impl Calendar {
         self.data.get(&month).and_then(|map| map.get(&day))
     }
 
-    pub async fn update_links(&mut self, client: &impl Client) {
-        for (_, day_releases) in self.data.iter_mut() {
-            for release in day_releases.iter_mut().flat_map(|(_, releases)| releases) {
-                release.generate_links(client).await;
+    /// Generates the links of every release, [`LINK_CONCURRENCY`] releases at a time.
+    pub async fn update_links(&mut self, providers: &[Box<dyn LinkProvider>]) {
+        let releases: Vec<&mut Release> = self
+            .data
+            .values_mut()
+            .flat_map(|days| days.values_mut())
+            .flatten()
+            .collect();
+        let total = releases.len();
+        info!("Generating the links of {total} releases");
+
+        // The futures are created beforehand: a stream holding a closure across
+        // an await point is not `Send` to the compiler, which the scheduled job requires.
+        let lookups: Vec<_> = releases
+            .into_iter()
+            .map(|release| release.generate_links(providers))
+            .collect();
+        let mut lookups = stream::iter(lookups).buffer_unordered(LINK_CONCURRENCY);
+        let mut done = 0;
+        while lookups.next().await.is_some() {
+            done += 1;
+            if done % PROGRESS_INTERVAL == 0 || done == total {
+                info!("Generated the links of {done}/{total} releases");
             }
         }
     }
+
+    /// Fetches the cover art of every release and stores its thumbnail in
+    /// the `COVERS_FOLDER`.
+    ///
+    /// Releases whose cover could not be found are remembered and only
+    /// looked up again once [`COVER_MISS_DAYS`] have passed.
+    pub async fn update_covers(&mut self, client: &impl Client) {
+        let misses = match CoverBmc::get_misses_since(
+            OffsetDateTime::now_utc() - Duration::days(COVER_MISS_DAYS),
+        ) {
+            Ok(misses) => misses,
+            Err(err) => {
+                error!("Error fetching the missing covers: {err}");
+                return;
+            }
+        };
+
+        let folder = Path::new(&config().COVERS_FOLDER);
+        let releases: Vec<&mut Release> = self
+            .data
+            .values_mut()
+            .flat_map(|days| days.values_mut())
+            .flatten()
+            .filter(|release| !misses.contains(&cover_file_name(release)))
+            .collect();
+
+        for release in releases {
+            match fetch_cover(client, release, folder).await {
+                Ok(Some(cover)) => release.cover = Some(cover),
+                Ok(None) => {
+                    if let Err(err) = CoverBmc::add_miss(&cover_file_name(release)) {
+                        error!("Error remembering the missing cover: {err}");
+                    }
+                }
+                Err(err) => error!(
+                    "Error fetching the cover of {} - {}: {err}",
+                    release.artist, release.album
+                ),
+            }
+        }
+    }
+
+    /// Looks up every release on MusicBrainz to find its identifiers.
+    ///
+    /// Releases identified during a previous run are given their stored
+    /// identifiers without querying the service again. Releases that could
+    /// not be found are remembered and only looked up again once
+    /// [`MUSICBRAINZ_MISS_DAYS`] have passed.
+    pub async fn enrich(&mut self, api: &impl MusicBrainzApi) {
+        let (known, misses) = match (
+            MusicBrainzBmc::get_known(),
+            MusicBrainzBmc::get_misses_since(
+                OffsetDateTime::now_utc() - Duration::days(MUSICBRAINZ_MISS_DAYS),
+            ),
+        ) {
+            (Ok(known), Ok(misses)) => (known, misses),
+            (Err(err), _) | (_, Err(err)) => {
+                error!("Error fetching the MusicBrainz cache: {err}");
+                return;
+            }
+        };
+
+        let releases: Vec<&mut Release> = self
+            .data
+            .values_mut()
+            .flat_map(|days| days.values_mut())
+            .flatten()
+            .collect();
+
+        let (mut num_found, mut num_missed) = (0, 0);
+        for release in releases {
+            let key = (release.artist.clone(), release.album.clone());
+            if let Some(found) = known.get(&key) {
+                release.musicbrainz = Some(found.clone());
+                continue;
+            } else if misses.contains(&key) {
+                continue;
+            }
+
+            match api.find_release(&release.artist, &release.album).await {
+                Ok(Some(found)) => {
+                    release.musicbrainz = Some(found);
+                    num_found += 1;
+                }
+                Ok(None) => {
+                    num_missed += 1;
+                    if let Err(err) = MusicBrainzBmc::add_miss(&release.artist, &release.album) {
+                        error!("Error caching the MusicBrainz miss of {key:?}: {err}");
+                    }
+                }
+                Err(err) => error!("Error looking up {key:?} on MusicBrainz: {err}"),
+            }
+        }
+
+        info!("Found {num_found} new releases on MusicBrainz, missed {num_missed}");
+    }
 }
 
 #[cfg(test)]
@@ -174,4 +600,70 @@ mod tests {
         pretty_assertions::assert_eq!(got, Some(&vec![release]));
         Ok(())
     }
+
+    #[test]
+    fn test_release_type_from_title_ok() -> Result<()> {
+        let test_cases = vec![
+            ("Ecdysis", "Ecdysis", ReleaseType::Album),
+            ("Hard Skool (EP)", "Hard Skool", ReleaseType::Ep),
+            ("Cold World EP (EP)", "Cold World EP", ReleaseType::Ep),
+            (
+                "Unspoken Names (Demo 1991) (EP)",
+                "Unspoken Names",
+                ReleaseType::Ep,
+            ),
+            (
+                "Heavy Metal Ninja (mini-album)",
+                "Heavy Metal Ninja",
+                ReleaseType::Ep,
+            ),
+            (
+                "Reaping the Whirlwind (live album)",
+                "Reaping the Whirlwind",
+                ReleaseType::Live,
+            ),
+            (
+                "... and Stuff (compilation album)",
+                "... and Stuff",
+                ReleaseType::Compilation,
+            ),
+            ("Revival (covers album)", "Revival", ReleaseType::Album),
+            (
+                "Into the Abyss (Demo 1991)",
+                "Into the Abyss",
+                ReleaseType::Demo,
+            ),
+            ("Painkiller (reissue)", "Painkiller", ReleaseType::Reissue),
+            (
+                "Complete Works (box set)",
+                "Complete Works",
+                ReleaseType::BoxSet,
+            ),
+            ("Mercy (single)", "Mercy", ReleaseType::Single),
+            ("Assassine(s)", "Assassine(s)", ReleaseType::Album),
+            (
+                "Zeal & Ardor (Deluxe)",
+                "Zeal & Ardor (Deluxe)",
+                ReleaseType::Album,
+            ),
+        ];
+
+        for (title, want_title, want_type) in test_cases {
+            let got = Release::new("Artist", title);
+
+            pretty_assertions::assert_eq!(got.album, want_title, "title: {title}");
+            pretty_assertions::assert_eq!(got.release_type, want_type, "title: {title}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_release_type_from_str_ok() -> Result<()> {
+        for kind in ReleaseType::ALL {
+            pretty_assertions::assert_eq!(kind.as_str().parse::<ReleaseType>(), Ok(kind));
+        }
+        pretty_assertions::assert_eq!("Box set".parse::<ReleaseType>(), Ok(ReleaseType::BoxSet));
+        assert!("mixtape".parse::<ReleaseType>().is_err());
+        Ok(())
+    }
 }
@@ -1,11 +1,17 @@
-use std::sync::OnceLock;
+use std::{collections::HashMap, sync::OnceLock};
 
-use crate::{error::Result, support::env::get_env};
+use crate::{
+    error::{Error, Result},
+    support::env::{get_env, get_env_or},
+};
 
 pub fn config() -> &'static Config {
     static INSTANCE: OnceLock<Config> = OnceLock::new();
 
     INSTANCE.get_or_init(|| {
+        #[cfg(test)]
+        crate::support::env::set_test_env();
+
         Config::load_from_env()
             .unwrap_or_else(|err| panic!("Fatal - Could not load configuration: {err:?}"))
     })
@@ -14,16 +20,174 @@ pub fn config() -> &'static Config {
 #[allow(non_snake_case)]
 pub struct Config {
     pub BASE_URL: String,
+    /// The address the messages of the contact form are forwarded to.
+    pub CONTACT_EMAIL: Option<String>,
+    /// The folder where the thumbnails of the releases' cover art are stored.
+    pub COVERS_FOLDER: String,
     pub DATABASE_URL: String,
     pub IS_PROD: bool,
+    /// The search URL of the labels' shops, keyed by label name.
+    pub LABEL_SHOPS: HashMap<String, String>,
+    /// The kinds of links generated for the releases, e.g. `youtube,bandcamp,spotify`.
+    pub LINK_PROVIDERS: Vec<String>,
+    /// The base URL of the MusicBrainz web service releases are enriched from.
+    pub MUSICBRAINZ_URL: String,
+    /// The path to the JSON file listing the chat channels notified of the releases.
+    pub NOTIFIERS_FILE: Option<String>,
+    /// The folder of recorded responses served instead of sending requests.
+    pub OFFLINE_DIR: Option<String>,
+    /// The proxy outgoing requests go through, e.g. `http://proxy.example.com:3128`.
+    pub PROXY_URL: Option<String>,
+    /// The folder where the responses to outgoing requests are recorded.
+    pub RECORD_DIR: Option<String>,
+    /// The providers releases are fetched from, e.g. `wikipedia,metal_archives`.
+    pub RELEASE_SOURCES: Vec<String>,
+    /// The path to a CSV file of user-submitted releases.
+    pub RELEASE_SOURCES_CSV: Option<String>,
+    /// The sender of the emails, e.g. `Heavy Metal Notifier <noreply@example.com>`.
+    pub SMTP_FROM: String,
+    /// The SMTP server emails are sent through. No emails are sent when unset.
+    pub SMTP_HOST: Option<String>,
+    pub SMTP_PASSWORD: Option<String>,
+    pub SMTP_PORT: u16,
+    /// How the connection to the SMTP server is secured: `starttls`, `tls` or `none`.
+    pub SMTP_TLS: String,
+    pub SMTP_USERNAME: Option<String>,
+    /// The webhooks the events are posted to.
+    pub WEBHOOKS: Vec<WebhookConfig>,
+}
+
+/// A webhook of the `WEBHOOKS` configuration.
+pub struct WebhookConfig {
+    pub url: String,
+    /// The events posted to the webhook, e.g. `daily_releases,release_added`.
+    pub events: Vec<String>,
+    /// The secret the payloads are signed with.
+    pub secret: String,
 }
 
 impl Config {
     pub fn load_from_env() -> Result<Self> {
         Ok(Self {
             BASE_URL: get_env("BASE_URL")?,
+            CONTACT_EMAIL: get_env("CONTACT_EMAIL")
+                .ok()
+                .filter(|email| !email.is_empty()),
+            COVERS_FOLDER: get_env_or("COVERS_FOLDER", "covers"),
             DATABASE_URL: get_env("DATABASE_URL")?,
             IS_PROD: get_env("IS_PROD")? == "true",
+            LABEL_SHOPS: parse_label_shops(&get_env_or("LABEL_SHOPS", "")),
+            LINK_PROVIDERS: list(&get_env_or(
+                "LINK_PROVIDERS",
+                "youtube,bandcamp,spotify,apple_music,deezer,tidal,metal_archives,label_shop",
+            )),
+            MUSICBRAINZ_URL: get_env_or("MUSICBRAINZ_URL", "https://musicbrainz.org"),
+            NOTIFIERS_FILE: get_env("NOTIFIERS_FILE")
+                .ok()
+                .filter(|path| !path.is_empty()),
+            OFFLINE_DIR: get_env("OFFLINE_DIR").ok().filter(|dir| !dir.is_empty()),
+            PROXY_URL: get_env("PROXY_URL").ok().filter(|url| !url.is_empty()),
+            RECORD_DIR: get_env("RECORD_DIR").ok().filter(|dir| !dir.is_empty()),
+            RELEASE_SOURCES: list(&get_env_or("RELEASE_SOURCES", "wikipedia")),
+            RELEASE_SOURCES_CSV: get_env("RELEASE_SOURCES_CSV").ok(),
+            SMTP_FROM: get_env_or("SMTP_FROM", "Heavy Metal Notifier <noreply@localhost>"),
+            SMTP_HOST: get_env("SMTP_HOST").ok().filter(|host| !host.is_empty()),
+            SMTP_PASSWORD: get_env("SMTP_PASSWORD").ok(),
+            SMTP_PORT: get_env_or("SMTP_PORT", "587")
+                .parse()
+                .map_err(|_| Error::InvalidEnv("SMTP_PORT"))?,
+            SMTP_TLS: get_env_or("SMTP_TLS", "starttls"),
+            SMTP_USERNAME: get_env("SMTP_USERNAME")
+                .ok()
+                .filter(|user| !user.is_empty()),
+            WEBHOOKS: parse_webhooks(&get_env_or("WEBHOOKS", ""))?,
+        })
+    }
+}
+
+/// Splits a comma-separated list of names.
+fn list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// Parses the `LABEL_SHOPS` configuration, e.g.
+/// `Nuclear Blast=https://shop.example.com/search?q={query};Relapse=...`.
+fn parse_label_shops(value: &str) -> HashMap<String, String> {
+    value
+        .split(';')
+        .filter_map(|shop| {
+            let (label, template) = shop.split_once('=')?;
+            let (label, template) = (label.trim(), template.trim());
+            if label.is_empty() || template.is_empty() {
+                return None;
+            }
+            Some((label.to_string(), template.to_string()))
         })
+        .collect()
+}
+
+/// Parses the `WEBHOOKS` configuration, a semicolon-separated list of webhooks
+/// whose URL, events and secret are separated by spaces, e.g.
+/// `https://example.com/hook daily_releases,release_added s3cr3t;...`.
+fn parse_webhooks(value: &str) -> Result<Vec<WebhookConfig>> {
+    value
+        .split(';')
+        .filter(|webhook| !webhook.trim().is_empty())
+        .map(
+            |webhook| match webhook.split_whitespace().collect::<Vec<_>>().as_slice() {
+                [url, events, secret] => Ok(WebhookConfig {
+                    url: url.to_string(),
+                    events: list(events),
+                    secret: secret.to_string(),
+                }),
+                _ => Err(Error::InvalidEnv("WEBHOOKS")),
+            },
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Result<T> = core::result::Result<T, Box<dyn std::error::Error>>;
+
+    #[test]
+    fn test_parse_webhooks_ok() -> Result<()> {
+        let got = parse_webhooks(
+            "https://a.example/hook daily_releases,release_added s3cr3t; https://b.example/hook release_moved t0p;",
+        )?;
+
+        pretty_assertions::assert_eq!(
+            got.iter()
+                .map(|webhook| (
+                    webhook.url.as_str(),
+                    webhook.events.clone(),
+                    webhook.secret.as_str()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "https://a.example/hook",
+                    vec!["daily_releases".to_string(), "release_added".to_string()],
+                    "s3cr3t"
+                ),
+                (
+                    "https://b.example/hook",
+                    vec!["release_moved".to_string()],
+                    "t0p"
+                ),
+            ]
+        );
+        assert!(parse_webhooks("")?.is_empty());
+        assert!(matches!(
+            parse_webhooks("https://a.example/hook release_added"),
+            Err(Error::InvalidEnv("WEBHOOKS"))
+        ));
+        Ok(())
     }
 }
@@ -14,20 +14,43 @@ pub enum Error {
     },
     DbFeedError,
     MissingEnv(&'static str),
+    InvalidEnv(&'static str),
     RequestFail,
+    /// A URL requested in offline mode whose response was never recorded.
+    MissingFixture(String),
+    /// A page answered `304 Not Modified` to a request that was not conditional.
+    UnexpectedNotModified(String),
     CalendarUpdateFail,
+    /// An imported file whose content is not of the expected format.
+    InvalidImport(&'static str),
 
     // Externals
     #[from]
+    Csv(csv::Error),
+    #[from]
     Diesel(diesel::result::Error),
     #[from]
+    EmailAddress(lettre::address::AddressError),
+    #[from]
+    EmailBuild(lettre::error::Error),
+    #[from]
     Env(std::env::VarError),
     #[from]
     Job(tokio_cron_scheduler::JobSchedulerError),
     #[from]
+    Join(tokio::task::JoinError),
+    #[from]
+    Image(image::ImageError),
+    #[from]
     Io(std::io::Error),
     #[from]
     Reqwest(reqwest::Error),
+    #[from]
+    SerdeJson(serde_json::Error),
+    #[from]
+    Smtp(lettre::transport::smtp::Error),
+    #[from]
+    TimeRange(time::error::ComponentRange),
 }
 
 impl core::fmt::Display for Error {
@@ -0,0 +1,369 @@
+//! Reads the artists listened to from the exports of music services, to
+//! follow them in one go.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    str::FromStr,
+};
+
+use serde_json::Value;
+
+use crate::{
+    error::{Error, Result},
+    model::Artist,
+    support::names::{max_similarity, normalize, normalized_similarity},
+};
+
+/// The minimum similarity for an imported name to be matched with an
+/// artist of the calendar whose name is spelled differently.
+pub const MIN_SIMILARITY: f32 = 0.85;
+
+/// The maximum number of distinct names read from an export, to bound the
+/// time spent matching them.
+pub const MAX_NAMES: usize = 2000;
+
+/// The header names of the column listing the artists in a CSV file.
+const ARTIST_HEADERS: [&str; 3] = ["artist", "artist name", "name"];
+
+/// The kind of file the artists are imported from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ImportFormat {
+    /// A CSV file whose first column lists artist names, with or without a header.
+    #[default]
+    Csv,
+    /// A file of a Spotify data export, i.e. `YourLibrary.json` or a streaming history.
+    Spotify,
+    /// A Last.fm library export, either a CSV of scrobbles starting with the
+    /// artist or the JSON of the `library.getArtists` API method.
+    LastFm,
+}
+
+impl ImportFormat {
+    pub const ALL: [ImportFormat; 3] = [
+        ImportFormat::Csv,
+        ImportFormat::Spotify,
+        ImportFormat::LastFm,
+    ];
+
+    /// The identifier of the format, as submitted by the import form.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ImportFormat::Csv => "csv",
+            ImportFormat::Spotify => "spotify",
+            ImportFormat::LastFm => "lastfm",
+        }
+    }
+
+    /// The name of the format shown to users.
+    pub fn label(&self) -> &'static str {
+        match self {
+            ImportFormat::Csv => "CSV of artist names",
+            ImportFormat::Spotify => "Spotify data export (JSON)",
+            ImportFormat::LastFm => "Last.fm library export",
+        }
+    }
+}
+
+impl fmt::Display for ImportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ImportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> core::result::Result<Self, Self::Err> {
+        ImportFormat::ALL
+            .into_iter()
+            .find(|format| format.as_str() == s.trim().to_lowercase())
+            .ok_or_else(|| format!("unknown import format `{s}`"))
+    }
+}
+
+/// Reads the distinct artist names of an exported file, in the order they
+/// first appear.
+pub fn parse_names(format: ImportFormat, content: &str) -> Result<Vec<String>> {
+    let content = content.trim_start_matches('\u{feff}');
+    let names = match format {
+        ImportFormat::Csv => csv_names(content)?,
+        ImportFormat::Spotify => spotify_names(&serde_json::from_str(content)?)?,
+        ImportFormat::LastFm if content.trim_start().starts_with('{') => {
+            lastfm_names(&serde_json::from_str(content)?)?
+        }
+        ImportFormat::LastFm => csv_names(content)?,
+    };
+
+    let mut seen = HashSet::new();
+    Ok(names
+        .into_iter()
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty() && seen.insert(name.to_lowercase()))
+        .take(MAX_NAMES)
+        .collect())
+}
+
+/// The first column of every row, skipping the header if there is one.
+fn csv_names(content: &str) -> Result<Vec<String>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(content.as_bytes());
+
+    let mut names = Vec::new();
+    for (i, record) in reader.records().enumerate() {
+        let record = record?;
+        let Some(name) = record.get(0) else {
+            continue;
+        };
+        if i == 0 && ARTIST_HEADERS.contains(&name.to_lowercase().as_str()) {
+            continue;
+        }
+        names.push(name.to_string());
+    }
+    Ok(names)
+}
+
+/// The artists of `YourLibrary.json`, or of the entries of a streaming history.
+fn spotify_names(json: &Value) -> Result<Vec<String>> {
+    let names = match json {
+        Value::Array(entries) => entries
+            .iter()
+            .filter_map(|entry| {
+                entry
+                    .get("artistName")
+                    .or_else(|| entry.get("master_metadata_album_artist_name"))
+                    .and_then(Value::as_str)
+            })
+            .map(str::to_string)
+            .collect(),
+        Value::Object(library) => {
+            let field = |list: &str, key: &str| {
+                library
+                    .get(list)
+                    .and_then(Value::as_array)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|item| item.get(key).and_then(Value::as_str))
+                    .map(str::to_string)
+                    .collect::<Vec<_>>()
+            };
+            [
+                field("artists", "name"),
+                field("tracks", "artist"),
+                field("albums", "artist"),
+            ]
+            .concat()
+        }
+        _ => Vec::new(),
+    };
+
+    if names.is_empty() {
+        return Err(Error::InvalidImport(
+            "no artist found in the Spotify export",
+        ));
+    }
+    Ok(names)
+}
+
+/// The artists of the response of the `library.getArtists` API method.
+fn lastfm_names(json: &Value) -> Result<Vec<String>> {
+    let artists = json
+        .pointer("/artists/artist")
+        .and_then(Value::as_array)
+        .ok_or(Error::InvalidImport(
+            "no artist found in the Last.fm export",
+        ))?;
+
+    Ok(artists
+        .iter()
+        .filter_map(|artist| artist.get("name").and_then(Value::as_str))
+        .map(str::to_string)
+        .collect())
+}
+
+/// The outcome of matching imported names against the artists of the calendar.
+#[derive(Debug, Default, PartialEq)]
+pub struct ArtistMatches<'a> {
+    /// The imported names along with the artists they match.
+    pub matched: Vec<(String, &'a Artist)>,
+    /// The names matching no artist.
+    pub unmatched: Vec<String>,
+}
+
+impl ArtistMatches<'_> {
+    /// The ids of the matched artists, without duplicates.
+    pub fn artist_ids(&self) -> Vec<i32> {
+        let mut ids = self
+            .matched
+            .iter()
+            .map(|(_, artist)| artist.id)
+            .collect::<Vec<_>>();
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+}
+
+/// Matches the names with the artists. A name is matched with every artist
+/// sharing it, regardless of case and punctuation, or else with every
+/// artist sharing the most similar name above [`MIN_SIMILARITY`].
+///
+/// This compares every name with every artist, so it is best run on a
+/// blocking thread.
+pub fn match_artists<'a>(names: &[String], artists: &'a [Artist]) -> ArtistMatches<'a> {
+    // The artists grouped by normalized name, in the order of the artists.
+    let mut by_name: Vec<(Vec<char>, Vec<&Artist>)> = Vec::new();
+    let mut index: HashMap<Vec<char>, usize> = HashMap::new();
+    for artist in artists {
+        let name = normalize(&artist.name);
+        match index.get(&name) {
+            Some(&i) => by_name[i].1.push(artist),
+            None => {
+                index.insert(name.clone(), by_name.len());
+                by_name.push((name, vec![artist]));
+            }
+        }
+    }
+
+    let mut matches = ArtistMatches::default();
+    for name in names {
+        let normalized = normalize(name);
+        let found = match index.get(&normalized) {
+            Some(&i) => Some(&by_name[i].1),
+            None => by_name
+                .iter()
+                .filter(|(other, _)| {
+                    max_similarity(normalized.len(), other.len()) >= MIN_SIMILARITY
+                })
+                .map(|(other, artists)| (normalized_similarity(&normalized, other), artists))
+                .filter(|(score, _)| *score >= MIN_SIMILARITY)
+                .max_by(|(a, _), (b, _)| a.total_cmp(b))
+                .map(|(_, artists)| artists),
+        };
+        match found {
+            Some(artists) => {
+                for artist in artists {
+                    matches.matched.push((name.clone(), artist));
+                }
+            }
+            None => matches.unmatched.push(name.clone()),
+        }
+    }
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Result<T> = core::result::Result<T, Box<dyn std::error::Error>>;
+
+    fn artist(id: i32, name: &str) -> Artist {
+        Artist {
+            id,
+            name: name.to_string(),
+            wikipedia: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_csv_ok() -> Result<()> {
+        let content = "\u{feff}Artist,Plays\nOpeth,12\n\"Guns N' Roses\",3\nopeth,1\n\n  Ghost  \n";
+
+        let got = parse_names(ImportFormat::Csv, content)?;
+
+        pretty_assertions::assert_eq!(got, vec!["Opeth", "Guns N' Roses", "Ghost"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_spotify_ok() -> Result<()> {
+        let library = r#"{
+            "tracks": [
+                {"artist": "Wintersun", "album": "Time I", "track": "Sons of Winter and Stars"},
+                {"artist": "Opeth", "album": "Ghost Reveries", "track": "Ghost of Perdition"}
+            ],
+            "albums": [{"artist": "Wintersun", "album": "Time II"}],
+            "artists": [{"name": "Insomnium", "uri": "spotify:artist:1"}]
+        }"#;
+        let history = r#"[
+            {"endTime": "2024-10-01 20:00", "artistName": "Gojira", "trackName": "Amazonia", "msPlayed": 1000},
+            {"ts": "2024-10-01T20:05:00Z", "master_metadata_album_artist_name": "Mastodon"},
+            {"ts": "2024-10-01T20:10:00Z", "master_metadata_album_artist_name": null}
+        ]"#;
+
+        pretty_assertions::assert_eq!(
+            parse_names(ImportFormat::Spotify, library)?,
+            vec!["Insomnium", "Wintersun", "Opeth"]
+        );
+        pretty_assertions::assert_eq!(
+            parse_names(ImportFormat::Spotify, history)?,
+            vec!["Gojira", "Mastodon"]
+        );
+        assert!(parse_names(ImportFormat::Spotify, r#"{"podcasts": []}"#).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_lastfm_ok() -> Result<()> {
+        let scrobbles = "Opeth,Blackwater Park,The Drapery Falls,01 Oct 2024 20:00\n\
+                         Opeth,Damnation,Windowpane,01 Oct 2024 20:10\n\
+                         Enslaved,Heimdal,Kingdom,01 Oct 2024 20:20\n";
+        let api = r#"{"artists": {"artist": [{"name": "Emperor", "playcount": "42"}, {"name": "Ulver"}]}}"#;
+
+        pretty_assertions::assert_eq!(
+            parse_names(ImportFormat::LastFm, scrobbles)?,
+            vec!["Opeth", "Enslaved"]
+        );
+        pretty_assertions::assert_eq!(
+            parse_names(ImportFormat::LastFm, api)?,
+            vec!["Emperor", "Ulver"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_match_artists_ok() -> Result<()> {
+        let artists = vec![
+            artist(1, "Guns N' Roses"),
+            artist(2, "Blood Incantation"),
+            artist(3, "Wintersun"),
+            artist(4, "Ghost"),
+            artist(5, "Ghost"),
+            artist(6, "Nightfall"),
+            artist(7, "Nightfall"),
+        ];
+        let names = [
+            "guns n roses",
+            "Blood Incantations",
+            "Ghost",
+            "Ghost Bath",
+            "Nightfal",
+            "Opeth",
+        ]
+        .map(String::from);
+
+        let got = match_artists(&names, &artists);
+
+        pretty_assertions::assert_eq!(
+            got.matched
+                .iter()
+                .map(|(name, artist)| (name.as_str(), artist.id))
+                .collect::<Vec<_>>(),
+            vec![
+                ("guns n roses", 1),
+                ("Blood Incantations", 2),
+                ("Ghost", 4),
+                ("Ghost", 5),
+                ("Nightfal", 6),
+                ("Nightfal", 7)
+            ]
+        );
+        pretty_assertions::assert_eq!(got.unmatched, vec!["Ghost Bath", "Opeth"]);
+        pretty_assertions::assert_eq!(got.artist_ids(), vec![1, 2, 4, 5, 6, 7]);
+        Ok(())
+    }
+}
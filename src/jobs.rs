@@ -1,19 +1,448 @@
 //! The `jobs` module implements functions that are meant to be run periodically.
 
-use time::OffsetDateTime;
+use std::{collections::HashMap, fmt};
+
+use futures::{stream, StreamExt};
+use time::{Date, Duration, Month, OffsetDateTime};
+use tracing::{error, info};
 
 use crate::{
+    config::config,
     error::Result,
-    model::CalendarBmc,
-    scraper::{client::MainClient, wiki::scrape},
+    mailer::Mailer,
+    model::{
+        CalendarBmc, LinkBmc, OutboxBmc, OutboxChannel, OutboxEntry, Release, ReleaseFilter,
+        ReleaseWithArtists, RevisionBmc, SnapshotBmc, SubscriberBmc,
+    },
+    notifiers::{notifiers_from_config, Notifier},
+    scraper::{
+        bandcamp,
+        client::AppClient,
+        links::providers_from_config,
+        musicbrainz::MusicBrainzWebService,
+        source::{fetch_calendar, fetch_revisions, sources_from_config},
+        wiki,
+    },
+    web::templates,
+    webhooks::{self, Dispatcher, EventKind},
 };
 
-/// Fetches, scrapes and updates the heavy metal calendar for the current 
-/// year and saves it in the database.
+/// The number of days after which an artist's Bandcamp page is looked up again.
+const BANDCAMP_RECHECK_DAYS: i64 = 30;
+
+/// The number of artists looked up on Bandcamp at once. The requests
+/// themselves are rate limited by the [`MainClient`](crate::scraper::client::MainClient).
+const BANDCAMP_CONCURRENCY: usize = 4;
+
+/// The number of artists between two progress messages.
+const PROGRESS_INTERVAL: usize = 50;
+
+/// Fetches, scrapes and updates the heavy metal calendar for the current
+/// year from the configured release sources and saves it in the database.
+///
+/// The update is skipped when every source tells the revision of its listing
+/// and none changed since the last update. In production, the releases are
+/// also looked up on MusicBrainz. The releases added and moved by the update
+/// are posted to the webhooks, unless the year was empty before.
 pub async fn update_calendar() -> Result<()> {
-    let client = MainClient::new();
-    let mut calendar = scrape(&client, OffsetDateTime::now_utc().year()).await?;
-    calendar.update_links(&client).await;
+    let client = AppClient::new()?;
+    let sources = sources_from_config(&client);
+    let year = OffsetDateTime::now_utc().year();
+
+    let revisions = fetch_revisions(&sources, year).await;
+    let mut is_unchanged = !sources.is_empty();
+    for (source, revision) in sources.iter().zip(revisions.iter()) {
+        is_unchanged &= revision.is_some() && RevisionBmc::get(source.name(), year)? == *revision;
+    }
+    if is_unchanged {
+        info!("The release sources of {year} are unchanged since the last update");
+        return Ok(());
+    }
+
+    let mut calendar = fetch_calendar(&sources, year).await?;
+    if config().IS_PROD {
+        calendar.enrich(&MusicBrainzWebService::new()?).await;
+    }
+    calendar.update_links(&providers_from_config()).await;
+    calendar.update_covers(&client).await;
+
+    // A source that failed contributed no releases, and must be fetched again next time.
+    let contributed = sources
+        .iter()
+        .map(|source| {
+            calendar
+                .data
+                .values()
+                .flat_map(|days| days.values())
+                .flatten()
+                .any(|release| release.providers.iter().any(|p| p == source.name()))
+        })
+        .collect::<Vec<_>>();
+
+    let dispatcher = Dispatcher::from_config()?;
+    let is_notified =
+        dispatcher.wants(EventKind::ReleaseAdded) || dispatcher.wants(EventKind::ReleaseMoved);
+    let before = if is_notified {
+        year_releases(year)?
+    } else {
+        Vec::new()
+    };
     CalendarBmc::create_or_update(calendar)?;
+    if !before.is_empty() {
+        let events = webhooks::release_changes(&before, &year_releases(year)?)?;
+        // Posting to slow webhooks must not hold back the revisions below.
+        tokio::spawn(async move {
+            if let Err(err) = dispatcher.dispatch(&events).await {
+                error!("Error posting the changes of the calendar to the webhooks: {err}");
+            }
+        });
+    }
+
+    for ((source, revision), contributed) in sources.iter().zip(revisions).zip(contributed) {
+        if let (Some(revision), true) = (revision, contributed) {
+            RevisionBmc::set(source.name(), year, &revision)?;
+        }
+    }
+    Ok(())
+}
+
+/// Looks up the Bandcamp page of the artists never looked up before and
+/// of those looked up more than [`BANDCAMP_RECHECK_DAYS`] days ago.
+pub async fn update_bandcamp_links() -> Result<()> {
+    let client = AppClient::new()?;
+    let stale = LinkBmc::get_stale_bandcamp(
+        OffsetDateTime::now_utc() - Duration::days(BANDCAMP_RECHECK_DAYS),
+    )?;
+    let total = stale.len();
+    info!("Looking up the Bandcamp page of {total} artists");
+
+    let lookups: Vec<_> = stale
+        .into_iter()
+        .map(|artist| {
+            let client = &client;
+            async move { (bandcamp::discover(client, &artist.name).await, artist) }
+        })
+        .collect();
+    let mut lookups = stream::iter(lookups).buffer_unordered(BANDCAMP_CONCURRENCY);
+
+    let mut done = 0;
+    while let Some((found, artist)) = lookups.next().await {
+        match found {
+            Ok(found) => LinkBmc::update_bandcamp(artist.id, found.as_ref())?,
+            Err(err) => error!("Error looking up {} on Bandcamp: {err}", artist.name),
+        }
+
+        done += 1;
+        if done % PROGRESS_INTERVAL == 0 || done == total {
+            info!("Looked up {done}/{total} artists on Bandcamp");
+        }
+    }
+
+    Ok(())
+}
+
+/// Queues the releases of the day in the notification outbox, then sends
+/// the notifications waiting in the outbox.
+///
+/// The releases are queued for the confirmed subscribers when an SMTP server
+/// is configured, for the webhooks subscribed to `daily_releases` and for the
+/// chat channels of the `NOTIFIERS_FILE` whose filter they match. A release
+/// is queued only once per recipient, so running the job again sends nothing
+/// new.
+pub async fn send_daily_notifications() -> Result<()> {
+    queue_daily_notifications()?;
+    process_outbox().await
+}
+
+fn queue_daily_notifications() -> Result<()> {
+    let releases = CalendarBmc::get()?;
+    if releases.is_empty() {
+        info!("No releases today, skipping the daily notifications");
+        return Ok(());
+    }
+    let all = releases
+        .iter()
+        .map(|(release, _, _)| release)
+        .collect::<Vec<_>>();
+
+    let mut num_queued = 0;
+    if config().SMTP_HOST.is_some() {
+        for subscriber in SubscriberBmc::get_confirmed()? {
+            num_queued +=
+                OutboxBmc::enqueue(OutboxChannel::Email, &subscriber.id.to_string(), &all)?;
+        }
+    }
+
+    for url in Dispatcher::from_config()?.urls(EventKind::DailyReleases) {
+        num_queued += OutboxBmc::enqueue(OutboxChannel::Webhook, url, &all)?;
+    }
+
+    for notifier in notifiers_from_config()? {
+        let matching = releases
+            .iter()
+            .filter(|release| notifier.filter().matches(release))
+            .map(|(release, _, _)| release)
+            .collect::<Vec<_>>();
+        num_queued += OutboxBmc::enqueue(OutboxChannel::Chat, &notifier.key(), &matching)?;
+    }
+
+    info!("Queued {num_queued} notifications");
+    Ok(())
+}
+
+/// Sends the notifications waiting in the notification outbox. The releases
+/// of a day are sent to each recipient in a single message.
+///
+/// Notifications are marked as sent once their message is delivered, so
+/// they are not sent again when the job reruns or the server restarts. Only
+/// a crash between delivering a message and marking it as sent sends it
+/// twice, except to Matrix rooms, which ignore a message they already
+/// received. Failed notifications are retried on the next run, and given up
+/// on after [`MAX_NOTIFICATION_ATTEMPTS`] attempts, or right away when the
+/// recipient or the release is gone. A release moved to another day since
+/// it was queued is sent with the releases of its new day instead.
+///
+/// [`MAX_NOTIFICATION_ATTEMPTS`]: crate::model::MAX_NOTIFICATION_ATTEMPTS
+pub async fn process_outbox() -> Result<()> {
+    // The daily job and the periodic retries must not send the same
+    // notifications at the same time.
+    static PROCESSING: futures::lock::Mutex<()> = futures::lock::Mutex::new(());
+    let _guard = PROCESSING.lock().await;
+
+    let today = OffsetDateTime::now_utc().date();
+    let entries = OutboxBmc::get_pending(today)?;
+    if entries.is_empty() {
+        return Ok(());
+    }
+    info!("Sending {} pending notifications", entries.len());
+
+    let mailer = Mailer::from_config()?;
+    let dispatcher = Dispatcher::from_config()?;
+    let notifiers = notifiers_from_config()?;
+    let mut days: HashMap<String, Vec<ReleaseWithArtists>> = HashMap::new();
+    // The dates of the releases of the year by key, loaded once a queued
+    // release is missing from its day.
+    let mut dates: Option<HashMap<String, String>> = None;
+
+    for group in entries.chunk_by(|a, b| {
+        (&a.channel, &a.recipient, &a.release_date) == (&b.channel, &b.recipient, &b.release_date)
+    }) {
+        let entry = &group[0];
+        if !days.contains_key(&entry.release_date) {
+            days.insert(
+                entry.release_date.clone(),
+                Release::parse_date(&entry.release_date)
+                    .map(CalendarBmc::get_day)
+                    .transpose()?
+                    .unwrap_or_default(),
+            );
+        }
+        let day = &days[&entry.release_date];
+
+        let mut releases = Vec::new();
+        let mut ids = Vec::new();
+        let mut gone_ids = Vec::new();
+        for entry in group {
+            match day
+                .iter()
+                .find(|(release, _, _)| release.notification_key() == entry.release_key)
+            {
+                Some(release) => {
+                    releases.push(release);
+                    ids.push(entry.id);
+                }
+                None => {
+                    if dates.is_none() {
+                        dates = Some(
+                            year_releases(today.year())?
+                                .into_iter()
+                                .map(|(release, _, _)| {
+                                    (release.notification_key(), release.date_string())
+                                })
+                                .collect(),
+                        );
+                    }
+                    match dates
+                        .as_ref()
+                        .and_then(|dates| dates.get(&entry.release_key))
+                    {
+                        Some(date) => {
+                            info!(
+                                "Moving the notification {} of {} to {date}",
+                                entry.id, entry.release_key
+                            );
+                            OutboxBmc::reschedule(entry.id, date)?;
+                        }
+                        None => gone_ids.push(entry.id),
+                    }
+                }
+            }
+        }
+        if !gone_ids.is_empty() {
+            OutboxBmc::record_failure(&gone_ids, "the release is no longer in the calendar", true)?;
+        }
+        if releases.is_empty() {
+            continue;
+        }
+
+        let result = send_notification(
+            entry,
+            &releases,
+            &ids,
+            mailer.as_ref(),
+            &dispatcher,
+            &notifiers,
+        )
+        .await;
+        match result {
+            Ok(()) => OutboxBmc::mark_sent(&ids)?,
+            Err(err) => {
+                error!(
+                    "Error notifying {} {} of {} releases: {err}",
+                    entry.channel,
+                    entry.recipient,
+                    releases.len()
+                );
+                OutboxBmc::record_failure(&ids, &err.to_string(), err.is_permanent())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Why a notification was not sent.
+enum NotificationError {
+    /// The recipient is gone, so the notification is not retried.
+    Gone(&'static str),
+    /// Sending failed, possibly for a while only.
+    Failed(String),
+}
+
+impl NotificationError {
+    fn is_permanent(&self) -> bool {
+        matches!(self, NotificationError::Gone(_))
+    }
+}
+
+impl fmt::Display for NotificationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotificationError::Gone(reason) => write!(f, "{reason}"),
+            NotificationError::Failed(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+/// Sends the releases of a day to the recipient of the outbox entry. The
+/// `ids` are those of the entries of the releases.
+async fn send_notification(
+    entry: &OutboxEntry,
+    releases: &[&ReleaseWithArtists],
+    ids: &[i32],
+    mailer: Option<&Mailer>,
+    dispatcher: &Dispatcher,
+    notifiers: &[Box<dyn Notifier>],
+) -> core::result::Result<(), NotificationError> {
+    let failed = |err: crate::error::Error| NotificationError::Failed(err.to_string());
+    let (release, _, _) = releases[0];
+    let date = release
+        .date()
+        .map(|date| format!("{} {}, {}", date.month(), date.day(), date.year()))
+        .unwrap_or_else(|| release.date_string());
+
+    match entry.channel() {
+        Some(OutboxChannel::Email) => {
+            let subscriber = match entry.recipient.parse() {
+                Ok(id) => SubscriberBmc::get(id).map_err(failed)?,
+                Err(_) => None,
+            };
+            let Some(subscriber) = subscriber.filter(|subscriber| subscriber.is_confirmed()) else {
+                return Err(NotificationError::Gone("the subscriber unsubscribed"));
+            };
+            let Some(mailer) = mailer else {
+                return Err(NotificationError::Failed(
+                    "SMTP_HOST is not set".to_string(),
+                ));
+            };
+
+            let email = templates::emails::daily_releases(&subscriber, &date, releases);
+            mailer.send(email).await.map_err(failed)
+        }
+        Some(OutboxChannel::Webhook) => {
+            let event =
+                webhooks::daily_releases(&release.date_string(), releases).map_err(failed)?;
+            match dispatcher
+                .send(&entry.recipient, &event)
+                .await
+                .map_err(failed)?
+            {
+                Some(delivery) => match delivery.error {
+                    Some(err) => Err(NotificationError::Failed(err)),
+                    None => Ok(()),
+                },
+                None => Err(NotificationError::Gone(
+                    "the webhook is no longer subscribed to daily_releases",
+                )),
+            }
+        }
+        Some(OutboxChannel::Chat) => {
+            let Some(notifier) = notifiers
+                .iter()
+                .find(|notifier| notifier.key() == entry.recipient)
+            else {
+                return Err(NotificationError::Gone(
+                    "the chat channel is no longer configured",
+                ));
+            };
+            info!("Sending {} releases to {}", releases.len(), notifier.name());
+
+            // The releases of a message are marked as sent once it is
+            // delivered, so that a failure only sends the remaining
+            // messages again. The transaction id of a message depends on
+            // its entries only, so that it is the same when sent again.
+            for message in notifier.messages(&date, releases) {
+                let sent = &ids[message.releases.clone()];
+                let txn_id = format!("outbox-{}-{}", sent.first().unwrap_or(&ids[0]), sent.len());
+                notifier.send(&message, &txn_id).await.map_err(failed)?;
+                OutboxBmc::mark_sent(sent).map_err(failed)?;
+            }
+            Ok(())
+        }
+        None => Err(NotificationError::Gone("the channel is unknown")),
+    }
+}
+
+/// Retrieves every release of the year.
+fn year_releases(year: i32) -> Result<Vec<ReleaseWithArtists>> {
+    let start = Date::from_calendar_date(year, Month::January, 1)?;
+    let end = Date::from_calendar_date(year, Month::December, 31)?;
+    CalendarBmc::get_between(start, end, &ReleaseFilter::default())
+}
+
+/// Scrapes an archived snapshot of a Wikipedia page again and prints the
+/// releases found, one per line, to reproduce a past scrape.
+pub fn replay_snapshot(id: i32) -> Result<()> {
+    let snapshot = SnapshotBmc::get(id)?;
+    let calendar = wiki::extract_snapshot(&snapshot.body)?;
+
+    let mut releases = calendar
+        .data
+        .iter()
+        .flat_map(|(month, days)| {
+            days.iter().flat_map(move |(day, releases)| {
+                releases
+                    .iter()
+                    .map(move |release| (*month as u8, *day, release))
+            })
+        })
+        .collect::<Vec<_>>();
+    releases.sort_by_key(|(month, day, _)| (*month, *day));
+
+    for (month, day, release) in releases {
+        println!(
+            "{}-{month:02}-{day:02}\t{}\t{}",
+            calendar.year, release.artist, release.album
+        );
+    }
     Ok(())
 }
@@ -8,8 +8,12 @@
 mod calendar;
 mod config;
 mod error;
+mod imports;
+mod mailer;
+mod notifiers;
 mod scraper;
 mod support;
+mod webhooks;
 
 pub mod jobs;
 pub mod model;
@@ -0,0 +1,256 @@
+//! The `mailer` module sends emails through the SMTP server of the configuration.
+
+use lettre::{
+    message::{
+        header::{Header, HeaderName, HeaderValue},
+        Mailbox, MultiPart,
+    },
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+
+use crate::{
+    config::config,
+    error::{Error, Result},
+};
+
+/// An email with both an HTML and a plain text body.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub html: String,
+    pub text: String,
+    /// The address replies go to instead of the sender.
+    pub reply_to: Option<String>,
+    /// The URL unsubscribing the recipient, advertised in the `List-Unsubscribe` header.
+    pub unsubscribe_url: Option<String>,
+}
+
+/// How the connection to the SMTP server is secured.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SmtpTls {
+    /// Upgrades a plain connection with `STARTTLS`, usually on port 587.
+    StartTls,
+    /// Connects with TLS, usually on port 465.
+    Tls,
+    /// Sends everything in clear, e.g. to a local SMTP sink.
+    None,
+}
+
+/// Sends emails through an SMTP server.
+pub struct Mailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl Mailer {
+    /// Creates the mailer of the SMTP server at `SMTP_HOST`, or `None` when
+    /// no server is configured.
+    pub fn from_config() -> Result<Option<Self>> {
+        let Some(host) = &config().SMTP_HOST else {
+            return Ok(None);
+        };
+
+        let tls = match config().SMTP_TLS.as_str() {
+            "starttls" => SmtpTls::StartTls,
+            "tls" => SmtpTls::Tls,
+            "none" => SmtpTls::None,
+            _ => return Err(Error::InvalidEnv("SMTP_TLS")),
+        };
+        let credentials = config()
+            .SMTP_USERNAME
+            .clone()
+            .map(|username| (username, config().SMTP_PASSWORD.clone().unwrap_or_default()));
+
+        Self::new(
+            host,
+            config().SMTP_PORT,
+            tls,
+            credentials,
+            &config().SMTP_FROM,
+        )
+        .map(Some)
+    }
+
+    pub fn new(
+        host: &str,
+        port: u16,
+        tls: SmtpTls,
+        credentials: Option<(String, String)>,
+        from: &str,
+    ) -> Result<Self> {
+        let mut builder = match tls {
+            SmtpTls::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)?,
+            SmtpTls::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(host)?,
+            SmtpTls::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host),
+        }
+        .port(port);
+        if let Some((username, password)) = credentials {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+
+        Ok(Self {
+            transport: builder.build(),
+            from: from.parse()?,
+        })
+    }
+
+    pub async fn send(&self, email: Email) -> Result<()> {
+        let mut builder = Message::builder()
+            .from(self.from.clone())
+            .to(email.to.parse()?)
+            .subject(email.subject);
+        if let Some(reply_to) = email.reply_to {
+            builder = builder.reply_to(reply_to.parse()?);
+        }
+        if let Some(url) = email.unsubscribe_url {
+            builder = builder
+                .header(ListUnsubscribe(url))
+                .header(ListUnsubscribePost);
+        }
+
+        let message =
+            builder.multipart(MultiPart::alternative_plain_html(email.text, email.html))?;
+        self.transport.send(message).await?;
+        Ok(())
+    }
+}
+
+/// The `List-Unsubscribe` header, which lets mail clients show an unsubscribe button.
+#[derive(Clone)]
+struct ListUnsubscribe(String);
+
+impl Header for ListUnsubscribe {
+    fn name() -> HeaderName {
+        HeaderName::new_from_ascii_str("List-Unsubscribe")
+    }
+
+    fn parse(s: &str) -> core::result::Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(Self(s.trim_matches(['<', '>']).to_string()))
+    }
+
+    fn display(&self) -> HeaderValue {
+        HeaderValue::new(Self::name(), format!("<{}>", self.0))
+    }
+}
+
+/// The `List-Unsubscribe-Post` header, which tells mail clients that the
+/// `List-Unsubscribe` URL unsubscribes with a single POST request (RFC 8058).
+#[derive(Clone)]
+struct ListUnsubscribePost;
+
+impl Header for ListUnsubscribePost {
+    fn name() -> HeaderName {
+        HeaderName::new_from_ascii_str("List-Unsubscribe-Post")
+    }
+
+    fn parse(_: &str) -> core::result::Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(Self)
+    }
+
+    fn display(&self) -> HeaderValue {
+        HeaderValue::new(Self::name(), "List-Unsubscribe=One-Click".to_string())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    use std::sync::{Arc, Mutex};
+
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+        net::TcpListener,
+    };
+
+    type Result<T> = core::result::Result<T, Box<dyn std::error::Error>>;
+
+    /// Runs an SMTP sink on a local port, which accepts every message.
+    /// Returns its port and the data of the messages received.
+    pub async fn serve_smtp_sink() -> Result<(u16, Arc<Mutex<Vec<String>>>)> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let port = listener.local_addr()?.port();
+        let messages = Arc::new(Mutex::new(Vec::new()));
+
+        let received = messages.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let received = received.clone();
+                tokio::spawn(async move {
+                    let (reader, mut writer) = stream.into_split();
+                    let mut lines = BufReader::new(reader).lines();
+                    writer.write_all(b"220 localhost sink\r\n").await?;
+
+                    let mut data: Option<String> = None;
+                    while let Some(line) = lines.next_line().await? {
+                        if let Some(message) = data.as_mut() {
+                            if line == "." {
+                                received.lock().unwrap().push(data.take().unwrap());
+                                writer.write_all(b"250 OK\r\n").await?;
+                            } else {
+                                message.push_str(&line);
+                                message.push('\n');
+                            }
+                            continue;
+                        }
+
+                        let command = line.to_uppercase();
+                        let reply: &[u8] = if command.starts_with("EHLO") {
+                            b"250 localhost\r\n"
+                        } else if command.starts_with("DATA") {
+                            data = Some(String::new());
+                            b"354 End data with <CR><LF>.<CR><LF>\r\n"
+                        } else if command.starts_with("QUIT") {
+                            writer.write_all(b"221 Bye\r\n").await?;
+                            break;
+                        } else {
+                            b"250 OK\r\n"
+                        };
+                        writer.write_all(reply).await?;
+                    }
+                    Ok::<_, std::io::Error>(())
+                });
+            }
+        });
+
+        Ok((port, messages))
+    }
+
+    #[tokio::test]
+    async fn test_send_ok() -> Result<()> {
+        let (port, messages) = serve_smtp_sink().await?;
+        let mailer = Mailer::new(
+            "127.0.0.1",
+            port,
+            SmtpTls::None,
+            None,
+            "Heavy Metal Notifier <noreply@localhost>",
+        )?;
+
+        mailer
+            .send(Email {
+                to: "metalhead@example.com".to_string(),
+                subject: "New releases".to_string(),
+                html: "<p>Wintersun - Time II</p>".to_string(),
+                text: "Wintersun - Time II".to_string(),
+                reply_to: None,
+                unsubscribe_url: Some("http://localhost/subscribers/unsubscribe/abc".to_string()),
+            })
+            .await?;
+
+        let messages = messages.lock().unwrap();
+        pretty_assertions::assert_eq!(messages.len(), 1);
+        let message = &messages[0];
+        assert!(message.contains("To: metalhead@example.com"));
+        assert!(message.contains("Subject: New releases"));
+        assert!(
+            message.contains("List-Unsubscribe: <http://localhost/subscribers/unsubscribe/abc>")
+        );
+        assert!(message.contains("List-Unsubscribe-Post: List-Unsubscribe=One-Click"));
+        assert!(message.contains("Content-Type: text/plain"));
+        assert!(message.contains("<p>Wintersun - Time II</p>"));
+        Ok(())
+    }
+}
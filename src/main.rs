@@ -1,7 +1,5 @@
-mod error;
-
 use dotenv::dotenv;
-use std::env;
+use std::{env, net::SocketAddr};
 use tokio::{net::TcpListener, signal};
 use tokio_cron_scheduler::{Job, JobScheduler};
 use tracing::{error, info};
@@ -13,6 +11,22 @@ async fn main() -> Result<()> {
     dotenv().ok();
     tracing_subscriber::fmt::init();
 
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    // The flags override the configuration, which is read on first use.
+    if let Some(dir) = flag_value(&args, "--offline-dir") {
+        env::set_var("OFFLINE_DIR", dir);
+    }
+    if let Some(dir) = flag_value(&args, "--record-dir") {
+        env::set_var("RECORD_DIR", dir);
+    }
+    if let Some(id) = flag_value(&args, "--replay-snapshot") {
+        let Ok(id) = id.parse() else {
+            eprintln!("Invalid snapshot id `{id}`");
+            std::process::exit(2);
+        };
+        return jobs::replay_snapshot(id);
+    }
+
     info!("Fetching and storing calendar");
     jobs::update_calendar().await?;
 
@@ -32,6 +46,46 @@ async fn main() -> Result<()> {
             })?,
         )
         .await?;
+    sched
+        .add(
+            // At 3:00 AM, every day
+            Job::new_async("0 0 3 * * *", |_uuid, _l| {
+                Box::pin(async move {
+                    info!("Updating Bandcamp links");
+                    if let Err(err) = jobs::update_bandcamp_links().await {
+                        error!("Error updating Bandcamp links: {err}")
+                    };
+                    info!("Bandcamp links updated")
+                })
+            })?,
+        )
+        .await?;
+    sched
+        .add(
+            // At 8:00 AM, every day
+            Job::new_async("0 0 8 * * *", |_uuid, _l| {
+                Box::pin(async move {
+                    info!("Sending daily notifications");
+                    if let Err(err) = jobs::send_daily_notifications().await {
+                        error!("Error sending daily notifications: {err}")
+                    };
+                    info!("Daily notifications sent")
+                })
+            })?,
+        )
+        .await?;
+    sched
+        .add(
+            // Every 30 minutes, to retry the failed notifications
+            Job::new_async("0 15,45 * * * *", |_uuid, _l| {
+                Box::pin(async move {
+                    if let Err(err) = jobs::process_outbox().await {
+                        error!("Error sending pending notifications: {err}")
+                    };
+                })
+            })?,
+        )
+        .await?;
     sched.shutdown_on_ctrl_c();
     sched.start().await?;
 
@@ -41,7 +95,12 @@ async fn main() -> Result<()> {
     let listener = TcpListener::bind(&addr).await?;
     info!("Serving at http://{addr}");
 
-    axum::serve(listener, routes().await.unwrap())
+    // The address of the client is used to rate limit the form submissions.
+    let app = routes()
+        .await
+        .unwrap()
+        .into_make_service_with_connect_info::<SocketAddr>();
+    axum::serve(listener, app)
         .with_graceful_shutdown(shutdown_signal())
         .await
         .unwrap();
@@ -49,6 +108,14 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+/// The value following a flag in the command-line arguments, e.g. `--offline-dir fixtures`.
+fn flag_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|arg| arg == flag)
+        .and_then(|i| args.get(i + 1))
+        .map(String::as_str)
+}
+
 async fn shutdown_signal() {
     let ctrl_c = async {
         signal::ctrl_c()
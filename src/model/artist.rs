@@ -0,0 +1,204 @@
+use diesel::{prelude::*, sql_types::Text};
+
+use crate::calendar::wikipedia_url;
+use crate::error::{Error, Result};
+
+use super::{
+    calendar::{Release, ReleaseWithArtists},
+    link::LinkBmc,
+    ModelManager,
+};
+
+define_sql_function!(fn lower(text: Text) -> Text);
+
+/// This struct corresponds to a row in the `artists`
+/// table in the database. Each artist has a unique `id` and
+/// a `name`.
+///
+/// Artists sharing the same name are told apart by the title
+/// of their Wikipedia page.
+#[derive(Queryable, Identifiable, Selectable, Clone, Debug, PartialEq)]
+#[diesel(table_name = super::schema::artists)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Artist {
+    pub id: i32,
+    pub name: String,
+    /// The title of the artist's Wikipedia page, or an empty string when unknown.
+    pub wikipedia: String,
+}
+
+impl Artist {
+    /// The URL of the artist's Wikipedia page, if known.
+    pub fn wikipedia_url(&self) -> Option<String> {
+        if self.wikipedia.is_empty() {
+            None
+        } else {
+            Some(wikipedia_url(&self.wikipedia))
+        }
+    }
+}
+
+/// Associates an artist with one of the releases they take part in.
+///
+/// This struct corresponds to a row in the `release_artists` table,
+/// which lets splits and collaborations have many artists.
+#[derive(Queryable, Selectable, Insertable, Debug, PartialEq)]
+#[diesel(table_name = super::schema::release_artists)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct ReleaseArtist {
+    pub release_id: i32,
+    pub artist_id: i32,
+    /// The order in which the artist is credited, starting at 0.
+    pub position: i32,
+}
+
+/// `ArtistBmc` is a backend model controller responsible for
+/// artist-related operations.
+pub struct ArtistBmc;
+
+impl ArtistBmc {
+    /// Retrieves the artist with the given `id`.
+    pub fn get(id: i32) -> Result<Artist> {
+        use super::schema::artists;
+
+        let mm = &mut ModelManager::new();
+        let conn = &mut mm.conn;
+
+        artists::table
+            .find(id)
+            .select(Artist::as_select())
+            .first(conn)
+            .optional()?
+            .ok_or(Error::EntityNotFound {
+                entity: "artist",
+                id: id as i64,
+            })
+    }
+
+    /// Retrieves every artist, ordered by name.
+    pub fn get_all() -> Result<Vec<Artist>> {
+        use super::schema::artists;
+
+        let mm = &mut ModelManager::new();
+        let conn = &mut mm.conn;
+
+        let artists = artists::table
+            .order((artists::name, artists::id))
+            .select(Artist::as_select())
+            .load(conn)?;
+
+        Ok(artists)
+    }
+
+    /// Retrieves the artists with the name, regardless of case.
+    pub fn get_by_name(name: &str) -> Result<Vec<Artist>> {
+        use super::schema::artists;
+
+        let mm = &mut ModelManager::new();
+        let conn = &mut mm.conn;
+
+        let artists = artists::table
+            .filter(lower(artists::name).eq(lower(name)))
+            .order(artists::id)
+            .select(Artist::as_select())
+            .load(conn)?;
+
+        Ok(artists)
+    }
+
+    /// Retrieves every release the artist takes part in, newest first,
+    /// along with all of the release's participants and links.
+    pub fn get_releases(id: i32) -> Result<Vec<ReleaseWithArtists>> {
+        use super::schema::*;
+
+        let mm = &mut ModelManager::new();
+        let conn = &mut mm.conn;
+
+        let releases = releases::table
+            .inner_join(release_artists::table)
+            .filter(release_artists::artist_id.eq(id))
+            .order((
+                releases::year.desc(),
+                releases::month.desc(),
+                releases::day.desc(),
+            ))
+            .select(Release::as_select())
+            .load(conn)?;
+
+        let participants = ArtistBmc::get_participants(conn, &releases)?;
+        let links = LinkBmc::get_for_releases(conn, &releases)?;
+        Ok(releases
+            .into_iter()
+            .zip(participants)
+            .zip(links)
+            .map(|((release, artists), links)| (release, artists, links))
+            .collect())
+    }
+
+    /// Retrieves the participants of each release, in credit order.
+    ///
+    /// The returned vector is parallel to `releases`.
+    pub(in crate::model) fn get_participants(
+        conn: &mut SqliteConnection,
+        releases: &[Release],
+    ) -> Result<Vec<Vec<Artist>>> {
+        use super::schema::*;
+
+        let release_ids = releases.iter().map(|r| r.id).collect::<Vec<_>>();
+        let rows: Vec<(i32, Artist)> = release_artists::table
+            .inner_join(artists::table)
+            .filter(release_artists::release_id.eq_any(release_ids))
+            .order(release_artists::position)
+            .select((release_artists::release_id, Artist::as_select()))
+            .load(conn)?;
+
+        Ok(releases
+            .iter()
+            .map(|release| {
+                rows.iter()
+                    .filter(|(release_id, _)| *release_id == release.id)
+                    .map(|(_, artist)| artist.clone())
+                    .collect()
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use super::super::schema::artists;
+
+    type Result<T> = core::result::Result<T, Box<dyn std::error::Error>>;
+
+    #[test]
+    fn test_get_by_name_ok() -> Result<()> {
+        let mm = &mut ModelManager::new();
+        diesel::insert_into(artists::table)
+            .values(&vec![
+                (
+                    artists::name.eq("Nightfall"),
+                    artists::wikipedia.eq("Nightfall (band)"),
+                ),
+                (
+                    artists::name.eq("Nightfall"),
+                    artists::wikipedia.eq("Nightfall (Greek band)"),
+                ),
+                (artists::name.eq("Nightfallen"), artists::wikipedia.eq("")),
+            ])
+            .execute(&mut mm.conn)?;
+
+        let artists = ArtistBmc::get_by_name("NIGHTFALL")?;
+
+        pretty_assertions::assert_eq!(
+            artists
+                .iter()
+                .map(|artist| artist.wikipedia.as_str())
+                .collect::<Vec<_>>(),
+            vec!["Nightfall (band)", "Nightfall (Greek band)"]
+        );
+        pretty_assertions::assert_eq!(ArtistBmc::get_by_name("Night")?, Vec::new());
+        Ok(())
+    }
+}
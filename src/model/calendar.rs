@@ -1,47 +1,14 @@
 use diesel::prelude::*;
-use time::OffsetDateTime;
+use time::{Date, OffsetDateTime};
 
-use crate::calendar::Calendar;
+use crate::calendar::{wikipedia_url, Calendar, Participant, ReleaseType};
+use crate::config::config;
 use crate::error::{Error, Result};
 
+use super::artist::{Artist, ArtistBmc, ReleaseArtist};
+use super::link::{LinkBmc, ReleaseLink};
 use super::ModelManager;
 
-/// This struct corresponds to a row in the `artists` 
-/// table in the database. Each artist has a unique `id` and 
-/// a `name`.
-#[derive(Queryable, Identifiable, Selectable, Debug, PartialEq)]
-#[diesel(table_name = super::schema::artists)]
-#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
-pub struct Artist {
-    pub id: i32,
-    pub name: String,
-}
-
-
-/// Represents web links associated with an artist.
-/// 
-/// This struct corresponds to a row in the `links` table, 
-/// which stores external links related to the artist 
-/// (such as YouTube and Bandcamp).
-#[derive(Queryable, Selectable, Identifiable, Associations, Debug, PartialEq)]
-#[diesel(belongs_to(Artist))]
-#[diesel(table_name = super::schema::links)]
-#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
-pub struct Link {
-    pub id: i32,
-    pub artist_id: i32,
-    pub url_youtube: String,
-    pub url_bandcamp: Option<String>,
-}
-
-#[derive(Insertable)]
-#[diesel(table_name = super::schema::links)]
-struct LinkForInsert {
-    artist_id: i32,
-    url_youtube: String,
-    url_bandcamp: Option<String>,
-}
-
 /// Represents a music release by an artist.
 ///
 /// This struct corresponds to a row in the `releases` table.
@@ -60,6 +27,107 @@ pub struct Release {
 
     pub artist_id: i32,
     pub album: String,
+    /// The [`ReleaseType`] identifier, e.g. `album` or `ep`.
+    pub release_type: String,
+    /// The artist as credited on Wikipedia, e.g. "Artist A / Artist B".
+    pub artist_credit: String,
+    /// The title of the album's Wikipedia page.
+    pub wikipedia_album: Option<String>,
+    /// The URL of the source Wikipedia cites for the release.
+    pub source_url: Option<String>,
+    /// The comma-separated names of the sources listing the release.
+    pub providers: String,
+    /// The MBID of the release's MusicBrainz release group.
+    pub musicbrainz_release_group_id: Option<String>,
+    /// The MBID of the MusicBrainz release that matched the album.
+    pub musicbrainz_release_id: Option<String>,
+    pub track_count: Option<i32>,
+    /// The file name of the cover art's thumbnail in the covers folder.
+    pub cover: Option<String>,
+    /// The label that published the release, according to MusicBrainz.
+    pub label: Option<String>,
+    /// The genre of the artists, e.g. "Melodic Death Metal", if known.
+    pub genre: Option<String>,
+}
+
+impl Release {
+    /// Parses the stored `release_type` column, falling back to an album.
+    pub fn kind(&self) -> ReleaseType {
+        self.release_type.parse().unwrap_or_default()
+    }
+
+    /// The release date, if the year, month and day make a valid date.
+    pub fn date(&self) -> Option<Date> {
+        calendar_date(self.year, self.month, self.day)
+    }
+
+    /// The release date as `YYYY-MM-DD`.
+    pub fn date_string(&self) -> String {
+        format!("{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+
+    /// Parses a date given as `YYYY-MM-DD`, the format of
+    /// [`date_string`](Self::date_string).
+    pub fn parse_date(date: &str) -> Option<Date> {
+        let parts = date
+            .splitn(3, '-')
+            .map(str::parse::<i32>)
+            .collect::<core::result::Result<Vec<_>, _>>()
+            .ok()?;
+        match parts[..] {
+            [year, month, day] => calendar_date(year, month, day),
+            _ => None,
+        }
+    }
+
+    /// Identifies the release across the updates of the calendar, which
+    /// recreate the releases of the year under new ids.
+    pub fn notification_key(&self) -> String {
+        format!(
+            "{} - {}",
+            self.artist_credit.to_lowercase(),
+            self.album.to_lowercase()
+        )
+    }
+
+    /// The URL of the album's Wikipedia page, if known.
+    pub fn wikipedia_url(&self) -> Option<String> {
+        self.wikipedia_album.as_deref().map(wikipedia_url)
+    }
+
+    /// The URL the thumbnail of the cover art is served at, if any.
+    pub fn cover_url(&self) -> Option<String> {
+        self.cover
+            .as_ref()
+            .map(|cover| format!("{}/covers/{cover}", config().BASE_URL))
+    }
+
+    /// The URL of the release group's MusicBrainz page, if known.
+    pub fn musicbrainz_url(&self) -> Option<String> {
+        self.musicbrainz_release_group_id
+            .as_ref()
+            .map(|id| format!("https://musicbrainz.org/release-group/{id}"))
+    }
+}
+
+/// A release along with its artists and its links.
+pub type ReleaseWithArtists = (Release, Vec<Artist>, Vec<ReleaseLink>);
+
+/// Criteria used to narrow down the releases fetched from the database.
+///
+/// An empty filter matches every release.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ReleaseFilter {
+    /// Only keep releases of these types.
+    pub types: Vec<ReleaseType>,
+    /// Only keep releases in which one of these artists takes part.
+    pub artist_ids: Vec<i32>,
+}
+
+impl ReleaseFilter {
+    pub fn is_empty(&self) -> bool {
+        self.types.is_empty() && self.artist_ids.is_empty()
+    }
 }
 
 /// Represents a new release to be inserted into the database.
@@ -75,6 +143,17 @@ struct ReleaseForInsert {
     pub day: i32,
     pub artist_id: i32,
     pub album: String,
+    pub release_type: String,
+    pub artist_credit: String,
+    pub wikipedia_album: Option<String>,
+    pub source_url: Option<String>,
+    pub providers: String,
+    pub musicbrainz_release_group_id: Option<String>,
+    pub musicbrainz_release_id: Option<String>,
+    pub track_count: Option<i32>,
+    pub cover: Option<String>,
+    pub label: Option<String>,
+    pub genre: Option<String>,
 }
 
 /// `CalendarBmc` is a backend model controller responsible for 
@@ -89,7 +168,7 @@ impl CalendarBmc {
     ///
     /// This method inserts new releases into the `releases` table
     /// or updates existing ones based on the calendar data. It 
-    /// handles linking artists and adding external links (YouTube, Bandcamp).
+    /// handles linking artists and storing each release's external links (YouTube, Bandcamp).
     pub fn create_or_update(calendar: Calendar) -> Result<()> {
         use super::schema::*;
 
@@ -102,53 +181,53 @@ impl CalendarBmc {
             for (month, data) in calendar.data.iter() {
                 for (day, releases) in data.iter() {
                     for release in releases.iter() {
-                        let artist_name = release.artist.clone();
-
-                        let artist_id: i32 = match diesel::insert_or_ignore_into(artists::table)
-                            .values(artists::name.eq(&artist_name))
-                            .returning(artists::id)
-                            .get_result(conn)
-                        {
-                            Ok(id) => id,
-                            Err(_) => artists::table
-                                .filter(artists::name.eq(&artist_name))
-                                .limit(1)
-                                .select(artists::id)
-                                .get_result(conn)?,
-                        };
-
-                        if CalendarBmc::get_links(conn, &artist_name).is_none() {
-                            let mut link_for_insert = LinkForInsert {
-                                artist_id,
-                                url_youtube: String::new(),
-                                url_bandcamp: None,
-                            };
-
-                            for link in release.links.iter() {
-                                match link {
-                                    crate::calendar::Link::Bandcamp(url) => {
-                                        link_for_insert.url_bandcamp = Some(url.to_string())
-                                    }
-                                    crate::calendar::Link::Youtube(url) => {
-                                        link_for_insert.url_youtube = url.to_string()
-                                    }
-                                };
-                            }
-
-                            diesel::insert_or_ignore_into(links::table)
-                                .values(&link_for_insert)
-                                .execute(conn)?;
+                        let mut artist_ids = Vec::with_capacity(release.artists.len());
+                        for participant in release.artists.iter() {
+                            artist_ids.push(CalendarBmc::get_or_create_artist(conn, participant)?);
                         }
+                        let artist_id = artist_ids[0];
 
-                        diesel::insert_into(releases::table)
+                        let release_id: i32 = diesel::insert_into(releases::table)
                             .values(&ReleaseForInsert {
                                 year: calendar.year,
                                 month: *month as i32,
                                 day: *day as i32,
                                 artist_id,
                                 album: release.album.clone(),
+                                release_type: release.release_type.as_str().to_string(),
+                                artist_credit: release.artist.clone(),
+                                wikipedia_album: release.wikipedia_album.clone(),
+                                source_url: release.source_url.clone(),
+                                providers: release.providers.join(","),
+                                musicbrainz_release_group_id: release
+                                    .musicbrainz
+                                    .as_ref()
+                                    .map(|mb| mb.release_group_id.clone()),
+                                musicbrainz_release_id: release
+                                    .musicbrainz
+                                    .as_ref()
+                                    .map(|mb| mb.release_id.clone()),
+                                track_count: release
+                                    .musicbrainz
+                                    .as_ref()
+                                    .and_then(|mb| mb.track_count),
+                                cover: release.cover.clone(),
+                                label: release.musicbrainz.as_ref().and_then(|mb| mb.label.clone()),
+                                genre: release.genre.clone(),
                             })
-                            .execute(conn)?;
+                            .returning(releases::id)
+                            .get_result(conn)?;
+                        LinkBmc::create_for_release(conn, release_id, release)?;
+
+                        for (position, artist_id) in artist_ids.into_iter().enumerate() {
+                            diesel::insert_or_ignore_into(release_artists::table)
+                                .values(&ReleaseArtist {
+                                    release_id,
+                                    artist_id,
+                                    position: position as i32,
+                                })
+                                .execute(conn)?;
+                        }
                     }
                 }
             }
@@ -157,29 +236,54 @@ impl CalendarBmc {
         })
     }
 
-    /// Retrieves links associated with an artist.
+    /// Retrieves the id of the artist, creating the artist if needed.
     ///
-    /// This method queries the `links` table to fetch YouTube 
-    /// and Bandcamp URLs associated with a given artist.
-    pub fn get_links(conn: &mut SqliteConnection, artist: impl Into<String>) -> Option<Vec<Link>> {
-        use super::schema::*;
+    /// Artists are matched on their Wikipedia page when it is known, so that
+    /// two bands sharing a name are kept apart. An artist first stored
+    /// without a page is given the page the first time it is seen with one.
+    fn get_or_create_artist(conn: &mut SqliteConnection, participant: &Participant) -> Result<i32> {
+        use super::schema::artists;
 
-        let links: core::result::Result<Vec<Link>, _> = links::table
-            .inner_join(artists::table)
-            .filter(artists::name.eq(artist.into()))
-            .select(Link::as_select())
-            .load(conn);
-
-        match links {
-            Ok(vec) => {
-                if vec.is_empty() {
-                    None
-                } else {
-                    Some(vec)
-                }
+        let name = participant.name.as_str();
+        let wikipedia = participant.wikipedia.as_deref().unwrap_or_default();
+
+        if !wikipedia.is_empty() {
+            let id: Option<i32> = artists::table
+                .filter(artists::wikipedia.eq(wikipedia))
+                .select(artists::id)
+                .first(conn)
+                .optional()?;
+            if let Some(id) = id {
+                return Ok(id);
+            }
+
+            let id: Option<i32> = diesel::update(
+                artists::table.filter(artists::name.eq(name).and(artists::wikipedia.eq(""))),
+            )
+            .set(artists::wikipedia.eq(wikipedia))
+            .returning(artists::id)
+            .get_result(conn)
+            .optional()?;
+            if let Some(id) = id {
+                return Ok(id);
+            }
+        } else {
+            let id: Option<i32> = artists::table
+                .filter(artists::name.eq(name))
+                .order(artists::wikipedia)
+                .select(artists::id)
+                .first(conn)
+                .optional()?;
+            if let Some(id) = id {
+                return Ok(id);
             }
-            Err(_) => None,
         }
+
+        let id = diesel::insert_into(artists::table)
+            .values((artists::name.eq(name), artists::wikipedia.eq(wikipedia)))
+            .returning(artists::id)
+            .get_result(conn)?;
+        Ok(id)
     }
 
     /// Retrieves releases for the current date.
@@ -187,32 +291,76 @@ impl CalendarBmc {
     /// This method fetches releases from the `releases` table 
     /// that match the current date (year, month, and day) and 
     /// joins the associated artist and links (YouTube, Bandcamp).
-    pub fn get() -> Result<Vec<(Release, Artist, (String, Option<String>))>> {
+    pub fn get() -> Result<Vec<ReleaseWithArtists>> {
+        let today = OffsetDateTime::now_utc().date();
+        CalendarBmc::get_between(today, today, &ReleaseFilter::default())
+    }
+
+    /// Retrieves every release of the date.
+    pub fn get_day(date: Date) -> Result<Vec<ReleaseWithArtists>> {
+        Self::get_between(date, date, &ReleaseFilter::default())
+    }
+
+    /// Retrieves the releases between two dates, inclusively, that match the filter.
+    ///
+    /// The releases are ordered by date, then by artist name.
+    pub fn get_between(
+        start: Date,
+        end: Date,
+        filter: &ReleaseFilter,
+    ) -> Result<Vec<ReleaseWithArtists>> {
         use super::schema::*;
 
         let mm = &mut ModelManager::new();
         let conn = &mut mm.conn;
 
-        let now = OffsetDateTime::now_utc();
-        let year = now.year();
-        let month = now.month() as i32;
-        let day = now.day() as i32;
-
-        let releases = releases::table
-            .inner_join(artists::table.inner_join(links::table))
-            .filter(
-                releases::year
-                    .eq(year)
-                    .and(releases::month.eq(month))
-                    .and(releases::day.eq(day)),
-            )
-            .select((
-                Release::as_select(),
-                Artist::as_select(),
-                (links::url_youtube, links::url_bandcamp),
+        let date_key =
+            |date: Date| date.year() * 10000 + date.month() as i32 * 100 + date.day() as i32;
+        let release_key = releases::year * 10000 + releases::month * 100 + releases::day;
+
+        let mut query = releases::table
+            .inner_join(artists::table)
+            .filter(release_key.between(date_key(start), date_key(end)))
+            .into_boxed();
+
+        if !filter.types.is_empty() {
+            let types = filter.types.iter().map(|t| t.as_str()).collect::<Vec<_>>();
+            query = query.filter(releases::release_type.eq_any(types));
+        }
+
+        if !filter.artist_ids.is_empty() {
+            query = query.filter(
+                releases::id.eq_any(
+                    release_artists::table
+                        .filter(release_artists::artist_id.eq_any(filter.artist_ids.clone()))
+                        .select(release_artists::release_id),
+                ),
+            );
+        }
+
+        let releases: Vec<Release> = query
+            .order((
+                releases::year,
+                releases::month,
+                releases::day,
+                releases::artist_credit,
             ))
+            .select(Release::as_select())
             .load(conn)?;
 
-        Ok(releases)
+        let participants = ArtistBmc::get_participants(conn, &releases)?;
+        let links = LinkBmc::get_for_releases(conn, &releases)?;
+
+        Ok(releases
+            .into_iter()
+            .zip(participants)
+            .zip(links)
+            .map(|((release, artists), links)| (release, artists, links))
+            .collect())
     }
 }
+
+fn calendar_date(year: i32, month: i32, day: i32) -> Option<Date> {
+    let month = time::Month::try_from(u8::try_from(month).ok()?).ok()?;
+    Date::from_calendar_date(year, month, u8::try_from(day).ok()?).ok()
+}
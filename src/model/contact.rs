@@ -0,0 +1,44 @@
+use diesel::prelude::*;
+use time::OffsetDateTime;
+
+use crate::error::Result;
+
+use super::{schema, ModelManager};
+
+/// `ContactMessage` represents a row in the `contact_messages` table, which
+/// stores the messages sent from the contact form.
+#[derive(Queryable, Identifiable, Selectable, Clone, Debug, PartialEq)]
+#[diesel(table_name = super::schema::contact_messages)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct ContactMessage {
+    pub id: i32,
+    /// The address to reply to.
+    pub email: String,
+    pub message: String,
+    pub created_at: i64,
+}
+
+/// `ContactMessageBmc` is a backend model controller responsible for the
+/// messages sent from the contact form.
+pub struct ContactMessageBmc;
+
+impl ContactMessageBmc {
+    /// Stores a message sent from the contact form.
+    pub fn create(email: &str, message: &str) -> Result<ContactMessage> {
+        use schema::contact_messages;
+
+        let mm = &mut ModelManager::new();
+        let conn = &mut mm.conn;
+
+        let message = diesel::insert_into(contact_messages::table)
+            .values((
+                contact_messages::email.eq(email),
+                contact_messages::message.eq(message),
+                contact_messages::created_at.eq(OffsetDateTime::now_utc().unix_timestamp()),
+            ))
+            .returning(ContactMessage::as_returning())
+            .get_result(conn)?;
+
+        Ok(message)
+    }
+}
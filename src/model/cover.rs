@@ -0,0 +1,48 @@
+use std::collections::HashSet;
+
+use diesel::prelude::*;
+use time::OffsetDateTime;
+
+use crate::error::Result;
+
+use super::{schema, ModelManager};
+
+/// `CoverBmc` is a backend model controller that remembers which releases
+/// have no cover art to be found, so that their pages are not fetched again
+/// on every calendar update.
+pub struct CoverBmc;
+
+impl CoverBmc {
+    /// Retrieves the file names of the thumbnails that could not be found
+    /// since the given time.
+    pub fn get_misses_since(since: OffsetDateTime) -> Result<HashSet<String>> {
+        use schema::cover_misses;
+
+        let mm = &mut ModelManager::new();
+        let conn = &mut mm.conn;
+
+        let misses: Vec<String> = cover_misses::table
+            .filter(cover_misses::checked_at.ge(since.unix_timestamp()))
+            .select(cover_misses::file_name)
+            .load(conn)?;
+
+        Ok(misses.into_iter().collect())
+    }
+
+    /// Remembers that no cover could be found today for the thumbnail.
+    pub fn add_miss(file_name: &str) -> Result<()> {
+        use schema::cover_misses;
+
+        let mm = &mut ModelManager::new();
+        let conn = &mut mm.conn;
+
+        diesel::replace_into(cover_misses::table)
+            .values((
+                cover_misses::file_name.eq(file_name),
+                cover_misses::checked_at.eq(OffsetDateTime::now_utc().unix_timestamp()),
+            ))
+            .execute(conn)?;
+
+        Ok(())
+    }
+}
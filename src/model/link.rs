@@ -0,0 +1,178 @@
+use diesel::prelude::*;
+use time::OffsetDateTime;
+
+use crate::calendar::{LinkKind, Participant, Release as CalendarRelease};
+use crate::error::Result;
+use crate::scraper::bandcamp::BandcampMatch;
+
+use super::{calendar::Release, schema, ModelManager};
+
+/// Represents a web link attached to a release.
+///
+/// This struct corresponds to a row in the `release_links` table,
+/// which stores the external links of a release, e.g. its YouTube
+/// search and its artist's Bandcamp page.
+#[derive(Queryable, Selectable, Identifiable, Associations, Clone, Debug, PartialEq)]
+#[diesel(belongs_to(Release))]
+#[diesel(table_name = super::schema::release_links)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct ReleaseLink {
+    pub id: i32,
+    pub release_id: i32,
+    /// The [`LinkKind`] identifier, e.g. `youtube`.
+    pub kind: String,
+    pub url: String,
+}
+
+impl ReleaseLink {
+    /// Parses the stored `kind` column.
+    pub fn kind(&self) -> Option<LinkKind> {
+        self.kind.parse().ok()
+    }
+}
+
+/// An artist whose Bandcamp page is due to be looked up.
+#[derive(Queryable, Debug, PartialEq)]
+pub struct StaleBandcampArtist {
+    pub id: i32,
+    pub name: String,
+}
+
+/// `LinkBmc` is a backend model controller responsible for the
+/// links of the releases.
+pub struct LinkBmc;
+
+impl LinkBmc {
+    /// Stores the links of a newly inserted release.
+    pub(in crate::model) fn create_for_release(
+        conn: &mut SqliteConnection,
+        release_id: i32,
+        release: &CalendarRelease,
+    ) -> Result<()> {
+        use schema::release_links;
+
+        for link in release.links.iter() {
+            diesel::insert_or_ignore_into(release_links::table)
+                .values((
+                    release_links::release_id.eq(release_id),
+                    release_links::kind.eq(link.kind.as_str()),
+                    release_links::url.eq(link.url.as_str()),
+                ))
+                .execute(conn)?;
+        }
+
+        Ok(())
+    }
+
+    /// Retrieves the links of each release, in the order they were added.
+    ///
+    /// The returned vector is parallel to `releases`.
+    pub(in crate::model) fn get_for_releases(
+        conn: &mut SqliteConnection,
+        releases: &[Release],
+    ) -> Result<Vec<Vec<ReleaseLink>>> {
+        let links = ReleaseLink::belonging_to(releases)
+            .order(schema::release_links::id)
+            .select(ReleaseLink::as_select())
+            .load(conn)?;
+
+        Ok(links.grouped_by(releases))
+    }
+
+    /// Retrieves the Bandcamp page of the participant, if known.
+    ///
+    /// The participant is matched with an artist as when the calendar is
+    /// stored: on their Wikipedia page when known, otherwise on their name.
+    pub fn get_bandcamp_url(
+        conn: &mut SqliteConnection,
+        participant: &Participant,
+    ) -> Option<String> {
+        use schema::artists;
+
+        let query = match participant.wikipedia.as_deref().filter(|w| !w.is_empty()) {
+            Some(wikipedia) => artists::table
+                .filter(artists::wikipedia.eq(wikipedia))
+                .into_boxed(),
+            None => artists::table
+                .filter(artists::name.eq(&participant.name))
+                .order(artists::wikipedia)
+                .into_boxed(),
+        };
+
+        query
+            .select(artists::bandcamp_url)
+            .first::<Option<String>>(conn)
+            .ok()
+            .flatten()
+    }
+
+    /// Retrieves the artists credited first on a release whose Bandcamp page
+    /// was never looked up, or was last looked up before the given time.
+    pub fn get_stale_bandcamp(checked_before: OffsetDateTime) -> Result<Vec<StaleBandcampArtist>> {
+        use schema::{artists, releases};
+
+        let mm = &mut ModelManager::new();
+        let conn = &mut mm.conn;
+
+        let stale = artists::table
+            .filter(
+                artists::bandcamp_checked_at
+                    .is_null()
+                    .or(artists::bandcamp_checked_at.lt(checked_before.unix_timestamp())),
+            )
+            .filter(artists::id.eq_any(releases::table.select(releases::artist_id)))
+            .order(artists::bandcamp_checked_at.asc())
+            .select((artists::id, artists::name))
+            .load(conn)?;
+
+        Ok(stale)
+    }
+
+    /// Stores the outcome of looking up an artist's Bandcamp page and
+    /// updates the Bandcamp link of the releases the artist is credited first on.
+    ///
+    /// The page is removed when it could not be found or verified anymore.
+    pub fn update_bandcamp(artist_id: i32, found: Option<&BandcampMatch>) -> Result<()> {
+        use schema::{artists, release_links, releases};
+
+        let mm = &mut ModelManager::new();
+        let conn = &mut mm.conn;
+
+        let url = found.map(|found| found.url.to_string());
+        conn.transaction(|conn| {
+            diesel::update(artists::table.find(artist_id))
+                .set((
+                    artists::bandcamp_url.eq(&url),
+                    artists::bandcamp_confidence.eq(found.map(|found| found.confidence)),
+                    artists::bandcamp_checked_at.eq(OffsetDateTime::now_utc().unix_timestamp()),
+                ))
+                .execute(conn)?;
+
+            let release_ids: Vec<i32> = releases::table
+                .filter(releases::artist_id.eq(artist_id))
+                .select(releases::id)
+                .load(conn)?;
+
+            diesel::delete(
+                release_links::table
+                    .filter(release_links::release_id.eq_any(&release_ids))
+                    .filter(release_links::kind.eq(LinkKind::Bandcamp.as_str())),
+            )
+            .execute(conn)?;
+
+            if let Some(url) = &url {
+                for release_id in release_ids {
+                    diesel::insert_or_ignore_into(release_links::table)
+                        .values((
+                            release_links::release_id.eq(release_id),
+                            release_links::kind.eq(LinkKind::Bandcamp.as_str()),
+                            release_links::url.eq(url),
+                        ))
+                        .execute(conn)?;
+                }
+            }
+
+            Ok(())
+        })
+    }
+}
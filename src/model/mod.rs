@@ -1,12 +1,34 @@
 //! The `model` module implements the building blocks of the repository.
 
+mod artist;
 mod calendar;
+mod contact;
+mod cover;
 mod feed;
+mod link;
+mod musicbrainz;
+mod outbox;
+mod revision;
+mod snapshot;
 mod store;
+mod subscriber;
+mod user;
+mod webhook;
 
 pub(in crate::model) mod schema;
-pub use calendar::CalendarBmc;
+pub use artist::{Artist, ArtistBmc};
+pub use calendar::{CalendarBmc, Release, ReleaseFilter, ReleaseWithArtists};
+pub use contact::{ContactMessage, ContactMessageBmc};
+pub use cover::CoverBmc;
 pub use feed::{Feed, FeedBmc, FeedForCreate};
+pub use link::{LinkBmc, ReleaseLink, StaleBandcampArtist};
+pub use musicbrainz::MusicBrainzBmc;
+pub use outbox::{OutboxBmc, OutboxChannel, OutboxEntry, OutboxStatus, MAX_NOTIFICATION_ATTEMPTS};
+pub use revision::RevisionBmc;
+pub use snapshot::{PageSnapshot, SnapshotBmc};
+pub use subscriber::{Subscriber, SubscriberBmc};
+pub use user::{User, UserBmc, Watchlist, LOGIN_TOKEN_TTL, SESSION_TTL};
+pub use webhook::{WebhookDelivery, WebhookDeliveryBmc, WebhookDeliveryForCreate};
 
 use diesel::prelude::*;
 
@@ -21,8 +43,22 @@ pub struct ModelManager {
 
 impl ModelManager {
     pub fn new() -> Self {
+        // The tests connect from several threads at once, so the migrations
+        // are applied before any of them could race to apply them.
+        #[cfg(test)]
+        {
+            static MIGRATED: std::sync::Once = std::sync::Once::new();
+            MIGRATED.call_once(|| drop(establish_connection(&config().DATABASE_URL)));
+        }
+
         Self {
             conn: establish_connection(&config().DATABASE_URL),
         }
     }
 }
+
+impl Default for ModelManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,99 @@
+use std::collections::{HashMap, HashSet};
+
+use diesel::prelude::*;
+use time::OffsetDateTime;
+
+use crate::calendar::MusicBrainzRelease;
+use crate::error::Result;
+
+use super::{schema, ModelManager};
+
+/// Releases keyed by their credited artist and album.
+type ReleaseKey = (String, String);
+
+/// A release's key along with its release group id, release id, track count and label.
+type KnownRow = (
+    String,
+    String,
+    Option<String>,
+    Option<String>,
+    Option<i32>,
+    Option<String>,
+);
+
+/// `MusicBrainzBmc` is a backend model controller that remembers which
+/// releases were looked up on MusicBrainz, so that the service is not
+/// queried for the same release on every calendar update.
+pub struct MusicBrainzBmc;
+
+impl MusicBrainzBmc {
+    /// Retrieves the MusicBrainz identifiers of the releases stored in the database.
+    pub fn get_known() -> Result<HashMap<ReleaseKey, MusicBrainzRelease>> {
+        use schema::releases;
+
+        let mm = &mut ModelManager::new();
+        let conn = &mut mm.conn;
+
+        let rows: Vec<KnownRow> = releases::table
+            .filter(releases::musicbrainz_release_group_id.is_not_null())
+            .select((
+                releases::artist_credit,
+                releases::album,
+                releases::musicbrainz_release_group_id,
+                releases::musicbrainz_release_id,
+                releases::track_count,
+                releases::label,
+            ))
+            .load(conn)?;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(
+                |(artist, album, group_id, release_id, track_count, label)| {
+                    Some((
+                        (artist, album),
+                        MusicBrainzRelease {
+                            release_group_id: group_id?,
+                            release_id: release_id.unwrap_or_default(),
+                            track_count,
+                            label,
+                        },
+                    ))
+                },
+            )
+            .collect())
+    }
+
+    /// Retrieves the releases that could not be found on MusicBrainz since the given time.
+    pub fn get_misses_since(since: OffsetDateTime) -> Result<HashSet<ReleaseKey>> {
+        use schema::musicbrainz_misses;
+
+        let mm = &mut ModelManager::new();
+        let conn = &mut mm.conn;
+
+        let misses: Vec<ReleaseKey> = musicbrainz_misses::table
+            .filter(musicbrainz_misses::checked_at.ge(since.unix_timestamp()))
+            .select((musicbrainz_misses::artist, musicbrainz_misses::album))
+            .load(conn)?;
+
+        Ok(misses.into_iter().collect())
+    }
+
+    /// Remembers that a release could not be found on MusicBrainz today.
+    pub fn add_miss(artist: &str, album: &str) -> Result<()> {
+        use schema::musicbrainz_misses;
+
+        let mm = &mut ModelManager::new();
+        let conn = &mut mm.conn;
+
+        diesel::replace_into(musicbrainz_misses::table)
+            .values((
+                musicbrainz_misses::artist.eq(artist),
+                musicbrainz_misses::album.eq(album),
+                musicbrainz_misses::checked_at.eq(OffsetDateTime::now_utc().unix_timestamp()),
+            ))
+            .execute(conn)?;
+
+        Ok(())
+    }
+}
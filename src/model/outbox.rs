@@ -0,0 +1,408 @@
+use std::{fmt, str::FromStr};
+
+use diesel::prelude::*;
+use time::{Date, OffsetDateTime};
+
+use crate::error::{Error, Result};
+
+use super::{schema, ModelManager, Release};
+
+/// The number of failed attempts after which a notification is given up on.
+pub const MAX_NOTIFICATION_ATTEMPTS: i32 = 5;
+
+/// The channels notifications are sent through.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum OutboxChannel {
+    /// The daily email of a subscriber, whose id is the recipient.
+    Email,
+    /// The `daily_releases` event of a webhook, whose URL is the recipient.
+    Webhook,
+    /// A chat channel of the `NOTIFIERS_FILE`, whose key is the recipient.
+    Chat,
+}
+
+impl OutboxChannel {
+    pub const ALL: [OutboxChannel; 3] = [
+        OutboxChannel::Email,
+        OutboxChannel::Webhook,
+        OutboxChannel::Chat,
+    ];
+
+    /// The identifier stored in the database.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OutboxChannel::Email => "email",
+            OutboxChannel::Webhook => "webhook",
+            OutboxChannel::Chat => "chat",
+        }
+    }
+}
+
+impl fmt::Display for OutboxChannel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for OutboxChannel {
+    type Err = String;
+
+    fn from_str(s: &str) -> core::result::Result<Self, Self::Err> {
+        OutboxChannel::ALL
+            .into_iter()
+            .find(|channel| channel.as_str() == s)
+            .ok_or_else(|| format!("unknown outbox channel `{s}`"))
+    }
+}
+
+/// The state of a notification.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutboxStatus {
+    /// Waiting to be sent, or to be retried after a failed attempt.
+    Pending,
+    Sent,
+    /// Given up on, either after too many attempts or because the recipient
+    /// or the release is gone.
+    Failed,
+}
+
+impl OutboxStatus {
+    /// The identifier stored in the database.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OutboxStatus::Pending => "pending",
+            OutboxStatus::Sent => "sent",
+            OutboxStatus::Failed => "failed",
+        }
+    }
+}
+
+/// `OutboxEntry` represents a row in the `notification_outbox` table, the
+/// notification of a release to a recipient of a channel.
+///
+/// A release is queued at most once per recipient, so that it is notified
+/// only once however many times the daily job runs.
+#[derive(Queryable, Identifiable, Selectable, Clone, Debug, PartialEq)]
+#[diesel(table_name = super::schema::notification_outbox)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct OutboxEntry {
+    pub id: i32,
+    pub channel: String,
+    /// The subscriber id, the webhook URL or the chat channel key.
+    pub recipient: String,
+    /// The release date as `YYYY-MM-DD`.
+    pub release_date: String,
+    /// See [`Release::notification_key`].
+    pub release_key: String,
+    pub status: String,
+    /// The number of failed attempts.
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl OutboxEntry {
+    /// Parses the stored `channel` column.
+    pub fn channel(&self) -> Option<OutboxChannel> {
+        self.channel.parse().ok()
+    }
+}
+
+/// `OutboxBmc` is a backend model controller responsible for the queue of
+/// the notifications to send.
+pub struct OutboxBmc;
+
+impl OutboxBmc {
+    /// Queues the notification of the releases to the recipient. Releases
+    /// already queued for the recipient are skipped, whatever their status.
+    ///
+    /// Returns the number of notifications queued.
+    pub fn enqueue(
+        channel: OutboxChannel,
+        recipient: &str,
+        releases: &[&Release],
+    ) -> Result<usize> {
+        use schema::notification_outbox;
+
+        let mm = &mut ModelManager::new();
+        let conn = &mut mm.conn;
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+
+        conn.transaction::<_, Error, _>(|conn| {
+            let mut num_queued = 0;
+            for release in releases {
+                num_queued += diesel::insert_or_ignore_into(notification_outbox::table)
+                    .values((
+                        notification_outbox::channel.eq(channel.as_str()),
+                        notification_outbox::recipient.eq(recipient),
+                        notification_outbox::release_date.eq(release.date_string()),
+                        notification_outbox::release_key.eq(release.notification_key()),
+                        notification_outbox::status.eq(OutboxStatus::Pending.as_str()),
+                        notification_outbox::created_at.eq(now),
+                        notification_outbox::updated_at.eq(now),
+                    ))
+                    .execute(conn)?;
+            }
+            Ok(num_queued)
+        })
+    }
+
+    /// Retrieves the notifications waiting to be sent of the releases out
+    /// by `until`, ordered by channel, recipient and release date.
+    pub fn get_pending(until: Date) -> Result<Vec<OutboxEntry>> {
+        use schema::notification_outbox;
+
+        let mm = &mut ModelManager::new();
+        let conn = &mut mm.conn;
+
+        // Dates display as `YYYY-MM-DD`, so they compare as strings.
+        let entries = notification_outbox::table
+            .filter(notification_outbox::status.eq(OutboxStatus::Pending.as_str()))
+            .filter(notification_outbox::release_date.le(until.to_string()))
+            .order((
+                notification_outbox::channel,
+                notification_outbox::recipient,
+                notification_outbox::release_date,
+                notification_outbox::id,
+            ))
+            .select(OutboxEntry::as_select())
+            .load(conn)?;
+
+        Ok(entries)
+    }
+
+    /// Marks the notifications as sent.
+    pub fn mark_sent(ids: &[i32]) -> Result<()> {
+        use schema::notification_outbox;
+
+        let mm = &mut ModelManager::new();
+        let conn = &mut mm.conn;
+
+        diesel::update(notification_outbox::table.filter(notification_outbox::id.eq_any(ids)))
+            .set((
+                notification_outbox::status.eq(OutboxStatus::Sent.as_str()),
+                notification_outbox::last_error.eq(None::<String>),
+                notification_outbox::updated_at.eq(OffsetDateTime::now_utc().unix_timestamp()),
+            ))
+            .execute(conn)?;
+
+        Ok(())
+    }
+
+    /// Moves the notification to the new date of its release, given as
+    /// `YYYY-MM-DD`, to be sent with the releases of that day.
+    pub fn reschedule(id: i32, release_date: &str) -> Result<()> {
+        use schema::notification_outbox;
+
+        let mm = &mut ModelManager::new();
+        let conn = &mut mm.conn;
+
+        diesel::update(
+            notification_outbox::table
+                .filter(notification_outbox::id.eq(id))
+                .filter(notification_outbox::status.eq(OutboxStatus::Pending.as_str())),
+        )
+        .set((
+            notification_outbox::release_date.eq(release_date),
+            notification_outbox::updated_at.eq(OffsetDateTime::now_utc().unix_timestamp()),
+        ))
+        .execute(conn)?;
+
+        Ok(())
+    }
+
+    /// Records a failed attempt at sending the notifications. They are
+    /// retried later unless `is_permanent` or after
+    /// [`MAX_NOTIFICATION_ATTEMPTS`] attempts, in which case they are marked
+    /// as failed. The notifications already sent are left as they are.
+    pub fn record_failure(ids: &[i32], error: &str, is_permanent: bool) -> Result<()> {
+        use schema::notification_outbox;
+
+        let mm = &mut ModelManager::new();
+        let conn = &mut mm.conn;
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+
+        conn.transaction::<_, Error, _>(|conn| {
+            let pending = notification_outbox::table
+                .filter(notification_outbox::id.eq_any(ids))
+                .filter(notification_outbox::status.eq(OutboxStatus::Pending.as_str()));
+
+            diesel::update(pending.clone())
+                .set((
+                    notification_outbox::attempts.eq(notification_outbox::attempts + 1),
+                    notification_outbox::last_error.eq(error),
+                    notification_outbox::updated_at.eq(now),
+                ))
+                .execute(conn)?;
+
+            let failed = notification_outbox::status.eq(OutboxStatus::Failed.as_str());
+            if is_permanent {
+                diesel::update(pending).set(failed).execute(conn)?;
+            } else {
+                diesel::update(
+                    pending.filter(notification_outbox::attempts.ge(MAX_NOTIFICATION_ATTEMPTS)),
+                )
+                .set(failed)
+                .execute(conn)?;
+            }
+
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use time::Month;
+
+    use crate::support::fixtures::release;
+
+    type Result<T> = core::result::Result<T, Box<dyn std::error::Error>>;
+
+    fn pending(recipient: &str) -> Result<Vec<OutboxEntry>> {
+        let until = Date::from_calendar_date(2024, Month::December, 31)?;
+        Ok(OutboxBmc::get_pending(until)?
+            .into_iter()
+            .filter(|entry| entry.recipient == recipient)
+            .collect())
+    }
+
+    #[test]
+    fn test_enqueue_ok() -> Result<()> {
+        let recipient = "test-enqueue";
+        let first = release(1, "Wintersun", "Time II").build().0;
+        let second = release(2, "Opeth", "The Last Will and Testament").build().0;
+
+        let num_queued = OutboxBmc::enqueue(OutboxChannel::Chat, recipient, &[&first])?;
+        pretty_assertions::assert_eq!(num_queued, 1);
+        let num_queued = OutboxBmc::enqueue(OutboxChannel::Chat, recipient, &[&first, &second])?;
+        pretty_assertions::assert_eq!(num_queued, 1, "the queued release is skipped");
+        let num_queued = OutboxBmc::enqueue(OutboxChannel::Email, recipient, &[&first])?;
+        pretty_assertions::assert_eq!(num_queued, 1, "another channel is another recipient");
+
+        let entries = pending(recipient)?;
+        pretty_assertions::assert_eq!(
+            entries
+                .iter()
+                .map(|entry| (entry.channel.as_str(), entry.release_key.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("chat", "wintersun - time ii"),
+                ("chat", "opeth - the last will and testament"),
+                ("email", "wintersun - time ii"),
+            ]
+        );
+        assert!(entries
+            .iter()
+            .all(|entry| entry.release_date == "2024-10-18"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_pending_until_ok() -> Result<()> {
+        let recipient = "test-get-pending-until";
+        let today = release(1, "Wintersun", "Time II").build().0;
+        let later = release(2, "Opeth", "The Last Will and Testament")
+            .date(11, 8)
+            .build()
+            .0;
+        OutboxBmc::enqueue(OutboxChannel::Chat, recipient, &[&today, &later])?;
+
+        let until = Date::from_calendar_date(2024, Month::October, 18)?;
+        let keys = |until| -> Result<Vec<String>> {
+            Ok(OutboxBmc::get_pending(until)?
+                .into_iter()
+                .filter(|entry| entry.recipient == recipient)
+                .map(|entry| entry.release_key)
+                .collect())
+        };
+        pretty_assertions::assert_eq!(keys(until)?, vec!["wintersun - time ii"]);
+
+        let entry = &pending(recipient)?[1];
+        OutboxBmc::reschedule(entry.id, "2024-10-18")?;
+        pretty_assertions::assert_eq!(
+            keys(until)?,
+            vec!["wintersun - time ii", "opeth - the last will and testament"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_record_failure_ok() -> Result<()> {
+        let recipient = "test-record-failure";
+        let first = release(1, "Wintersun", "Time II").build().0;
+        let second = release(2, "Opeth", "The Last Will and Testament").build().0;
+        OutboxBmc::enqueue(OutboxChannel::Webhook, recipient, &[&first, &second])?;
+        let ids = pending(recipient)?
+            .iter()
+            .map(|entry| entry.id)
+            .collect::<Vec<_>>();
+
+        for attempt in 1..MAX_NOTIFICATION_ATTEMPTS {
+            OutboxBmc::record_failure(&ids, "timed out", false)?;
+            let entries = pending(recipient)?;
+            pretty_assertions::assert_eq!(entries.len(), 2, "retried after {attempt} attempts");
+            assert!(entries.iter().all(|entry| entry.attempts == attempt
+                && entry.last_error.as_deref() == Some("timed out")));
+        }
+
+        OutboxBmc::record_failure(&ids, "timed out", false)?;
+        assert!(
+            pending(recipient)?.is_empty(),
+            "given up on after the last attempt"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_record_failure_permanent_ok() -> Result<()> {
+        let recipient = "test-record-failure-permanent";
+        let release = release(1, "Wintersun", "Time II").build().0;
+        OutboxBmc::enqueue(OutboxChannel::Webhook, recipient, &[&release])?;
+        let id = pending(recipient)?[0].id;
+
+        OutboxBmc::record_failure(&[id], "gone", true)?;
+
+        assert!(pending(recipient)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_mark_sent_ok() -> Result<()> {
+        let recipient = "test-mark-sent";
+        let first = release(1, "Wintersun", "Time II").build().0;
+        let second = release(2, "Opeth", "The Last Will and Testament").build().0;
+        OutboxBmc::enqueue(OutboxChannel::Chat, recipient, &[&first, &second])?;
+        let ids = pending(recipient)?
+            .iter()
+            .map(|entry| entry.id)
+            .collect::<Vec<_>>();
+
+        OutboxBmc::mark_sent(&ids[..1])?;
+        OutboxBmc::record_failure(&ids, "timed out", true)?;
+        OutboxBmc::reschedule(ids[0], "2024-10-01")?;
+
+        let mm = &mut ModelManager::new();
+        let entries = schema::notification_outbox::table
+            .filter(schema::notification_outbox::id.eq_any(&ids))
+            .order(schema::notification_outbox::id)
+            .select(OutboxEntry::as_select())
+            .load(&mut mm.conn)?;
+        pretty_assertions::assert_eq!(
+            entries
+                .iter()
+                .map(|entry| (
+                    entry.status.as_str(),
+                    entry.attempts,
+                    entry.release_date.as_str()
+                ))
+                .collect::<Vec<_>>(),
+            vec![("sent", 0, "2024-10-18"), ("failed", 1, "2024-10-18")],
+            "a sent notification is neither failed nor moved"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,48 @@
+use diesel::prelude::*;
+use time::OffsetDateTime;
+
+use crate::error::Result;
+
+use super::{schema, ModelManager};
+
+/// `RevisionBmc` is a backend model controller that remembers the revision
+/// of each release source's listing last stored in the calendar, so that
+/// an unchanged listing is not processed again.
+pub struct RevisionBmc;
+
+impl RevisionBmc {
+    /// Retrieves the revision of the source's listing of the year last stored, if any.
+    pub fn get(source: &str, year: i32) -> Result<Option<String>> {
+        use schema::source_revisions;
+
+        let mm = &mut ModelManager::new();
+        let conn = &mut mm.conn;
+
+        let revision = source_revisions::table
+            .find((source, year))
+            .select(source_revisions::revision)
+            .first(conn)
+            .optional()?;
+
+        Ok(revision)
+    }
+
+    /// Remembers that the revision of the source's listing of the year was stored.
+    pub fn set(source: &str, year: i32, revision: &str) -> Result<()> {
+        use schema::source_revisions;
+
+        let mm = &mut ModelManager::new();
+        let conn = &mut mm.conn;
+
+        diesel::replace_into(source_revisions::table)
+            .values((
+                source_revisions::source.eq(source),
+                source_revisions::year.eq(year),
+                source_revisions::revision.eq(revision),
+                source_revisions::updated_at.eq(OffsetDateTime::now_utc().unix_timestamp()),
+            ))
+            .execute(conn)?;
+
+        Ok(())
+    }
+}
@@ -4,6 +4,26 @@ diesel::table! {
     artists (id) {
         id -> Integer,
         name -> Text,
+        wikipedia -> Text,
+        bandcamp_url -> Nullable<Text>,
+        bandcamp_confidence -> Nullable<Float>,
+        bandcamp_checked_at -> Nullable<BigInt>,
+    }
+}
+
+diesel::table! {
+    contact_messages (id) {
+        id -> Integer,
+        email -> Text,
+        message -> Text,
+        created_at -> BigInt,
+    }
+}
+
+diesel::table! {
+    cover_misses (file_name) {
+        file_name -> Text,
+        checked_at -> BigInt,
     }
 }
 
@@ -16,11 +36,61 @@ diesel::table! {
 }
 
 diesel::table! {
-    links (id) {
+    login_tokens (token) {
+        token -> Text,
+        email -> Text,
+        expires_at -> BigInt,
+    }
+}
+
+diesel::table! {
+    musicbrainz_misses (artist, album) {
+        artist -> Text,
+        album -> Text,
+        checked_at -> BigInt,
+    }
+}
+
+diesel::table! {
+    notification_outbox (id) {
+        id -> Integer,
+        channel -> Text,
+        recipient -> Text,
+        release_date -> Text,
+        release_key -> Text,
+        status -> Text,
+        attempts -> Integer,
+        last_error -> Nullable<Text>,
+        created_at -> BigInt,
+        updated_at -> BigInt,
+    }
+}
+
+diesel::table! {
+    page_snapshots (id) {
         id -> Integer,
+        url -> Text,
+        body -> Text,
+        etag -> Nullable<Text>,
+        last_modified -> Nullable<Text>,
+        fetched_at -> BigInt,
+    }
+}
+
+diesel::table! {
+    release_artists (release_id, artist_id) {
+        release_id -> Integer,
         artist_id -> Integer,
-        url_youtube -> Text,
-        url_bandcamp -> Nullable<Text>,
+        position -> Integer,
+    }
+}
+
+diesel::table! {
+    release_links (id) {
+        id -> Integer,
+        release_id -> Integer,
+        kind -> Text,
+        url -> Text,
     }
 }
 
@@ -32,10 +102,110 @@ diesel::table! {
         day -> Integer,
         artist_id -> Integer,
         album -> Text,
+        release_type -> Text,
+        artist_credit -> Text,
+        wikipedia_album -> Nullable<Text>,
+        source_url -> Nullable<Text>,
+        providers -> Text,
+        musicbrainz_release_group_id -> Nullable<Text>,
+        musicbrainz_release_id -> Nullable<Text>,
+        track_count -> Nullable<Integer>,
+        cover -> Nullable<Text>,
+        label -> Nullable<Text>,
+        genre -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    sessions (token) {
+        token -> Text,
+        user_id -> Integer,
+        expires_at -> BigInt,
+    }
+}
+
+diesel::table! {
+    source_revisions (source, year) {
+        source -> Text,
+        year -> Integer,
+        revision -> Text,
+        updated_at -> BigInt,
+    }
+}
+
+diesel::table! {
+    subscribers (id) {
+        id -> Integer,
+        email -> Text,
+        token -> Text,
+        confirmed_at -> Nullable<BigInt>,
+        created_at -> BigInt,
+        confirmation_sent_at -> Nullable<BigInt>,
+    }
+}
+
+diesel::table! {
+    users (id) {
+        id -> Integer,
+        email -> Text,
+        feed_token -> Text,
+        created_at -> BigInt,
+    }
+}
+
+diesel::table! {
+    watchlist_artists (user_id, artist_id) {
+        user_id -> Integer,
+        artist_id -> Integer,
+    }
+}
+
+diesel::table! {
+    watchlist_genres (user_id, genre) {
+        user_id -> Integer,
+        genre -> Text,
+    }
+}
+
+diesel::table! {
+    webhook_deliveries (id) {
+        id -> Integer,
+        url -> Text,
+        event -> Text,
+        payload -> Text,
+        attempts -> Integer,
+        status_code -> Nullable<Integer>,
+        error -> Nullable<Text>,
+        created_at -> BigInt,
     }
 }
 
-diesel::joinable!(links -> artists (artist_id));
+diesel::joinable!(release_artists -> artists (artist_id));
+diesel::joinable!(release_artists -> releases (release_id));
+diesel::joinable!(release_links -> releases (release_id));
 diesel::joinable!(releases -> artists (artist_id));
+diesel::joinable!(sessions -> users (user_id));
+diesel::joinable!(watchlist_artists -> artists (artist_id));
+diesel::joinable!(watchlist_artists -> users (user_id));
+diesel::joinable!(watchlist_genres -> users (user_id));
 
-diesel::allow_tables_to_appear_in_same_query!(artists, feeds, links, releases,);
+diesel::allow_tables_to_appear_in_same_query!(
+    artists,
+    contact_messages,
+    cover_misses,
+    feeds,
+    login_tokens,
+    musicbrainz_misses,
+    notification_outbox,
+    page_snapshots,
+    release_artists,
+    release_links,
+    releases,
+    sessions,
+    source_revisions,
+    subscribers,
+    users,
+    watchlist_artists,
+    watchlist_genres,
+    webhook_deliveries,
+);
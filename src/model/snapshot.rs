@@ -0,0 +1,105 @@
+use diesel::prelude::*;
+use time::OffsetDateTime;
+
+use crate::error::{Error, Result};
+
+use super::{schema, ModelManager};
+
+/// `PageSnapshot` represents a row in the `page_snapshots` table, which
+/// archives every version of the pages releases are scraped from.
+#[derive(Queryable, Identifiable, Selectable, Clone, Debug, PartialEq)]
+#[diesel(table_name = super::schema::page_snapshots)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct PageSnapshot {
+    pub id: i32,
+    pub url: String,
+    /// The body of the page as it was fetched.
+    pub body: String,
+    /// The `ETag` header the page was served with.
+    pub etag: Option<String>,
+    /// The `Last-Modified` header the page was served with.
+    pub last_modified: Option<String>,
+    /// The Unix timestamp of when the page was fetched.
+    pub fetched_at: i64,
+}
+
+/// `SnapshotBmc` is a backend model controller responsible for the
+/// archive of fetched pages.
+pub struct SnapshotBmc;
+
+impl SnapshotBmc {
+    /// Archives a version of the page fetched now.
+    ///
+    /// When the body is the same as the latest snapshot's, only the
+    /// validators of the latest snapshot are updated.
+    pub fn create(
+        url: &str,
+        body: &str,
+        etag: Option<&str>,
+        last_modified: Option<&str>,
+    ) -> Result<()> {
+        use schema::page_snapshots;
+
+        let latest = SnapshotBmc::get_latest(url)?;
+
+        let mm = &mut ModelManager::new();
+        let conn = &mut mm.conn;
+
+        if let Some(latest) = latest.filter(|latest| latest.body == body) {
+            diesel::update(page_snapshots::table.find(latest.id))
+                .set((
+                    page_snapshots::etag.eq(etag),
+                    page_snapshots::last_modified.eq(last_modified),
+                ))
+                .execute(conn)?;
+            return Ok(());
+        }
+
+        diesel::insert_into(page_snapshots::table)
+            .values((
+                page_snapshots::url.eq(url),
+                page_snapshots::body.eq(body),
+                page_snapshots::etag.eq(etag),
+                page_snapshots::last_modified.eq(last_modified),
+                page_snapshots::fetched_at.eq(OffsetDateTime::now_utc().unix_timestamp()),
+            ))
+            .execute(conn)?;
+
+        Ok(())
+    }
+
+    /// Retrieves an archived snapshot by its id.
+    pub fn get(id: i32) -> Result<PageSnapshot> {
+        use schema::page_snapshots;
+
+        let mm = &mut ModelManager::new();
+        let conn = &mut mm.conn;
+
+        page_snapshots::table
+            .find(id)
+            .select(PageSnapshot::as_select())
+            .first(conn)
+            .optional()?
+            .ok_or(Error::EntityNotFound {
+                entity: "page_snapshot",
+                id: id as i64,
+            })
+    }
+
+    /// Retrieves the most recent snapshot of the page, if it was ever fetched.
+    pub fn get_latest(url: &str) -> Result<Option<PageSnapshot>> {
+        use schema::page_snapshots;
+
+        let mm = &mut ModelManager::new();
+        let conn = &mut mm.conn;
+
+        let snapshot = page_snapshots::table
+            .filter(page_snapshots::url.eq(url))
+            .order((page_snapshots::fetched_at.desc(), page_snapshots::id.desc()))
+            .select(PageSnapshot::as_select())
+            .first(conn)
+            .optional()?;
+
+        Ok(snapshot)
+    }
+}
@@ -0,0 +1 @@
+ALTER TABLE releases ADD COLUMN release_type VARCHAR NOT NULL DEFAULT 'album';
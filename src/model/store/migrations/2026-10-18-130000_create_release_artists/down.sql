@@ -0,0 +1,2 @@
+DROP TABLE release_artists;
+ALTER TABLE releases DROP COLUMN artist_credit;
@@ -0,0 +1,14 @@
+ALTER TABLE releases ADD COLUMN artist_credit VARCHAR NOT NULL DEFAULT '';
+
+UPDATE releases
+SET artist_credit = (SELECT name FROM artists WHERE artists.id = releases.artist_id);
+
+CREATE TABLE release_artists (
+    release_id INTEGER NOT NULL REFERENCES releases (id) ON DELETE CASCADE,
+    artist_id INTEGER NOT NULL REFERENCES artists (id) ON DELETE CASCADE,
+    position INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (release_id, artist_id)
+);
+
+INSERT INTO release_artists (release_id, artist_id, position)
+SELECT id, artist_id, 0 FROM releases;
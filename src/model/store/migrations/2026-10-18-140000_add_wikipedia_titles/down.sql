@@ -0,0 +1,12 @@
+ALTER TABLE releases DROP COLUMN wikipedia_album;
+
+CREATE TABLE artists_old (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    name VARCHAR NOT NULL UNIQUE
+);
+
+INSERT OR IGNORE INTO artists_old (id, name)
+SELECT id, name FROM artists ORDER BY id;
+
+DROP TABLE artists;
+ALTER TABLE artists_old RENAME TO artists;
@@ -0,0 +1,14 @@
+CREATE TABLE artists_new (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    name VARCHAR NOT NULL,
+    wikipedia VARCHAR NOT NULL DEFAULT '',
+    UNIQUE (name, wikipedia)
+);
+
+INSERT INTO artists_new (id, name)
+SELECT id, name FROM artists;
+
+DROP TABLE artists;
+ALTER TABLE artists_new RENAME TO artists;
+
+ALTER TABLE releases ADD COLUMN wikipedia_album VARCHAR;
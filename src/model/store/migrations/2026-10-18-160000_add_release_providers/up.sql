@@ -0,0 +1 @@
+ALTER TABLE releases ADD COLUMN providers VARCHAR NOT NULL DEFAULT 'wikipedia';
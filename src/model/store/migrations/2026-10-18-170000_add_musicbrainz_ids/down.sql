@@ -0,0 +1,5 @@
+DROP TABLE musicbrainz_misses;
+
+ALTER TABLE releases DROP COLUMN track_count;
+ALTER TABLE releases DROP COLUMN musicbrainz_release_id;
+ALTER TABLE releases DROP COLUMN musicbrainz_release_group_id;
@@ -0,0 +1,10 @@
+ALTER TABLE releases ADD COLUMN musicbrainz_release_group_id VARCHAR;
+ALTER TABLE releases ADD COLUMN musicbrainz_release_id VARCHAR;
+ALTER TABLE releases ADD COLUMN track_count INTEGER;
+
+CREATE TABLE musicbrainz_misses (
+    artist VARCHAR NOT NULL,
+    album VARCHAR NOT NULL,
+    checked_at BIGINT NOT NULL,
+    PRIMARY KEY (artist, album)
+);
@@ -0,0 +1,2 @@
+DROP TABLE cover_misses;
+ALTER TABLE releases DROP COLUMN cover;
@@ -0,0 +1,6 @@
+ALTER TABLE releases ADD COLUMN cover VARCHAR;
+
+CREATE TABLE cover_misses (
+    file_name VARCHAR NOT NULL PRIMARY KEY,
+    checked_at BIGINT NOT NULL
+);
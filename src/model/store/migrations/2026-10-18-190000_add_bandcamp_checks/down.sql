@@ -0,0 +1,2 @@
+ALTER TABLE links DROP COLUMN bandcamp_checked_at;
+ALTER TABLE links DROP COLUMN bandcamp_confidence;
@@ -0,0 +1,2 @@
+ALTER TABLE links ADD COLUMN bandcamp_confidence REAL;
+ALTER TABLE links ADD COLUMN bandcamp_checked_at BIGINT;
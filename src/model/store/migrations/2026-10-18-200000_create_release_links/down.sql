@@ -0,0 +1,31 @@
+CREATE TABLE links (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    artist_id INTEGER NOT NULL REFERENCES artists (id) ON DELETE CASCADE,
+    url_youtube TEXT NOT NULL,
+    url_bandcamp TEXT,
+    bandcamp_confidence REAL,
+    bandcamp_checked_at BIGINT
+);
+
+INSERT INTO links (artist_id, url_youtube, url_bandcamp, bandcamp_confidence, bandcamp_checked_at)
+SELECT
+    artists.id,
+    COALESCE((
+        SELECT release_links.url
+        FROM release_links
+        INNER JOIN releases ON releases.id = release_links.release_id
+        WHERE releases.artist_id = artists.id AND release_links.kind = 'youtube'
+        ORDER BY release_links.id
+        LIMIT 1
+    ), ''),
+    artists.bandcamp_url,
+    artists.bandcamp_confidence,
+    artists.bandcamp_checked_at
+FROM artists
+WHERE EXISTS (SELECT 1 FROM releases WHERE releases.artist_id = artists.id);
+
+ALTER TABLE artists DROP COLUMN bandcamp_checked_at;
+ALTER TABLE artists DROP COLUMN bandcamp_confidence;
+ALTER TABLE artists DROP COLUMN bandcamp_url;
+
+DROP TABLE release_links;
@@ -0,0 +1,36 @@
+CREATE TABLE release_links (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    release_id INTEGER NOT NULL REFERENCES releases (id) ON DELETE CASCADE,
+    kind VARCHAR NOT NULL,
+    url VARCHAR NOT NULL,
+    UNIQUE (release_id, kind, url)
+);
+
+INSERT OR IGNORE INTO release_links (release_id, kind, url)
+SELECT releases.id, 'youtube', links.url_youtube
+FROM releases
+INNER JOIN links ON links.artist_id = releases.artist_id
+WHERE links.url_youtube <> '';
+
+INSERT OR IGNORE INTO release_links (release_id, kind, url)
+SELECT releases.id, 'bandcamp', links.url_bandcamp
+FROM releases
+INNER JOIN links ON links.artist_id = releases.artist_id
+WHERE links.url_bandcamp IS NOT NULL;
+
+ALTER TABLE artists ADD COLUMN bandcamp_url VARCHAR;
+ALTER TABLE artists ADD COLUMN bandcamp_confidence REAL;
+ALTER TABLE artists ADD COLUMN bandcamp_checked_at BIGINT;
+
+UPDATE artists SET
+    bandcamp_url = (
+        SELECT url_bandcamp FROM links WHERE links.artist_id = artists.id ORDER BY links.id LIMIT 1
+    ),
+    bandcamp_confidence = (
+        SELECT bandcamp_confidence FROM links WHERE links.artist_id = artists.id ORDER BY links.id LIMIT 1
+    ),
+    bandcamp_checked_at = (
+        SELECT bandcamp_checked_at FROM links WHERE links.artist_id = artists.id ORDER BY links.id LIMIT 1
+    );
+
+DROP TABLE links;
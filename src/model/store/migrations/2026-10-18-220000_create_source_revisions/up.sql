@@ -0,0 +1,7 @@
+CREATE TABLE source_revisions (
+    source VARCHAR NOT NULL,
+    year INTEGER NOT NULL,
+    revision VARCHAR NOT NULL,
+    updated_at BIGINT NOT NULL,
+    PRIMARY KEY (source, year)
+);
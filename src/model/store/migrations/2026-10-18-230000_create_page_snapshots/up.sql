@@ -0,0 +1,10 @@
+CREATE TABLE page_snapshots (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    url VARCHAR NOT NULL,
+    body TEXT NOT NULL,
+    etag VARCHAR,
+    last_modified VARCHAR,
+    fetched_at BIGINT NOT NULL
+);
+
+CREATE INDEX page_snapshots_url ON page_snapshots (url, fetched_at);
@@ -0,0 +1,8 @@
+CREATE TABLE subscribers (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    email VARCHAR NOT NULL UNIQUE CHECK (email = lower(email)),
+    token VARCHAR NOT NULL UNIQUE,
+    confirmed_at BIGINT,
+    created_at BIGINT NOT NULL,
+    confirmation_sent_at BIGINT
+);
@@ -0,0 +1,6 @@
+CREATE TABLE contact_messages (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    email VARCHAR NOT NULL,
+    message TEXT NOT NULL,
+    created_at BIGINT NOT NULL
+);
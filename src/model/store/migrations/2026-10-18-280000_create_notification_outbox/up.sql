@@ -0,0 +1,15 @@
+CREATE TABLE notification_outbox (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    channel VARCHAR NOT NULL,
+    recipient VARCHAR NOT NULL,
+    release_date VARCHAR NOT NULL,
+    release_key VARCHAR NOT NULL,
+    status VARCHAR NOT NULL DEFAULT 'pending',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    created_at BIGINT NOT NULL,
+    updated_at BIGINT NOT NULL,
+    UNIQUE (channel, recipient, release_key)
+);
+
+CREATE INDEX notification_outbox_status_idx ON notification_outbox (status);
@@ -0,0 +1,5 @@
+DROP TABLE watchlist_genres;
+DROP TABLE watchlist_artists;
+DROP TABLE sessions;
+DROP TABLE login_tokens;
+DROP TABLE users;
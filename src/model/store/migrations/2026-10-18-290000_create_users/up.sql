@@ -0,0 +1,32 @@
+CREATE TABLE users (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    email VARCHAR NOT NULL UNIQUE CHECK (email = lower(email)),
+    feed_token VARCHAR NOT NULL UNIQUE,
+    created_at BIGINT NOT NULL
+);
+
+-- Users are created on their first login, so a login token holds the email
+-- address it was sent to.
+CREATE TABLE login_tokens (
+    token VARCHAR NOT NULL PRIMARY KEY,
+    email VARCHAR NOT NULL,
+    expires_at BIGINT NOT NULL
+);
+
+CREATE TABLE sessions (
+    token VARCHAR NOT NULL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    expires_at BIGINT NOT NULL
+);
+
+CREATE TABLE watchlist_artists (
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    artist_id INTEGER NOT NULL REFERENCES artists (id) ON DELETE CASCADE,
+    PRIMARY KEY (user_id, artist_id)
+);
+
+CREATE TABLE watchlist_genres (
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    genre VARCHAR NOT NULL,
+    PRIMARY KEY (user_id, genre)
+);
@@ -1,4 +1,4 @@
-use diesel::prelude::*;
+use diesel::{connection::SimpleConnection, prelude::*};
 
 use diesel_migrations::MigrationHarness;
 use diesel_migrations::{embed_migrations, EmbeddedMigrations};
@@ -7,10 +7,20 @@ const MIGRATIONS: EmbeddedMigrations = embed_migrations!("src/model/store/migrat
 
 pub fn establish_connection(database_url: &str) -> SqliteConnection {
     let mut conn = SqliteConnection::establish(database_url)
-        .expect(&format!("Error connecting to {}", database_url));
+        .unwrap_or_else(|_| panic!("Error connecting to {}", database_url));
+
+    // The jobs and the web server write concurrently, each through
+    // connections of their own.
+    conn.batch_execute("PRAGMA busy_timeout = 5000;")
+        .expect("busy timeout should have been set");
 
     conn.run_pending_migrations(MIGRATIONS)
         .expect("migrations should have been applied");
 
+    // Enabled after the migrations so that rebuilding a table does not
+    // cascade the deletion of the old one to the rows referencing it.
+    conn.batch_execute("PRAGMA foreign_keys = ON;")
+        .expect("foreign keys should have been enabled");
+
     conn
 }
@@ -0,0 +1,162 @@
+use diesel::prelude::*;
+use time::{Duration, OffsetDateTime};
+
+use crate::{error::Result, support::token::generate_token};
+
+use super::{schema, ModelManager};
+
+/// How long to wait before emailing an address that subscribed again
+/// another confirmation link.
+pub const CONFIRMATION_INTERVAL: Duration = Duration::hours(1);
+
+/// `Subscriber` represents a row in the `subscribers` table, which stores
+/// the email addresses the releases of the day are sent to.
+#[derive(Queryable, Identifiable, Selectable, Clone, Debug, PartialEq)]
+#[diesel(table_name = super::schema::subscribers)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Subscriber {
+    pub id: i32,
+    pub email: String,
+    /// The secret of the subscriber's confirmation and unsubscribe links.
+    pub token: String,
+    /// The Unix timestamp of when the subscriber confirmed their address.
+    /// Emails are only sent to confirmed subscribers.
+    pub confirmed_at: Option<i64>,
+    pub created_at: i64,
+    /// The Unix timestamp of when the last confirmation link was emailed.
+    pub confirmation_sent_at: Option<i64>,
+}
+
+impl Subscriber {
+    pub fn is_confirmed(&self) -> bool {
+        self.confirmed_at.is_some()
+    }
+}
+
+/// `SubscriberBmc` is a backend model controller responsible for the
+/// email subscribers.
+pub struct SubscriberBmc;
+
+impl SubscriberBmc {
+    /// Adds an unconfirmed subscriber, or retrieves the subscriber when the
+    /// address already subscribed. Addresses are compared regardless of case.
+    pub fn create(email: &str) -> Result<Subscriber> {
+        use schema::subscribers;
+
+        let email = email.trim().to_lowercase();
+        let mm = &mut ModelManager::new();
+        let conn = &mut mm.conn;
+
+        diesel::insert_or_ignore_into(subscribers::table)
+            .values((
+                subscribers::email.eq(&email),
+                subscribers::token.eq(generate_token()),
+                subscribers::created_at.eq(OffsetDateTime::now_utc().unix_timestamp()),
+            ))
+            .execute(conn)?;
+
+        let subscriber = subscribers::table
+            .filter(subscribers::email.eq(&email))
+            .select(Subscriber::as_select())
+            .first(conn)?;
+
+        Ok(subscriber)
+    }
+
+    /// Records that a confirmation link is emailed to the unconfirmed
+    /// subscriber now. Returns `false` when the subscriber confirmed their
+    /// address or was sent a link less than [`CONFIRMATION_INTERVAL`] ago.
+    pub fn claim_confirmation(id: i32) -> Result<bool> {
+        use schema::subscribers;
+
+        let mm = &mut ModelManager::new();
+        let conn = &mut mm.conn;
+
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        let num_updated = diesel::update(
+            subscribers::table
+                .find(id)
+                .filter(subscribers::confirmed_at.is_null())
+                .filter(
+                    subscribers::confirmation_sent_at
+                        .is_null()
+                        .or(subscribers::confirmation_sent_at
+                            .le(now - CONFIRMATION_INTERVAL.whole_seconds())),
+                ),
+        )
+        .set(subscribers::confirmation_sent_at.eq(now))
+        .execute(conn)?;
+
+        Ok(num_updated > 0)
+    }
+
+    /// Confirms the subscriber with the token. Returns `None` when no
+    /// subscriber has the token, e.g. after unsubscribing.
+    pub fn confirm(token: &str) -> Result<Option<Subscriber>> {
+        use schema::subscribers;
+
+        let mm = &mut ModelManager::new();
+        let conn = &mut mm.conn;
+
+        diesel::update(
+            subscribers::table
+                .filter(subscribers::token.eq(token))
+                .filter(subscribers::confirmed_at.is_null()),
+        )
+        .set(subscribers::confirmed_at.eq(OffsetDateTime::now_utc().unix_timestamp()))
+        .execute(conn)?;
+
+        let subscriber = subscribers::table
+            .filter(subscribers::token.eq(token))
+            .select(Subscriber::as_select())
+            .first(conn)
+            .optional()?;
+
+        Ok(subscriber)
+    }
+
+    /// Removes the subscriber with the token. Returns whether one was removed.
+    pub fn delete_by_token(token: &str) -> Result<bool> {
+        use schema::subscribers;
+
+        let mm = &mut ModelManager::new();
+        let conn = &mut mm.conn;
+
+        let num_deleted = diesel::delete(subscribers::table.filter(subscribers::token.eq(token)))
+            .execute(conn)?;
+
+        Ok(num_deleted > 0)
+    }
+
+    /// Retrieves the subscriber with the id, if they did not unsubscribe.
+    pub fn get(id: i32) -> Result<Option<Subscriber>> {
+        use schema::subscribers;
+
+        let mm = &mut ModelManager::new();
+        let conn = &mut mm.conn;
+
+        let subscriber = subscribers::table
+            .find(id)
+            .select(Subscriber::as_select())
+            .first(conn)
+            .optional()?;
+
+        Ok(subscriber)
+    }
+
+    /// Retrieves the subscribers who confirmed their address.
+    pub fn get_confirmed() -> Result<Vec<Subscriber>> {
+        use schema::subscribers;
+
+        let mm = &mut ModelManager::new();
+        let conn = &mut mm.conn;
+
+        let subscribers = subscribers::table
+            .filter(subscribers::confirmed_at.is_not_null())
+            .order(subscribers::id)
+            .select(Subscriber::as_select())
+            .load(conn)?;
+
+        Ok(subscribers)
+    }
+}
@@ -0,0 +1,466 @@
+use diesel::prelude::*;
+use time::{Duration, OffsetDateTime};
+
+use crate::{
+    error::{Error, Result},
+    support::token::generate_token,
+};
+
+use super::{schema, Artist, ModelManager, ReleaseWithArtists};
+
+/// How long a login link can be used.
+pub const LOGIN_TOKEN_TTL: Duration = Duration::minutes(15);
+
+/// How long to wait before emailing an address another login link while
+/// the last one is still unused.
+pub const LOGIN_LINK_INTERVAL: Duration = Duration::minutes(5);
+
+/// How long a user stays logged in.
+pub const SESSION_TTL: Duration = Duration::days(30);
+
+/// `User` represents a row in the `users` table. Users log in with a link
+/// emailed to them and follow the releases of their watchlist.
+#[derive(Queryable, Identifiable, Selectable, Clone, Debug, PartialEq)]
+#[diesel(table_name = super::schema::users)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct User {
+    pub id: i32,
+    pub email: String,
+    /// The secret of the user's personal feed at `/feed/{feed_token}.xml`.
+    pub feed_token: String,
+    pub created_at: i64,
+}
+
+/// The artists and genres a user follows.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Watchlist {
+    /// The followed artists, ordered by name.
+    pub artists: Vec<Artist>,
+    /// The followed genres, matched on part of a release's genre, e.g.
+    /// `black` for "Atmospheric Black Metal".
+    pub genres: Vec<String>,
+}
+
+impl Watchlist {
+    pub fn is_empty(&self) -> bool {
+        self.artists.is_empty() && self.genres.is_empty()
+    }
+
+    /// Whether one of the followed artists takes part in the release or the
+    /// release has one of the followed genres.
+    pub fn matches(&self, (release, artists, _): &ReleaseWithArtists) -> bool {
+        let is_artist = artists
+            .iter()
+            .any(|artist| self.artists.iter().any(|followed| followed.id == artist.id));
+
+        let is_genre = release.genre.as_ref().is_some_and(|genre| {
+            let genre = genre.to_lowercase();
+            self.genres
+                .iter()
+                .any(|followed| genre.contains(&followed.to_lowercase()))
+        });
+
+        is_artist || is_genre
+    }
+}
+
+/// `UserBmc` is a backend model controller responsible for the user
+/// accounts, their sessions and their watchlists.
+pub struct UserBmc;
+
+impl UserBmc {
+    /// Creates the token of a login link sent to the email address, valid
+    /// for [`LOGIN_TOKEN_TTL`]. Returns `None` when the address was sent a
+    /// link it has not used less than [`LOGIN_LINK_INTERVAL`] ago.
+    pub fn create_login_token(email: &str) -> Result<Option<String>> {
+        use schema::login_tokens;
+
+        let mm = &mut ModelManager::new();
+        let conn = &mut mm.conn;
+
+        let email = email.trim().to_lowercase();
+        let now = OffsetDateTime::now_utc();
+        conn.immediate_transaction::<_, Error, _>(|conn| {
+            diesel::delete(
+                login_tokens::table.filter(login_tokens::expires_at.lt(now.unix_timestamp())),
+            )
+            .execute(conn)?;
+
+            let recent = (now + LOGIN_TOKEN_TTL - LOGIN_LINK_INTERVAL).unix_timestamp();
+            let num_recent: i64 = login_tokens::table
+                .filter(login_tokens::email.eq(&email))
+                .filter(login_tokens::expires_at.gt(recent))
+                .count()
+                .get_result(conn)?;
+            if num_recent > 0 {
+                return Ok(None);
+            }
+
+            let token = generate_token();
+            diesel::insert_into(login_tokens::table)
+                .values((
+                    login_tokens::token.eq(&token),
+                    login_tokens::email.eq(&email),
+                    login_tokens::expires_at.eq((now + LOGIN_TOKEN_TTL).unix_timestamp()),
+                ))
+                .execute(conn)?;
+
+            Ok(Some(token))
+        })
+    }
+
+    /// Uses up the login token. Returns the user it was sent to, created on
+    /// their first login, or `None` when the token is unknown, already used
+    /// or expired.
+    pub fn consume_login_token(token: &str) -> Result<Option<User>> {
+        use schema::login_tokens;
+
+        let mm = &mut ModelManager::new();
+        let conn = &mut mm.conn;
+
+        conn.transaction::<_, Error, _>(|conn| {
+            let email: Option<String> = diesel::delete(
+                login_tokens::table
+                    .filter(login_tokens::token.eq(token))
+                    .filter(
+                        login_tokens::expires_at.ge(OffsetDateTime::now_utc().unix_timestamp()),
+                    ),
+            )
+            .returning(login_tokens::email)
+            .get_result(conn)
+            .optional()?;
+
+            match email {
+                Some(email) => Ok(Some(Self::get_or_create(conn, &email)?)),
+                None => Ok(None),
+            }
+        })
+    }
+
+    /// Retrieves the user with the email address, creating the user if
+    /// needed. Email addresses are stored in lowercase.
+    fn get_or_create(conn: &mut SqliteConnection, email: &str) -> Result<User> {
+        use schema::users;
+
+        let email = email.trim().to_lowercase();
+        diesel::insert_or_ignore_into(users::table)
+            .values((
+                users::email.eq(&email),
+                users::feed_token.eq(generate_token()),
+                users::created_at.eq(OffsetDateTime::now_utc().unix_timestamp()),
+            ))
+            .execute(conn)?;
+
+        let user = users::table
+            .filter(users::email.eq(&email))
+            .select(User::as_select())
+            .first(conn)?;
+
+        Ok(user)
+    }
+
+    /// Opens a session for the user, valid for [`SESSION_TTL`]. Returns its token.
+    pub fn create_session(user_id: i32) -> Result<String> {
+        use schema::sessions;
+
+        let mm = &mut ModelManager::new();
+        let conn = &mut mm.conn;
+
+        let now = OffsetDateTime::now_utc();
+        diesel::delete(sessions::table.filter(sessions::expires_at.lt(now.unix_timestamp())))
+            .execute(conn)?;
+
+        let token = generate_token();
+        diesel::insert_into(sessions::table)
+            .values((
+                sessions::token.eq(&token),
+                sessions::user_id.eq(user_id),
+                sessions::expires_at.eq((now + SESSION_TTL).unix_timestamp()),
+            ))
+            .execute(conn)?;
+
+        Ok(token)
+    }
+
+    /// Retrieves the user of the session, unless the session expired.
+    pub fn get_by_session(token: &str) -> Result<Option<User>> {
+        use schema::{sessions, users};
+
+        let mm = &mut ModelManager::new();
+        let conn = &mut mm.conn;
+
+        let user = sessions::table
+            .inner_join(users::table)
+            .filter(sessions::token.eq(token))
+            .filter(sessions::expires_at.ge(OffsetDateTime::now_utc().unix_timestamp()))
+            .select(User::as_select())
+            .first(conn)
+            .optional()?;
+
+        Ok(user)
+    }
+
+    /// Closes the session.
+    pub fn delete_session(token: &str) -> Result<()> {
+        use schema::sessions;
+
+        let mm = &mut ModelManager::new();
+        let conn = &mut mm.conn;
+
+        diesel::delete(sessions::table.filter(sessions::token.eq(token))).execute(conn)?;
+
+        Ok(())
+    }
+
+    /// Retrieves the user whose personal feed has the token.
+    pub fn get_by_feed_token(token: &str) -> Result<Option<User>> {
+        use schema::users;
+
+        let mm = &mut ModelManager::new();
+        let conn = &mut mm.conn;
+
+        let user = users::table
+            .filter(users::feed_token.eq(token))
+            .select(User::as_select())
+            .first(conn)
+            .optional()?;
+
+        Ok(user)
+    }
+
+    /// Gives the user's personal feed a new token, so that the previous URL
+    /// stops working. Returns the new token.
+    pub fn reset_feed_token(user_id: i32) -> Result<String> {
+        use schema::users;
+
+        let mm = &mut ModelManager::new();
+        let conn = &mut mm.conn;
+
+        let token = generate_token();
+        diesel::update(users::table.find(user_id))
+            .set(users::feed_token.eq(&token))
+            .execute(conn)?;
+
+        Ok(token)
+    }
+
+    /// Retrieves the artists and genres the user follows.
+    pub fn get_watchlist(user_id: i32) -> Result<Watchlist> {
+        use schema::{artists, watchlist_artists, watchlist_genres};
+
+        let mm = &mut ModelManager::new();
+        let conn = &mut mm.conn;
+
+        let artists = watchlist_artists::table
+            .inner_join(artists::table)
+            .filter(watchlist_artists::user_id.eq(user_id))
+            .order(artists::name)
+            .select(Artist::as_select())
+            .load(conn)?;
+
+        let genres = watchlist_genres::table
+            .filter(watchlist_genres::user_id.eq(user_id))
+            .order(watchlist_genres::genre)
+            .select(watchlist_genres::genre)
+            .load(conn)?;
+
+        Ok(Watchlist { artists, genres })
+    }
+
+    /// Adds the artists to the user's watchlist. Returns the number of
+    /// artists that were not followed yet.
+    pub fn add_artists(user_id: i32, artist_ids: &[i32]) -> Result<usize> {
+        use schema::watchlist_artists;
+
+        let mm = &mut ModelManager::new();
+        let conn = &mut mm.conn;
+
+        conn.transaction::<_, Error, _>(|conn| {
+            let mut num_added = 0;
+            for artist_id in artist_ids {
+                num_added += diesel::insert_or_ignore_into(watchlist_artists::table)
+                    .values((
+                        watchlist_artists::user_id.eq(user_id),
+                        watchlist_artists::artist_id.eq(artist_id),
+                    ))
+                    .execute(conn)?;
+            }
+            Ok(num_added)
+        })
+    }
+
+    /// Removes the artist from the user's watchlist.
+    pub fn remove_artist(user_id: i32, artist_id: i32) -> Result<()> {
+        use schema::watchlist_artists;
+
+        let mm = &mut ModelManager::new();
+        let conn = &mut mm.conn;
+
+        diesel::delete(watchlist_artists::table.find((user_id, artist_id))).execute(conn)?;
+
+        Ok(())
+    }
+
+    /// Adds the genre to the user's watchlist. Genres are stored in lowercase.
+    pub fn add_genre(user_id: i32, genre: &str) -> Result<()> {
+        use schema::watchlist_genres;
+
+        let mm = &mut ModelManager::new();
+        let conn = &mut mm.conn;
+
+        diesel::insert_or_ignore_into(watchlist_genres::table)
+            .values((
+                watchlist_genres::user_id.eq(user_id),
+                watchlist_genres::genre.eq(genre.trim().to_lowercase()),
+            ))
+            .execute(conn)?;
+
+        Ok(())
+    }
+
+    /// Removes the genre from the user's watchlist.
+    pub fn remove_genre(user_id: i32, genre: &str) -> Result<()> {
+        use schema::watchlist_genres;
+
+        let mm = &mut ModelManager::new();
+        let conn = &mut mm.conn;
+
+        diesel::delete(watchlist_genres::table.find((user_id, genre))).execute(conn)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::support::fixtures::release;
+
+    type Result<T> = core::result::Result<T, Box<dyn std::error::Error>>;
+
+    #[test]
+    fn test_consume_login_token_ok() -> Result<()> {
+        let token = UserBmc::create_login_token(" Login@Example.com ")?.expect("no link was sent");
+
+        let user = UserBmc::consume_login_token(&token)?.expect("the token is valid");
+        pretty_assertions::assert_eq!(user.email, "login@example.com");
+        pretty_assertions::assert_eq!(
+            UserBmc::consume_login_token(&token)?,
+            None,
+            "a token is used once"
+        );
+
+        let token =
+            UserBmc::create_login_token("login@example.com")?.expect("the previous link was used");
+        pretty_assertions::assert_eq!(
+            UserBmc::consume_login_token(&token)?,
+            Some(user),
+            "the user is created once"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_login_token_throttled_ok() -> Result<()> {
+        let token = UserBmc::create_login_token("throttled@example.com")?;
+
+        assert!(token.is_some());
+        pretty_assertions::assert_eq!(
+            UserBmc::create_login_token("Throttled@Example.com")?,
+            None,
+            "one unused link per interval"
+        );
+        assert!(UserBmc::create_login_token("other-throttled@example.com")?.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_consume_login_token_expired_ok() -> Result<()> {
+        let token =
+            UserBmc::create_login_token("expired-login@example.com")?.expect("no link was sent");
+        let mm = &mut ModelManager::new();
+        diesel::update(schema::login_tokens::table.find(&token))
+            .set(
+                schema::login_tokens::expires_at.eq(OffsetDateTime::now_utc().unix_timestamp() - 1),
+            )
+            .execute(&mut mm.conn)?;
+
+        pretty_assertions::assert_eq!(UserBmc::consume_login_token(&token)?, None);
+        let user = schema::users::table
+            .filter(schema::users::email.eq("expired-login@example.com"))
+            .select(User::as_select())
+            .first(&mut mm.conn)
+            .optional()?;
+        pretty_assertions::assert_eq!(user, None, "no user is created");
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_by_session_ok() -> Result<()> {
+        let token = UserBmc::create_login_token("session@example.com")?.expect("no link was sent");
+        let user = UserBmc::consume_login_token(&token)?.expect("the token is valid");
+        let session = UserBmc::create_session(user.id)?;
+        let expired = UserBmc::create_session(user.id)?;
+        let mm = &mut ModelManager::new();
+        diesel::update(schema::sessions::table.find(&expired))
+            .set(schema::sessions::expires_at.eq(OffsetDateTime::now_utc().unix_timestamp() - 1))
+            .execute(&mut mm.conn)?;
+
+        pretty_assertions::assert_eq!(UserBmc::get_by_session(&session)?, Some(user));
+        pretty_assertions::assert_eq!(UserBmc::get_by_session(&expired)?, None);
+
+        UserBmc::delete_session(&session)?;
+        pretty_assertions::assert_eq!(UserBmc::get_by_session(&session)?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_reset_feed_token_ok() -> Result<()> {
+        let token = UserBmc::create_login_token("feed@example.com")?.expect("no link was sent");
+        let user = UserBmc::consume_login_token(&token)?.expect("the token is valid");
+        pretty_assertions::assert_eq!(
+            UserBmc::get_by_feed_token(&user.feed_token)?,
+            Some(user.clone())
+        );
+
+        let feed_token = UserBmc::reset_feed_token(user.id)?;
+
+        assert_ne!(feed_token, user.feed_token);
+        pretty_assertions::assert_eq!(UserBmc::get_by_feed_token(&user.feed_token)?, None);
+        pretty_assertions::assert_eq!(
+            UserBmc::get_by_feed_token(&feed_token)?.map(|user| user.id),
+            Some(user.id)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_watchlist_matches_ok() -> Result<()> {
+        let watchlist = Watchlist {
+            artists: vec![Artist {
+                id: 2,
+                name: "Spectral Voice".to_string(),
+                wikipedia: String::new(),
+            }],
+            genres: vec!["black".to_string()],
+        };
+        let mut split = release(1, "Ulthar / Spectral Voice", "Helleborus").build();
+        split.1[1].id = 2;
+
+        assert!(watchlist.matches(&split), "a followed artist takes part");
+        assert!(watchlist.matches(
+            &release(3, "Wintersun", "Time II")
+                .genre("Atmospheric Black Metal")
+                .build()
+        ));
+        assert!(!watchlist.matches(
+            &release(4, "Opeth", "The Last Will and Testament")
+                .genre("Progressive Metal")
+                .build()
+        ));
+        assert!(!watchlist.matches(&release(5, "Ulthar", "Anthronomicon").build()));
+        assert!(!Watchlist::default().matches(&split));
+        Ok(())
+    }
+}
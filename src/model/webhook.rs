@@ -0,0 +1,65 @@
+use diesel::prelude::*;
+use time::OffsetDateTime;
+
+use crate::error::Result;
+
+use super::{schema, ModelManager};
+
+/// `WebhookDelivery` represents a row in the `webhook_deliveries` table,
+/// which logs every event sent to a webhook along with its outcome.
+#[derive(Queryable, Identifiable, Selectable, Clone, Debug, PartialEq)]
+#[diesel(table_name = super::schema::webhook_deliveries)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct WebhookDelivery {
+    pub id: i32,
+    pub url: String,
+    /// The event type, e.g. `release_added`.
+    pub event: String,
+    /// The JSON body sent.
+    pub payload: String,
+    /// The number of requests sent, including the retries.
+    pub attempts: i32,
+    /// The status code of the last response, if any was received.
+    pub status_code: Option<i32>,
+    /// Why the delivery failed, or `None` when it succeeded.
+    pub error: Option<String>,
+    pub created_at: i64,
+}
+
+/// `WebhookDeliveryForCreate` holds the outcome of a delivery to log.
+pub struct WebhookDeliveryForCreate {
+    pub url: String,
+    pub event: String,
+    pub payload: String,
+    pub attempts: i32,
+    pub status_code: Option<i32>,
+    pub error: Option<String>,
+}
+
+/// `WebhookDeliveryBmc` is a backend model controller responsible for the
+/// log of the webhook deliveries.
+pub struct WebhookDeliveryBmc;
+
+impl WebhookDeliveryBmc {
+    /// Logs the outcome of a delivery.
+    pub fn create(delivery: WebhookDeliveryForCreate) -> Result<()> {
+        use schema::webhook_deliveries;
+
+        let mm = &mut ModelManager::new();
+        let conn = &mut mm.conn;
+
+        diesel::insert_into(webhook_deliveries::table)
+            .values((
+                webhook_deliveries::url.eq(delivery.url),
+                webhook_deliveries::event.eq(delivery.event),
+                webhook_deliveries::payload.eq(delivery.payload),
+                webhook_deliveries::attempts.eq(delivery.attempts),
+                webhook_deliveries::status_code.eq(delivery.status_code),
+                webhook_deliveries::error.eq(delivery.error),
+                webhook_deliveries::created_at.eq(OffsetDateTime::now_utc().unix_timestamp()),
+            ))
+            .execute(conn)?;
+
+        Ok(())
+    }
+}
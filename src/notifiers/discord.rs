@@ -0,0 +1,147 @@
+use axum::async_trait;
+use serde_json::{json, Value};
+
+use crate::{error::Result, model::ReleaseWithArtists};
+
+use super::{
+    link_list, post_json, release_title, secret_digest, Message, Notifier, NotifierFilter,
+};
+
+/// The maximum number of embeds of a Discord message.
+const MAX_EMBEDS: usize = 10;
+
+/// Posts the releases to a channel through a [Discord webhook](https://discord.com/developers/docs/resources/webhook).
+pub struct DiscordNotifier {
+    client: reqwest::Client,
+    url: String,
+    filter: NotifierFilter,
+}
+
+impl DiscordNotifier {
+    pub fn new(client: reqwest::Client, url: String, filter: NotifierFilter) -> Self {
+        Self {
+            client,
+            url,
+            filter,
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for DiscordNotifier {
+    fn name(&self) -> &'static str {
+        "discord"
+    }
+
+    fn key(&self) -> String {
+        // The URL of a webhook holds its token.
+        format!("discord:{}", secret_digest(&self.url))
+    }
+
+    fn filter(&self) -> &NotifierFilter {
+        &self.filter
+    }
+
+    /// One embed per release. The title of the day is only given to the
+    /// first message.
+    fn messages(&self, date: &str, releases: &[&ReleaseWithArtists]) -> Vec<Message> {
+        releases
+            .chunks(MAX_EMBEDS)
+            .enumerate()
+            .map(|(i, chunk)| {
+                let embeds = chunk.iter().map(|release| embed(release)).collect::<Vec<_>>();
+                let start = i * MAX_EMBEDS;
+                Message {
+                    releases: start..start + chunk.len(),
+                    body: if i == 0 {
+                        json!({ "content": format!("**Heavy metal releases of {date}**"), "embeds": embeds })
+                    } else {
+                        json!({ "embeds": embeds })
+                    },
+                }
+            })
+            .collect()
+    }
+
+    async fn send(&self, message: &Message, _txn_id: &str) -> Result<()> {
+        post_json(&self.client, &self.url, &message.body).await?;
+        Ok(())
+    }
+}
+
+fn embed(release: &ReleaseWithArtists) -> Value {
+    let (release, _, links) = release;
+
+    let mut lines = Vec::new();
+    if let Some(genre) = &release.genre {
+        lines.push(genre.clone());
+    }
+    let links = link_list(links)
+        .into_iter()
+        .map(|(kind, url)| format!("[{kind}]({url})"))
+        .collect::<Vec<_>>();
+    if !links.is_empty() {
+        lines.push(links.join(" · "));
+    }
+
+    let mut embed = json!({
+        "title": release_title(release),
+        "description": lines.join("\n"),
+    });
+    if let Some(url) = release.cover_url() {
+        embed["thumbnail"] = json!({ "url": url });
+    }
+    embed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        notifiers::tests::{notify, serve_stub},
+        support::fixtures::release,
+    };
+
+    type Result<T> = core::result::Result<T, Box<dyn std::error::Error>>;
+
+    #[tokio::test]
+    async fn test_notify_ok() -> Result<()> {
+        let (base_url, received) = serve_stub("").await?;
+        let notifier = DiscordNotifier::new(
+            reqwest::Client::new(),
+            format!("{base_url}/api/webhooks/1/abc"),
+            NotifierFilter::default(),
+        );
+        let releases = (1..=12)
+            .map(|id| {
+                release(id, "Wintersun", "Time II")
+                    .genre("Melodic Death Metal")
+                    .build()
+            })
+            .collect::<Vec<_>>();
+
+        notify(&notifier, &releases).await?;
+
+        let received = received.lock().unwrap();
+        pretty_assertions::assert_eq!(received.len(), 2, "12 releases take two messages");
+        pretty_assertions::assert_eq!(received[0].path, "/api/webhooks/1/abc");
+        let first = &received[0].body;
+        pretty_assertions::assert_eq!(
+            first["content"],
+            "**Heavy metal releases of October 18, 2024**"
+        );
+        pretty_assertions::assert_eq!(first["embeds"].as_array().unwrap().len(), 10);
+        pretty_assertions::assert_eq!(
+            first["embeds"][0],
+            json!({
+                "title": "Wintersun - Time II",
+                "description": "Melodic Death Metal\n[Bandcamp](https://band1.bandcamp.com)",
+            })
+        );
+        pretty_assertions::assert_eq!(received[1].body["embeds"].as_array().unwrap().len(), 2);
+        assert!(received[1].body.get("content").is_none());
+        assert!(!notifier.key().contains("/abc"));
+        Ok(())
+    }
+}
@@ -0,0 +1,138 @@
+use axum::async_trait;
+use serde_json::json;
+
+use crate::{error::Result, model::ReleaseWithArtists};
+
+use super::{day_url, push_lines, secret_digest, send_json, Message, Notifier, NotifierFilter};
+
+/// The default priority of Gotify, from 0 (silent) to 10 (max).
+const DEFAULT_PRIORITY: u8 = 5;
+
+/// Pushes the releases to the application of a [Gotify](https://gotify.net/docs/pushmsg) server.
+pub struct GotifyNotifier {
+    client: reqwest::Client,
+    server: String,
+    token: String,
+    priority: u8,
+    base_url: String,
+    filter: NotifierFilter,
+}
+
+impl GotifyNotifier {
+    /// Creates a notifier whose notifications open the calendar pages of the
+    /// website at `base_url`. The token is the one of a Gotify application.
+    pub fn new(
+        client: reqwest::Client,
+        server: String,
+        token: String,
+        priority: Option<u8>,
+        base_url: String,
+        filter: NotifierFilter,
+    ) -> Self {
+        Self {
+            client,
+            server,
+            token,
+            priority: priority.unwrap_or(DEFAULT_PRIORITY),
+            base_url,
+            filter,
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for GotifyNotifier {
+    fn name(&self) -> &'static str {
+        "gotify"
+    }
+
+    fn key(&self) -> String {
+        // An application token only posts to its own application.
+        format!(
+            "gotify:{}:{}",
+            self.server.trim_end_matches('/'),
+            secret_digest(&self.token)
+        )
+    }
+
+    fn filter(&self) -> &NotifierFilter {
+        &self.filter
+    }
+
+    /// A single message, one line per release. Gotify has no limit on the
+    /// length of a message.
+    fn messages(&self, date: &str, releases: &[&ReleaseWithArtists]) -> Vec<Message> {
+        let click = day_url(&self.base_url, &releases[0].0);
+
+        vec![Message {
+            releases: 0..releases.len(),
+            body: json!({
+                "title": format!("Heavy metal releases of {date}"),
+                "message": push_lines(releases).join("\n"),
+                "priority": self.priority,
+                "extras": {
+                    "client::display": { "contentType": "text/plain" },
+                    "client::notification": { "click": { "url": click } },
+                },
+            }),
+        }]
+    }
+
+    async fn send(&self, message: &Message, _txn_id: &str) -> Result<()> {
+        let url = format!("{}/message", self.server.trim_end_matches('/'));
+        let req = self.client.post(url).bearer_auth(&self.token);
+        send_json(req, &message.body).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        notifiers::tests::{notify, serve_stub},
+        support::fixtures::release,
+    };
+
+    type Result<T> = core::result::Result<T, Box<dyn std::error::Error>>;
+
+    #[tokio::test]
+    async fn test_notify_ok() -> Result<()> {
+        let (base_url, received) = serve_stub(r#"{"id":1}"#).await?;
+        let notifier = GotifyNotifier::new(
+            reqwest::Client::new(),
+            format!("{base_url}/"),
+            "app_abc".to_string(),
+            None,
+            "https://metal.example".to_string(),
+            NotifierFilter::default(),
+        );
+        let releases = [
+            release(1, "Wintersun", "Time II")
+                .genre("Melodic Death Metal")
+                .build(),
+            release(2, "Opeth", "The Last Will and Testament").build(),
+        ];
+
+        notify(&notifier, &releases).await?;
+
+        let received = received.lock().unwrap();
+        pretty_assertions::assert_eq!(received[0].path, "/message");
+        pretty_assertions::assert_eq!(received[0].authorization.as_deref(), Some("Bearer app_abc"));
+        pretty_assertions::assert_eq!(
+            received[0].body,
+            json!({
+                "title": "Heavy metal releases of October 18, 2024",
+                "message": "Wintersun - Time II · Melodic Death Metal\nOpeth - The Last Will and Testament",
+                "priority": 5,
+                "extras": {
+                    "client::display": { "contentType": "text/plain" },
+                    "client::notification": { "click": { "url": "https://metal.example/calendar/2024-10-18" } },
+                },
+            })
+        );
+        assert!(!notifier.key().contains("app_abc"));
+        Ok(())
+    }
+}
@@ -0,0 +1,185 @@
+use axum::async_trait;
+use serde_json::json;
+
+use crate::{
+    error::{Error, Result},
+    model::ReleaseWithArtists,
+};
+
+use super::{
+    char_count, link_list, release_html, release_range, release_title, send_json, split_message,
+    Message, Notifier, NotifierFilter,
+};
+
+/// The maximum number of characters of the HTML of a message. Matrix events
+/// are limited to 64 KiB, which holds both the plain text and the HTML.
+const MAX_LEN: usize = 12_000;
+
+/// Sends the releases to a room through the [Matrix Client-Server API](https://spec.matrix.org/latest/client-server-api/#put_matrixclientv3roomsroomidsendeventtypetxnid).
+pub struct MatrixNotifier {
+    client: reqwest::Client,
+    homeserver: reqwest::Url,
+    access_token: String,
+    room_id: String,
+    filter: NotifierFilter,
+}
+
+impl MatrixNotifier {
+    pub fn new(
+        client: reqwest::Client,
+        homeserver: reqwest::Url,
+        access_token: String,
+        room_id: String,
+        filter: NotifierFilter,
+    ) -> Self {
+        Self {
+            client,
+            homeserver,
+            access_token,
+            room_id,
+            filter,
+        }
+    }
+
+    /// The URL a message is sent to. The transaction id must be unique for
+    /// the access token, otherwise the homeserver ignores the message.
+    fn send_url(&self, txn_id: &str) -> Result<reqwest::Url> {
+        let mut url = self.homeserver.clone();
+        url.path_segments_mut()
+            .map_err(|_| Error::InvalidEnv("NOTIFIERS_FILE"))?
+            .pop_if_empty()
+            .extend([
+                "_matrix",
+                "client",
+                "v3",
+                "rooms",
+                &self.room_id,
+                "send",
+                "m.room.message",
+                txn_id,
+            ]);
+        Ok(url)
+    }
+}
+
+#[async_trait]
+impl Notifier for MatrixNotifier {
+    fn name(&self) -> &'static str {
+        "matrix"
+    }
+
+    fn key(&self) -> String {
+        format!("matrix:{}:{}", self.homeserver, self.room_id)
+    }
+
+    fn filter(&self) -> &NotifierFilter {
+        &self.filter
+    }
+
+    /// The title of the day is only given to the first message.
+    fn messages(&self, date: &str, releases: &[&ReleaseWithArtists]) -> Vec<Message> {
+        let title = format!("Heavy metal releases of {date}");
+
+        let mut html = vec![format!("<b>{title}</b>")];
+        html.extend(
+            releases
+                .iter()
+                .map(|release| release_html(release).replace('\n', "<br>")),
+        );
+        let mut text = vec![title];
+        text.extend(releases.iter().map(|release| release_text(release)));
+
+        split_message(&html, MAX_LEN, char_count)
+            .into_iter()
+            .map(|range| Message {
+                releases: release_range(&range),
+                body: json!({
+                    "msgtype": "m.text",
+                    "body": text[range.clone()].join("\n\n"),
+                    "format": "org.matrix.custom.html",
+                    "formatted_body": html[range].join("<br><br>"),
+                }),
+            })
+            .collect()
+    }
+
+    /// The homeserver ignores a message whose transaction id it already
+    /// received, so a message sent again after a failure is not duplicated.
+    async fn send(&self, message: &Message, txn_id: &str) -> Result<()> {
+        send_json(
+            self.client
+                .put(self.send_url(txn_id)?)
+                .bearer_auth(&self.access_token),
+            &message.body,
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+/// The plain text of a release for the clients not showing HTML.
+fn release_text((release, _, links): &ReleaseWithArtists) -> String {
+    let mut lines = vec![release_title(release)];
+    if let Some(genre) = &release.genre {
+        lines.push(genre.clone());
+    }
+    lines.extend(
+        link_list(links)
+            .into_iter()
+            .map(|(kind, url)| format!("{kind}: {url}")),
+    );
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use axum::http::Method;
+
+    use crate::{
+        notifiers::tests::{notify, serve_stub},
+        support::fixtures::release,
+    };
+
+    type Result<T> = core::result::Result<T, Box<dyn std::error::Error>>;
+
+    #[tokio::test]
+    async fn test_notify_ok() -> Result<()> {
+        let (base_url, received) = serve_stub(r#"{"event_id":"$1"}"#).await?;
+        let notifier = MatrixNotifier::new(
+            reqwest::Client::new(),
+            format!("{base_url}/").parse()?,
+            "syt_abc".to_string(),
+            "!room:matrix.org".to_string(),
+            NotifierFilter::default(),
+        );
+        let releases = [release(1, "Wintersun", "Time II")
+            .genre("Melodic Death Metal")
+            .build()];
+
+        notify(&notifier, &releases).await?;
+
+        let received = received.lock().unwrap();
+        pretty_assertions::assert_eq!(received.len(), 1);
+        pretty_assertions::assert_eq!(received[0].method, Method::PUT);
+        pretty_assertions::assert_eq!(
+            received[0].path,
+            "/_matrix/client/v3/rooms/!room:matrix.org/send/m.room.message/txn-0"
+        );
+        pretty_assertions::assert_eq!(received[0].authorization.as_deref(), Some("Bearer syt_abc"));
+        pretty_assertions::assert_eq!(
+            received[0].body,
+            json!({
+                "msgtype": "m.text",
+                "body": "Heavy metal releases of October 18, 2024\n\n\
+                         Wintersun - Time II\nMelodic Death Metal\nBandcamp: https://band1.bandcamp.com",
+                "format": "org.matrix.custom.html",
+                "formatted_body": "<b>Heavy metal releases of October 18, 2024</b><br><br>\
+                                   <b>Wintersun - Time II</b><br>Melodic Death Metal<br>\
+                                   <a href=\"https://band1.bandcamp.com\">Bandcamp</a>",
+            })
+        );
+        Ok(())
+    }
+}
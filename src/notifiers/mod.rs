@@ -0,0 +1,511 @@
+//! The `notifiers` module posts the releases of the day to chat channels.
+//!
+//! The channels are listed in the JSON file at `NOTIFIERS_FILE`, e.g.
+//!
+//! ```json
+//! [
+//!     { "kind": "discord", "url": "https://discord.com/api/webhooks/1/abc" },
+//!     { "kind": "slack", "url": "https://hooks.slack.com/services/T/B/X", "genres": ["black"] },
+//!     { "kind": "telegram", "token": "123:abc", "chat_id": "-100123" },
+//!     { "kind": "matrix", "homeserver": "https://matrix.org", "access_token": "syt_abc", "room_id": "!abc:matrix.org" },
+//!     { "kind": "ntfy", "topic": "heavy-metal", "priority": 4, "tags": ["metal"] },
+//!     { "kind": "gotify", "server": "https://gotify.example.com", "token": "AbC" }
+//! ]
+//! ```
+//!
+//! Every channel can be narrowed down to some artists and genres.
+
+mod discord;
+mod gotify;
+mod matrix;
+mod ntfy;
+mod slack;
+mod telegram;
+
+use std::{fs, ops::Range, time::Duration};
+
+use axum::async_trait;
+use serde::Deserialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    calendar::{LinkKind, ReleaseType},
+    config::config,
+    error::{Error, Result},
+    model::{Release, ReleaseLink, ReleaseWithArtists},
+    scraper::client::USER_AGENT,
+};
+
+pub use discord::DiscordNotifier;
+pub use gotify::GotifyNotifier;
+pub use matrix::MatrixNotifier;
+pub use ntfy::NtfyNotifier;
+pub use slack::SlackNotifier;
+pub use telegram::TelegramNotifier;
+
+const TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Sends the releases of a day to a channel.
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    /// Describes the channel in the logs, e.g. `discord`.
+    fn name(&self) -> &'static str;
+
+    /// Identifies the channel in the notification outbox, e.g.
+    /// `telegram:123:-100123`. It stays the same as long as the channel's
+    /// configuration does, and holds no secret since it is stored and
+    /// logged: secrets are replaced by their [`secret_digest`].
+    fn key(&self) -> String;
+
+    /// The releases the channel is interested in.
+    fn filter(&self) -> &NotifierFilter;
+
+    /// Builds the messages of the releases of the date, e.g. "October 18,
+    /// 2024", split when they exceed the limits of the channel. There is at
+    /// least one release.
+    fn messages(&self, date: &str, releases: &[&ReleaseWithArtists]) -> Vec<Message>;
+
+    /// Sends a message. The transaction id stays the same when the message
+    /// is sent again, so that the channels supporting it ignore a message
+    /// they already received.
+    async fn send(&self, message: &Message, txn_id: &str) -> Result<()>;
+}
+
+/// A message sent to a channel, along with the releases it lists.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Message {
+    /// The indices of the releases listed in the message, so that they are
+    /// marked as sent as soon as the message is delivered.
+    pub releases: Range<usize>,
+    pub body: Value,
+}
+
+/// Narrows down the releases sent to a channel. An empty filter matches
+/// every release.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+pub struct NotifierFilter {
+    /// Only keep releases in which one of these artists takes part.
+    #[serde(default)]
+    pub artists: Vec<String>,
+    /// Only keep releases whose genre contains one of these, e.g. `black` for
+    /// "Atmospheric Black Metal".
+    #[serde(default)]
+    pub genres: Vec<String>,
+}
+
+impl NotifierFilter {
+    pub fn matches(&self, (release, artists, _): &ReleaseWithArtists) -> bool {
+        let is_artist = self.artists.is_empty()
+            || self.artists.iter().any(|name| {
+                release.artist_credit.eq_ignore_ascii_case(name)
+                    || artists
+                        .iter()
+                        .any(|artist| artist.name.eq_ignore_ascii_case(name))
+            });
+
+        let is_genre = self.genres.is_empty()
+            || release.genre.as_ref().is_some_and(|genre| {
+                let genre = genre.to_lowercase();
+                self.genres
+                    .iter()
+                    .any(|wanted| genre.contains(&wanted.to_lowercase()))
+            });
+
+        is_artist && is_genre
+    }
+}
+
+/// A channel of the `NOTIFIERS_FILE`.
+#[derive(Deserialize)]
+struct ChannelConfig {
+    #[serde(flatten)]
+    kind: ChannelKind,
+    #[serde(flatten)]
+    filter: NotifierFilter,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum ChannelKind {
+    Discord {
+        url: String,
+    },
+    Gotify {
+        server: String,
+        token: String,
+        priority: Option<u8>,
+    },
+    Matrix {
+        homeserver: String,
+        access_token: String,
+        room_id: String,
+    },
+    Ntfy {
+        #[serde(default = "ntfy::default_server")]
+        server: String,
+        topic: String,
+        token: Option<String>,
+        priority: Option<u8>,
+        #[serde(default)]
+        tags: Vec<String>,
+    },
+    Slack {
+        url: String,
+    },
+    Telegram {
+        token: String,
+        chat_id: String,
+        #[serde(default = "telegram::default_base_url")]
+        base_url: String,
+    },
+}
+
+/// Creates the notifiers of the channels listed in the `NOTIFIERS_FILE`.
+pub fn notifiers_from_config() -> Result<Vec<Box<dyn Notifier>>> {
+    let Some(path) = &config().NOTIFIERS_FILE else {
+        return Ok(Vec::new());
+    };
+
+    let channels: Vec<ChannelConfig> = serde_json::from_str(&fs::read_to_string(path)?)?;
+    let client = http_client()?;
+    let base_url = &config().BASE_URL;
+
+    channels
+        .into_iter()
+        .map(|channel| -> Result<Box<dyn Notifier>> {
+            Ok(match channel.kind {
+                ChannelKind::Discord { url } => {
+                    Box::new(DiscordNotifier::new(client.clone(), url, channel.filter))
+                }
+                ChannelKind::Gotify {
+                    server,
+                    token,
+                    priority,
+                } => Box::new(GotifyNotifier::new(
+                    client.clone(),
+                    server,
+                    token,
+                    priority,
+                    base_url.clone(),
+                    channel.filter,
+                )),
+                ChannelKind::Matrix {
+                    homeserver,
+                    access_token,
+                    room_id,
+                } => Box::new(MatrixNotifier::new(
+                    client.clone(),
+                    homeserver
+                        .parse()
+                        .map_err(|_| Error::InvalidEnv("NOTIFIERS_FILE"))?,
+                    access_token,
+                    room_id,
+                    channel.filter,
+                )),
+                ChannelKind::Ntfy {
+                    server,
+                    topic,
+                    token,
+                    priority,
+                    tags,
+                } => Box::new(NtfyNotifier::new(
+                    client.clone(),
+                    server,
+                    topic,
+                    token,
+                    priority,
+                    tags,
+                    base_url.clone(),
+                    channel.filter,
+                )),
+                ChannelKind::Slack { url } => {
+                    Box::new(SlackNotifier::new(client.clone(), url, channel.filter))
+                }
+                ChannelKind::Telegram {
+                    token,
+                    chat_id,
+                    base_url,
+                } => Box::new(TelegramNotifier::new(
+                    client.clone(),
+                    base_url,
+                    token,
+                    chat_id,
+                    channel.filter,
+                )),
+            })
+        })
+        .collect()
+}
+
+/// The title of a release in a message, e.g. "Blood Incantation - Luminescent Bridge (EP)".
+fn release_title(release: &Release) -> String {
+    match release.kind() {
+        ReleaseType::Album => format!("{} - {}", release.artist_credit, release.album),
+        kind => format!("{} - {} ({kind})", release.artist_credit, release.album),
+    }
+}
+
+/// The links of a release whose kind is known.
+fn link_list(links: &[ReleaseLink]) -> Vec<(LinkKind, &str)> {
+    links
+        .iter()
+        .filter_map(|link| Some((link.kind()?, link.url.as_str())))
+        .collect()
+}
+
+/// A release in the HTML of Telegram and Matrix messages: its title in bold,
+/// followed by its genre and its links on their own lines.
+fn release_html((release, _, links): &ReleaseWithArtists) -> String {
+    let mut lines = vec![format!("<b>{}</b>", escape_html(&release_title(release)))];
+    if let Some(genre) = &release.genre {
+        lines.push(escape_html(genre));
+    }
+    let links = link_list(links)
+        .into_iter()
+        .map(|(kind, url)| format!("<a href=\"{}\">{kind}</a>", escape_html(url)))
+        .collect::<Vec<_>>();
+    if !links.is_empty() {
+        lines.push(links.join(" · "));
+    }
+    lines.join("\n")
+}
+
+/// The lines of push notifications, one per release, e.g.
+/// "Wintersun - Time II · Melodic Death Metal".
+fn push_lines(releases: &[&ReleaseWithArtists]) -> Vec<String> {
+    releases
+        .iter()
+        .map(|(release, _, _)| match &release.genre {
+            Some(genre) => format!("{} · {genre}", release_title(release)),
+            None => release_title(release),
+        })
+        .collect()
+}
+
+/// The calendar page of the day of the release on the website at `base_url`.
+fn day_url(base_url: &str, release: &Release) -> String {
+    format!(
+        "{}/calendar/{}",
+        base_url.trim_end_matches('/'),
+        release.date_string()
+    )
+}
+
+/// The SHA-256 of a secret of a channel, e.g. the URL of a Discord webhook,
+/// which tells the channels apart without revealing the secret.
+fn secret_digest(secret: &str) -> String {
+    hex::encode(Sha256::digest(secret.as_bytes()))
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Splits the blocks of a long message into the ranges of blocks sent as one
+/// message, each at most `max_len` long once its blocks are joined by a blank
+/// line. The length is measured by `len`, e.g. [`char_count`] or [`str::len`]
+/// for the services limiting the number of bytes. A block longer than
+/// `max_len` is sent on its own.
+fn split_message(blocks: &[String], max_len: usize, len: fn(&str) -> usize) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    let mut message_len = 0;
+
+    for (i, block) in blocks.iter().enumerate() {
+        let block_len = len(block);
+        if i > start && message_len + 2 + block_len > max_len {
+            ranges.push(start..i);
+            start = i;
+            message_len = 0;
+        }
+        message_len += if i > start { 2 + block_len } else { block_len };
+    }
+    if start < blocks.len() {
+        ranges.push(start..blocks.len());
+    }
+    ranges
+}
+
+/// The releases of the range of blocks of a message whose first block is
+/// the title of the day.
+fn release_range(blocks: &Range<usize>) -> Range<usize> {
+    blocks.start.saturating_sub(1)..blocks.end - 1
+}
+
+/// The number of characters of the text.
+fn char_count(text: &str) -> usize {
+    text.chars().count()
+}
+
+fn http_client() -> Result<reqwest::Client> {
+    Ok(reqwest::Client::builder()
+        .user_agent(USER_AGENT)
+        .timeout(TIMEOUT)
+        .build()?)
+}
+
+/// Posts a JSON body, failing when the server responds with an error.
+async fn post_json(
+    client: &reqwest::Client,
+    url: &str,
+    body: &serde_json::Value,
+) -> Result<reqwest::Response> {
+    send_json(client.post(url), body).await
+}
+
+/// Sends the request with a JSON body, failing when the server responds with an error.
+///
+/// The URLs of some channels hold their secret, e.g. the token of a Telegram
+/// bot, so the errors are stripped of the URL before being logged and
+/// stored in the outbox.
+async fn send_json(
+    req: reqwest::RequestBuilder,
+    body: &serde_json::Value,
+) -> Result<reqwest::Response> {
+    let res = req
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(body.to_string())
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(reqwest::Error::without_url)?;
+    Ok(res)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    use std::sync::{Arc, Mutex};
+
+    use axum::{
+        extract::State,
+        http::{header::AUTHORIZATION, HeaderMap, Method, Uri},
+        routing::any,
+        Router,
+    };
+    use tokio::net::TcpListener;
+
+    use crate::support::fixtures::release;
+
+    type Result<T> = core::result::Result<T, Box<dyn std::error::Error>>;
+
+    /// A request received by a stub.
+    #[derive(Debug)]
+    pub struct StubRequest {
+        pub method: Method,
+        /// The path and the query of the request.
+        pub path: String,
+        pub authorization: Option<String>,
+        pub body: serde_json::Value,
+    }
+
+    pub type Received = Arc<Mutex<Vec<StubRequest>>>;
+
+    /// Sends every message of the releases of October 18, 2024 to the channel.
+    pub async fn notify(notifier: &dyn Notifier, releases: &[ReleaseWithArtists]) -> Result<()> {
+        let releases = releases.iter().collect::<Vec<_>>();
+        for (i, message) in notifier
+            .messages("October 18, 2024", &releases)
+            .iter()
+            .enumerate()
+        {
+            notifier.send(message, &format!("txn-{i}")).await?;
+        }
+        Ok(())
+    }
+
+    /// Serves a stub accepting JSON bodies at any path on a local port, which
+    /// answers with the response. Returns its base URL and the requests received.
+    pub async fn serve_stub(response: &'static str) -> Result<(String, Received)> {
+        async fn receive(
+            State((received, response)): State<(Received, &'static str)>,
+            method: Method,
+            uri: Uri,
+            headers: HeaderMap,
+            body: String,
+        ) -> &'static str {
+            let body = serde_json::from_str(&body).unwrap_or(serde_json::Value::String(body));
+            received.lock().unwrap().push(StubRequest {
+                method,
+                path: uri.path_and_query().unwrap().to_string(),
+                authorization: headers
+                    .get(AUTHORIZATION)
+                    .and_then(|value| value.to_str().ok())
+                    .map(str::to_string),
+                body,
+            });
+            response
+        }
+
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let app = Router::new()
+            .fallback(any(receive))
+            .with_state((received.clone(), response));
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        Ok((format!("http://{addr}"), received))
+    }
+
+    #[test]
+    fn test_filter_matches_ok() -> Result<()> {
+        let releases = [
+            release(1, "Wintersun", "Time II")
+                .genre("Melodic Death Metal")
+                .build(),
+            release(2, "Ulthar / Pandiscordian Necrogenesis", "Helleborus")
+                .genre("Death Metal | Black Metal")
+                .build(),
+            release(3, "Opeth", "The Last Will and Testament").build(),
+        ];
+        let filters = [
+            NotifierFilter::default(),
+            NotifierFilter {
+                artists: vec!["ulthar".to_string(), "Opeth".to_string()],
+                genres: vec![],
+            },
+            NotifierFilter {
+                artists: vec![],
+                genres: vec!["black".to_string()],
+            },
+            NotifierFilter {
+                artists: vec!["Wintersun".to_string(), "Opeth".to_string()],
+                genres: vec!["death".to_string()],
+            },
+        ];
+
+        let got = filters
+            .iter()
+            .map(|filter| {
+                releases
+                    .iter()
+                    .filter(|release| filter.matches(release))
+                    .map(|(release, _, _)| release.id)
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        pretty_assertions::assert_eq!(got, vec![vec![1, 2, 3], vec![2, 3], vec![2], vec![1]]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_split_message_ok() -> Result<()> {
+        let blocks = ["aaaa", "bb", "cccccccccccc", "d", "e"].map(str::to_string);
+
+        let accented = ["éé", "éé"].map(str::to_string);
+
+        let got = split_message(&blocks, 10, char_count);
+
+        pretty_assertions::assert_eq!(got, vec![0..2, 2..3, 3..5]);
+        pretty_assertions::assert_eq!(split_message(&[], 10, char_count), vec![]);
+        pretty_assertions::assert_eq!(split_message(&accented, 6, char_count), vec![0..2]);
+        pretty_assertions::assert_eq!(split_message(&accented, 6, str::len), vec![0..1, 1..2]);
+        Ok(())
+    }
+}
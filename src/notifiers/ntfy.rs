@@ -0,0 +1,179 @@
+use axum::async_trait;
+use serde_json::json;
+
+use crate::{error::Result, model::ReleaseWithArtists};
+
+use super::{
+    day_url, push_lines, secret_digest, send_json, split_message, Message, Notifier, NotifierFilter,
+};
+
+/// The maximum number of bytes of a message. ntfy turns longer messages
+/// into attachments.
+const MAX_LEN: usize = 4096;
+
+/// The default priority of ntfy, from 1 (min) to 5 (max).
+const DEFAULT_PRIORITY: u8 = 3;
+
+pub fn default_server() -> String {
+    "https://ntfy.sh".to_string()
+}
+
+/// Publishes the releases to a topic of an [ntfy](https://docs.ntfy.sh/publish/#publish-as-json) server.
+pub struct NtfyNotifier {
+    client: reqwest::Client,
+    server: String,
+    topic: String,
+    token: Option<String>,
+    priority: u8,
+    tags: Vec<String>,
+    base_url: String,
+    filter: NotifierFilter,
+}
+
+impl NtfyNotifier {
+    /// Creates a notifier whose notifications open the calendar pages of the
+    /// website at `base_url`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        client: reqwest::Client,
+        server: String,
+        topic: String,
+        token: Option<String>,
+        priority: Option<u8>,
+        tags: Vec<String>,
+        base_url: String,
+        filter: NotifierFilter,
+    ) -> Self {
+        Self {
+            client,
+            server,
+            topic,
+            token,
+            priority: priority.unwrap_or(DEFAULT_PRIORITY),
+            tags,
+            base_url,
+            filter,
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for NtfyNotifier {
+    fn name(&self) -> &'static str {
+        "ntfy"
+    }
+
+    fn key(&self) -> String {
+        // Anyone knowing the name of a topic can read it, so the name is
+        // kept as secret as a password.
+        format!(
+            "ntfy:{}/{}",
+            self.server.trim_end_matches('/'),
+            secret_digest(&self.topic)
+        )
+    }
+
+    fn filter(&self) -> &NotifierFilter {
+        &self.filter
+    }
+
+    /// One line per release.
+    fn messages(&self, date: &str, releases: &[&ReleaseWithArtists]) -> Vec<Message> {
+        let lines = push_lines(releases);
+        let ranges = split_message(&lines, MAX_LEN, str::len);
+        let num_messages = ranges.len();
+
+        ranges
+            .into_iter()
+            .enumerate()
+            .map(|(i, range)| {
+                let mut title = format!("Heavy metal releases of {date}");
+                if num_messages > 1 {
+                    title.push_str(&format!(" ({}/{num_messages})", i + 1));
+                }
+                Message {
+                    releases: range.clone(),
+                    body: json!({
+                        "topic": self.topic,
+                        "title": title,
+                        "message": lines[range].join("\n"),
+                        "priority": self.priority,
+                        "tags": self.tags,
+                        "click": day_url(&self.base_url, &releases[0].0),
+                    }),
+                }
+            })
+            .collect()
+    }
+
+    async fn send(&self, message: &Message, _txn_id: &str) -> Result<()> {
+        let mut req = self.client.post(self.server.trim_end_matches('/'));
+        if let Some(token) = &self.token {
+            req = req.bearer_auth(token);
+        }
+        send_json(req, &message.body).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        notifiers::tests::{notify, serve_stub},
+        support::fixtures::release,
+    };
+
+    type Result<T> = core::result::Result<T, Box<dyn std::error::Error>>;
+
+    #[tokio::test]
+    async fn test_notify_ok() -> Result<()> {
+        let (base_url, received) = serve_stub(r#"{"id":"abc"}"#).await?;
+        let notifier = NtfyNotifier::new(
+            reqwest::Client::new(),
+            base_url,
+            "metal".to_string(),
+            Some("tk_abc".to_string()),
+            Some(4),
+            vec!["metal".to_string(), "guitar".to_string()],
+            "https://metal.example".to_string(),
+            NotifierFilter::default(),
+        );
+        let mut releases = vec![
+            release(1, "Wintersun", "Time II")
+                .genre("Melodic Death Metal")
+                .build(),
+            release(2, "Opeth", "The Last Will and Testament").build(),
+        ];
+        releases.extend((3..=200).map(|id| release(id, "Ulthar", "Helleborus").build()));
+
+        notify(&notifier, &releases).await?;
+
+        let received = received.lock().unwrap();
+        pretty_assertions::assert_eq!(received.len(), 2, "200 releases take two messages");
+        pretty_assertions::assert_eq!(received[0].path, "/");
+        pretty_assertions::assert_eq!(received[0].authorization.as_deref(), Some("Bearer tk_abc"));
+        let first = &received[0].body;
+        pretty_assertions::assert_eq!(first["topic"], "metal");
+        pretty_assertions::assert_eq!(
+            first["title"],
+            "Heavy metal releases of October 18, 2024 (1/2)"
+        );
+        assert!(first["message"].as_str().unwrap().starts_with(
+            "Wintersun - Time II · Melodic Death Metal\nOpeth - The Last Will and Testament\n"
+        ));
+        pretty_assertions::assert_eq!(first["priority"], 4);
+        pretty_assertions::assert_eq!(first["tags"], json!(["metal", "guitar"]));
+        pretty_assertions::assert_eq!(first["click"], "https://metal.example/calendar/2024-10-18");
+        assert!(received
+            .iter()
+            .all(|req| req.body["message"].as_str().unwrap().len() <= MAX_LEN));
+        pretty_assertions::assert_eq!(
+            received[1].body["title"],
+            "Heavy metal releases of October 18, 2024 (2/2)"
+        );
+        assert!(!notifier.key().ends_with("/metal"));
+        Ok(())
+    }
+}
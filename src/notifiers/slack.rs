@@ -0,0 +1,156 @@
+use axum::async_trait;
+use serde_json::{json, Value};
+
+use crate::{error::Result, model::ReleaseWithArtists};
+
+use super::{
+    link_list, post_json, release_title, secret_digest, Message, Notifier, NotifierFilter,
+};
+
+/// The maximum number of releases of a message. Slack accepts 50 blocks per message.
+const MAX_RELEASES: usize = 40;
+
+/// Posts the releases to a channel through a [Slack incoming webhook](https://api.slack.com/messaging/webhooks).
+pub struct SlackNotifier {
+    client: reqwest::Client,
+    url: String,
+    filter: NotifierFilter,
+}
+
+impl SlackNotifier {
+    pub fn new(client: reqwest::Client, url: String, filter: NotifierFilter) -> Self {
+        Self {
+            client,
+            url,
+            filter,
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for SlackNotifier {
+    fn name(&self) -> &'static str {
+        "slack"
+    }
+
+    fn key(&self) -> String {
+        // The URL of an incoming webhook is its secret.
+        format!("slack:{}", secret_digest(&self.url))
+    }
+
+    fn filter(&self) -> &NotifierFilter {
+        &self.filter
+    }
+
+    /// One section per release, each message starting with the title of
+    /// the day.
+    fn messages(&self, date: &str, releases: &[&ReleaseWithArtists]) -> Vec<Message> {
+        let title = format!("Heavy metal releases of {date}");
+
+        releases
+            .chunks(MAX_RELEASES)
+            .enumerate()
+            .map(|(i, chunk)| {
+                let mut blocks = vec![json!({
+                    "type": "header",
+                    "text": { "type": "plain_text", "text": title },
+                })];
+                blocks.extend(chunk.iter().map(|release| section(release)));
+                let start = i * MAX_RELEASES;
+                Message {
+                    releases: start..start + chunk.len(),
+                    body: json!({ "text": title, "blocks": blocks }),
+                }
+            })
+            .collect()
+    }
+
+    async fn send(&self, message: &Message, _txn_id: &str) -> Result<()> {
+        post_json(&self.client, &self.url, &message.body).await?;
+        Ok(())
+    }
+}
+
+fn section(release: &ReleaseWithArtists) -> Value {
+    let (release, _, links) = release;
+
+    let mut lines = vec![format!("*{}*", escape(&release_title(release)))];
+    if let Some(genre) = &release.genre {
+        lines.push(escape(genre));
+    }
+    let links = link_list(links)
+        .into_iter()
+        .map(|(kind, url)| format!("<{url}|{kind}>"))
+        .collect::<Vec<_>>();
+    if !links.is_empty() {
+        lines.push(links.join(" · "));
+    }
+
+    let mut section = json!({
+        "type": "section",
+        "text": { "type": "mrkdwn", "text": lines.join("\n") },
+    });
+    if let Some(url) = release.cover_url() {
+        section["accessory"] = json!({
+            "type": "image",
+            "image_url": url,
+            "alt_text": release_title(release),
+        });
+    }
+    section
+}
+
+/// Escapes the characters Slack uses for its links and mentions.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        notifiers::tests::{notify, serve_stub},
+        support::fixtures::release,
+    };
+
+    type Result<T> = core::result::Result<T, Box<dyn std::error::Error>>;
+
+    #[tokio::test]
+    async fn test_notify_ok() -> Result<()> {
+        let (base_url, received) = serve_stub("ok").await?;
+        let notifier = SlackNotifier::new(
+            reqwest::Client::new(),
+            format!("{base_url}/services/T/B/X"),
+            NotifierFilter::default(),
+        );
+        let releases = [release(1, "Mötley Crüe & Co", "<Demo>").build()];
+
+        notify(&notifier, &releases).await?;
+
+        let received = received.lock().unwrap();
+        pretty_assertions::assert_eq!(
+            received[0].body,
+            json!({
+                "text": "Heavy metal releases of October 18, 2024",
+                "blocks": [
+                    {
+                        "type": "header",
+                        "text": { "type": "plain_text", "text": "Heavy metal releases of October 18, 2024" },
+                    },
+                    {
+                        "type": "section",
+                        "text": {
+                            "type": "mrkdwn",
+                            "text": "*Mötley Crüe &amp; Co - &lt;Demo&gt;*\n<https://band1.bandcamp.com|Bandcamp>",
+                        },
+                    },
+                ],
+            })
+        );
+        assert!(!notifier.key().contains("T/B/X"));
+        Ok(())
+    }
+}
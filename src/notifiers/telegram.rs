@@ -0,0 +1,171 @@
+use axum::async_trait;
+use serde_json::json;
+
+use crate::{error::Result, model::ReleaseWithArtists};
+
+use super::{
+    char_count, post_json, release_html, release_range, split_message, Message, Notifier,
+    NotifierFilter,
+};
+
+/// The maximum number of characters of a Telegram message.
+const MAX_LEN: usize = 4096;
+
+pub fn default_base_url() -> String {
+    "https://api.telegram.org".to_string()
+}
+
+/// Sends the releases to a chat through the [Telegram Bot API](https://core.telegram.org/bots/api#sendmessage).
+pub struct TelegramNotifier {
+    client: reqwest::Client,
+    base_url: String,
+    token: String,
+    chat_id: String,
+    filter: NotifierFilter,
+}
+
+impl TelegramNotifier {
+    pub fn new(
+        client: reqwest::Client,
+        base_url: String,
+        token: String,
+        chat_id: String,
+        filter: NotifierFilter,
+    ) -> Self {
+        Self {
+            client,
+            base_url,
+            token,
+            chat_id,
+            filter,
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for TelegramNotifier {
+    fn name(&self) -> &'static str {
+        "telegram"
+    }
+
+    fn key(&self) -> String {
+        // The bot id is the part of the token before the colon.
+        let bot_id = self.token.split(':').next().unwrap_or_default();
+        format!("telegram:{bot_id}:{}", self.chat_id)
+    }
+
+    fn filter(&self) -> &NotifierFilter {
+        &self.filter
+    }
+
+    /// The title of the day is only given to the first message.
+    fn messages(&self, date: &str, releases: &[&ReleaseWithArtists]) -> Vec<Message> {
+        let mut blocks = vec![format!("<b>Heavy metal releases of {date}</b>")];
+        blocks.extend(releases.iter().map(|release| release_html(release)));
+
+        split_message(&blocks, MAX_LEN, char_count)
+            .into_iter()
+            .map(|range| Message {
+                releases: release_range(&range),
+                body: json!({
+                    "chat_id": self.chat_id,
+                    "text": blocks[range].join("\n\n"),
+                    "parse_mode": "HTML",
+                    "link_preview_options": { "is_disabled": true },
+                }),
+            })
+            .collect()
+    }
+
+    async fn send(&self, message: &Message, _txn_id: &str) -> Result<()> {
+        let url = format!(
+            "{}/bot{}/sendMessage",
+            self.base_url.trim_end_matches('/'),
+            self.token
+        );
+        post_json(&self.client, &url, &message.body).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        notifiers::tests::{notify, serve_stub},
+        support::fixtures::release,
+    };
+
+    type Result<T> = core::result::Result<T, Box<dyn std::error::Error>>;
+
+    #[tokio::test]
+    async fn test_notify_ok() -> Result<()> {
+        let (base_url, received) = serve_stub(r#"{"ok":true}"#).await?;
+        let notifier = TelegramNotifier::new(
+            reqwest::Client::new(),
+            base_url,
+            "123:abc".to_string(),
+            "-100123".to_string(),
+            NotifierFilter::default(),
+        );
+        let mut releases = vec![release(1, "Mötley Crüe & Co", "<Demo>")
+            .genre("Heavy Metal")
+            .build()];
+        releases
+            .extend((2..=60).map(|id| release(id, "Opeth", "The Last Will and Testament").build()));
+
+        notify(&notifier, &releases).await?;
+
+        let received = received.lock().unwrap();
+        pretty_assertions::assert_eq!(received.len(), 2, "60 releases take two messages");
+        pretty_assertions::assert_eq!(received[0].path, "/bot123:abc/sendMessage");
+        let text = received[0].body["text"].as_str().unwrap();
+        assert!(text.starts_with(
+            "<b>Heavy metal releases of October 18, 2024</b>\n\n\
+             <b>Mötley Crüe &amp; Co - &lt;Demo&gt;</b>\nHeavy Metal\n\
+             <a href=\"https://band1.bandcamp.com\">Bandcamp</a>\n\n"
+        ));
+        assert!(received
+            .iter()
+            .all(
+                |req| req.body["text"].as_str().unwrap().chars().count() <= MAX_LEN
+                    && req.body["chat_id"] == "-100123"
+                    && req.body["parse_mode"] == "HTML"
+            ));
+        assert!(!received[1].body["text"]
+            .as_str()
+            .unwrap()
+            .contains("Heavy metal releases"));
+        let ranges = notifier
+            .messages("October 18, 2024", &releases.iter().collect::<Vec<_>>())
+            .into_iter()
+            .map(|message| message.releases)
+            .collect::<Vec<_>>();
+        pretty_assertions::assert_eq!(ranges[0].start, 0);
+        pretty_assertions::assert_eq!(ranges[1].start, ranges[0].end);
+        pretty_assertions::assert_eq!(ranges[1].end, 60);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_notify_error_hides_token_ok() -> Result<()> {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let base_url = format!("http://{}", listener.local_addr()?);
+        drop(listener);
+        let notifier = TelegramNotifier::new(
+            reqwest::Client::new(),
+            base_url,
+            "123:abc".to_string(),
+            "-100123".to_string(),
+            NotifierFilter::default(),
+        );
+        let releases = [release(1, "Wintersun", "Time II").build()];
+
+        let got = notify(&notifier, &releases).await;
+
+        let err = got.err().ok_or("the server is down")?.to_string();
+        assert!(!err.contains("123:abc"), "{err}");
+        Ok(())
+    }
+}
@@ -0,0 +1,160 @@
+use reqwest::Url;
+use scraper::{Html, Selector};
+
+use crate::{error::Result, support::names::similarity};
+
+use super::client::Client;
+
+/// The minimum confidence for a Bandcamp page to be attributed to an artist.
+pub const MIN_CONFIDENCE: f32 = 0.85;
+
+/// The number of search results considered.
+const MAX_CANDIDATES: usize = 3;
+
+/// A Bandcamp page attributed to an artist.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BandcampMatch {
+    pub url: Url,
+    /// How closely the band name on the page matches the artist's name,
+    /// from 0 to 1.
+    pub confidence: f32,
+}
+
+/// Finds the Bandcamp page of the artist.
+///
+/// The artist is looked up with Bandcamp's band search. The closest results
+/// are then visited in order, and the first whose band name matches the
+/// artist's name is kept. An error is returned only when the search itself
+/// fails, so that the artist can be checked again later.
+pub async fn discover(client: &impl Client, artist: &str) -> Result<Option<BandcampMatch>> {
+    let mut query = String::new();
+    url_escape::encode_component_to_string(artist, &mut query);
+    let search = client
+        .get_text(&format!(
+            "https://bandcamp.com/search?q={query}&item_type=b"
+        ))
+        .await?;
+
+    let mut candidates = search_results(&search)
+        .into_iter()
+        .map(|(name, url)| (similarity(artist, &name), url))
+        .filter(|(score, _)| *score >= MIN_CONFIDENCE)
+        .collect::<Vec<_>>();
+    candidates.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+
+    for (_, url) in candidates.into_iter().take(MAX_CANDIDATES) {
+        let Ok(page) = client.get_text(url.as_str()).await else {
+            continue;
+        };
+        let Some(name) = band_name(&page) else {
+            continue;
+        };
+
+        let confidence = similarity(artist, &name);
+        if confidence >= MIN_CONFIDENCE {
+            return Ok(Some(BandcampMatch { url, confidence }));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Extracts the name and the URL of the bands listed on a search page.
+fn search_results(html: &str) -> Vec<(String, Url)> {
+    let document = Html::parse_document(html);
+    let result_selector = Selector::parse("li.searchresult .result-info").unwrap();
+    let heading_selector = Selector::parse(".heading a").unwrap();
+    let url_selector = Selector::parse(".itemurl a").unwrap();
+
+    document
+        .select(&result_selector)
+        .filter_map(|result| {
+            let name = result
+                .select(&heading_selector)
+                .next()?
+                .text()
+                .collect::<String>();
+            let url = result
+                .select(&url_selector)
+                .next()?
+                .text()
+                .collect::<String>();
+            let mut url = Url::parse(url.trim()).ok()?;
+            url.set_query(None);
+            Some((name.trim().to_string(), url))
+        })
+        .collect()
+}
+
+/// Extracts the band name shown on a Bandcamp page.
+fn band_name(html: &str) -> Option<String> {
+    let document = Html::parse_document(html);
+    let site_name_selector = Selector::parse(r#"meta[property="og:site_name"]"#).unwrap();
+    let title_selector = Selector::parse("#band-name-location .title").unwrap();
+
+    document
+        .select(&site_name_selector)
+        .next()
+        .and_then(|meta| meta.attr("content").map(str::to_string))
+        .or_else(|| {
+            document
+                .select(&title_selector)
+                .next()
+                .map(|title| title.text().collect::<String>())
+        })
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::scraper::client::tests::MockClient;
+
+    type Result<T> = core::result::Result<T, Box<dyn std::error::Error>>;
+
+    const SEARCH_URL: &str = "https://bandcamp.com/search?q=Wintersun&item_type=b";
+
+    #[tokio::test]
+    async fn test_discover_ok() -> Result<()> {
+        let client = MockClient::new()
+            .with_fixture(SEARCH_URL, "bandcamp_search.html")
+            .with_fixture("https://wintersun.bandcamp.com/", "bandcamp_album.html");
+
+        let got = discover(&client, "Wintersun").await?;
+
+        pretty_assertions::assert_eq!(
+            got,
+            Some(BandcampMatch {
+                url: Url::parse("https://wintersun.bandcamp.com")?,
+                confidence: 1.0,
+            })
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_discover_unverified_ok() -> Result<()> {
+        let client = MockClient::new()
+            .with_fixture(SEARCH_URL, "bandcamp_search.html")
+            .with_fixture(
+                "https://wintersun.bandcamp.com/",
+                "bandcamp_other_band.html",
+            );
+
+        let got = discover(&client, "Wintersun").await?;
+
+        pretty_assertions::assert_eq!(got, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_similarity_ok() -> Result<()> {
+        pretty_assertions::assert_eq!(similarity("Guns N' Roses", "guns n roses"), 1.0);
+        pretty_assertions::assert_eq!(similarity("Wintersun", "Ghost"), 0.0);
+        assert!(similarity("Wintersun", "Wintersun Tribute") < MIN_CONFIDENCE);
+        assert!(similarity("Blood Incantation", "Blood Incantations") >= MIN_CONFIDENCE);
+        Ok(())
+    }
+}
@@ -1,114 +1,403 @@
+use std::time::Duration;
+
 use axum::async_trait;
-use reqwest::Url;
-use scraper::Html;
+use reqwest::{
+    header::{HeaderMap, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, RETRY_AFTER},
+    Response, StatusCode,
+};
+use tracing::{info, warn};
+
+use crate::{
+    config::config,
+    error::{Error, Result},
+    model::{PageSnapshot, SnapshotBmc},
+};
+
+use super::{
+    rate_limit::RateLimiter,
+    replay::{RecordingClient, ReplayClient},
+    wiki,
+};
+
+/// The User-Agent sent with every request, as asked of bots by Wikipedia and MusicBrainz.
+pub const USER_AGENT: &str = concat!(
+    "heavy-metal-notifier/",
+    env!("CARGO_PKG_VERSION"),
+    " ( https://github.com/reaper47/heavy-metal-notifier )"
+);
+
+/// The number of requests per second sent to a host once its burst is spent.
+const REQUESTS_PER_SECOND: f64 = 2.0;
+
+/// The number of requests that can be sent to a host at once.
+const REQUESTS_BURST: u32 = 4;
+
+/// The time allowed to connect to a host.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
 
-use crate::error::Result;
+/// The time allowed between two reads of a response.
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
 
-pub struct MainClient;
+/// The number of times a request is sent again after a server error.
+const MAX_RETRIES: u32 = 3;
+
+/// The time waited before the first retry, doubled on every retry.
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(1);
+
+/// The longest time waited before a retry, even when the server asks for more.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// Sends the requests of the application through a shared HTTP client.
+///
+/// Requests are rate limited per host, and retried with an exponential
+/// backoff when the server is unavailable or asks to slow down.
+pub struct MainClient {
+    http: reqwest::Client,
+    rate_limiter: RateLimiter,
+}
 
 impl MainClient {
-    pub fn new() -> Self {
-        Self {}
+    /// Creates a client going through the proxy at `PROXY_URL`, if any.
+    pub fn new() -> Result<Self> {
+        Self::with_proxy(config().PROXY_URL.as_deref())
+    }
+
+    pub fn with_proxy(proxy: Option<&str>) -> Result<Self> {
+        let mut builder = reqwest::Client::builder()
+            .user_agent(USER_AGENT)
+            .connect_timeout(CONNECT_TIMEOUT)
+            .read_timeout(READ_TIMEOUT);
+        if let Some(proxy) = proxy {
+            builder = builder.proxy(reqwest::Proxy::all(proxy)?);
+        }
+
+        Ok(Self {
+            http: builder.build()?,
+            rate_limiter: RateLimiter::new(REQUESTS_PER_SECOND, REQUESTS_BURST),
+        })
+    }
+
+    /// Sends a GET request, retrying it up to [`MAX_RETRIES`] times on
+    /// timeouts, server errors and `429 Too Many Requests`.
+    async fn get(&self, url: &str, headers: HeaderMap) -> Result<Response> {
+        let mut attempt = 0;
+        loop {
+            self.rate_limiter.acquire(url).await;
+
+            let result = self.http.get(url).headers(headers.clone()).send().await;
+            let retry_after = match &result {
+                Ok(res) if is_retryable(res.status()) => retry_after(res),
+                Err(err) if err.is_timeout() => None,
+                _ => return Ok(result?.error_for_status()?),
+            };
+
+            if attempt == MAX_RETRIES {
+                return Ok(result?.error_for_status()?);
+            }
+
+            let delay = retry_delay(attempt, retry_after);
+            warn!("Retrying {url} in {delay:?}");
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    /// Archives the page of the response in `page_snapshots`, along with its validators.
+    async fn archive(&self, url: &str, res: Response) -> Result<String> {
+        let header = |name| {
+            res.headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let (etag, last_modified) = (header(ETAG), header(LAST_MODIFIED));
+        let body = res.text().await?;
+        SnapshotBmc::create(url, &body, etag.as_deref(), last_modified.as_deref())?;
+        Ok(body)
+    }
+}
+
+/// The client chosen by the configuration: live, recording the responses
+/// in `RECORD_DIR`, or replaying the responses recorded in `OFFLINE_DIR`.
+pub enum AppClient {
+    Live(MainClient),
+    Record(RecordingClient),
+    Replay(ReplayClient),
+}
+
+impl AppClient {
+    /// Creates the client of the configuration. Replaying takes precedence over recording.
+    pub fn new() -> Result<Self> {
+        if let Some(dir) = &config().OFFLINE_DIR {
+            return Ok(Self::Replay(ReplayClient::open(dir)?));
+        }
+
+        let client = MainClient::new()?;
+        match &config().RECORD_DIR {
+            Some(dir) => Ok(Self::Record(RecordingClient::open(dir, client)?)),
+            None => Ok(Self::Live(client)),
+        }
+    }
+
+    fn inner(&self) -> &(dyn Client + Send + Sync) {
+        match self {
+            Self::Live(client) => client,
+            Self::Record(client) => client,
+            Self::Replay(client) => client,
+        }
     }
 }
 
 #[async_trait]
 pub trait Client {
     async fn get_calendar(&self, year: i32) -> Result<scraper::Html>;
-    async fn get_bandcamp_link(&self, artist: String) -> Option<Url>;
+    /// Fetches the body of an arbitrary page, e.g. a JSON API response.
+    async fn get_text(&self, url: &str) -> Result<String>;
+    /// Fetches a binary resource, e.g. an image.
+    async fn get_bytes(&self, url: &str) -> Result<Vec<u8>>;
+
+    /// Fetches a page whose content changes under a stable URL, e.g. the
+    /// latest revision of a Wikipedia page.
+    ///
+    /// Unlike [`Client::get_text`], the page may be archived and requested
+    /// only if it changed since.
+    async fn get_page(&self, url: &str) -> Result<String> {
+        self.get_text(url).await
+    }
+
+    /// Fetches a page releases are scraped from, whose content never
+    /// changes under its URL, e.g. a revision of a Wikipedia page.
+    ///
+    /// Unlike [`Client::get_text`], the page may be archived so that past
+    /// scrapes can be reproduced, and is not fetched again once archived.
+    async fn get_archived_page(&self, url: &str) -> Result<String> {
+        self.get_text(url).await
+    }
 }
 
 #[async_trait]
 impl Client for MainClient {
     async fn get_calendar(&self, year: i32) -> Result<scraper::Html> {
-        let url = format!("https://en.wikipedia.org/wiki/{year}_in_heavy_metal_music");
-        let res = reqwest::get(url).await?;
-        let text = res.text().await?;
-        Ok(Html::parse_document(text.as_str()))
-    }
-
-    async fn get_bandcamp_link(&self, artist: String) -> Option<Url> {
-        let artist = artist
-            .to_lowercase()
-            .replace(":", "")
-            .split_whitespace()
-            .collect::<String>();
-        let url = format!("https://{artist}.bandcamp.com");
-        let res = match reqwest::get(&url).await {
-            Ok(res) => {
-                let req_url = res.url().path();
-                let req_host = res.url().host_str().unwrap_or("");
-                if req_url != "/signup" || req_host == format!("{}.bandcamp.com", artist) {
-                    Some(Url::parse(&url).unwrap())
-                } else {
-                    None
-                }
-            }
-            Err(_) => None,
-        };
-        std::thread::sleep(std::time::Duration::from_millis(200));
-        res
+        let revision = wiki::latest_revision(self, year).await?;
+        wiki::parse_revision(self, revision).await
+    }
+
+    async fn get_text(&self, url: &str) -> Result<String> {
+        Ok(self.get(url, HeaderMap::new()).await?.text().await?)
+    }
+
+    async fn get_bytes(&self, url: &str) -> Result<Vec<u8>> {
+        Ok(self
+            .get(url, HeaderMap::new())
+            .await?
+            .bytes()
+            .await?
+            .to_vec())
+    }
+
+    /// Sends a conditional request validated by the latest snapshot of the
+    /// page, and archives the page in `page_snapshots` when it changed.
+    async fn get_page(&self, url: &str) -> Result<String> {
+        let latest = SnapshotBmc::get_latest(url)?;
+
+        let res = self.get(url, conditional_headers(latest.as_ref())).await?;
+        if res.status() == StatusCode::NOT_MODIFIED {
+            let latest = latest.ok_or_else(|| Error::UnexpectedNotModified(url.to_string()))?;
+            info!("{url} is unchanged since its snapshot {}", latest.id);
+            return Ok(latest.body);
+        }
+
+        self.archive(url, res).await
     }
+
+    /// Serves the page from its snapshot, or fetches it and archives it in
+    /// `page_snapshots` when it was never fetched.
+    async fn get_archived_page(&self, url: &str) -> Result<String> {
+        if let Some(snapshot) = SnapshotBmc::get_latest(url)? {
+            info!("{url} is served from its snapshot {}", snapshot.id);
+            return Ok(snapshot.body);
+        }
+
+        let res = self.get(url, HeaderMap::new()).await?;
+        if res.status() == StatusCode::NOT_MODIFIED {
+            return Err(Error::UnexpectedNotModified(url.to_string()));
+        }
+        self.archive(url, res).await
+    }
+}
+
+#[async_trait]
+impl Client for AppClient {
+    async fn get_calendar(&self, year: i32) -> Result<scraper::Html> {
+        self.inner().get_calendar(year).await
+    }
+
+    async fn get_text(&self, url: &str) -> Result<String> {
+        self.inner().get_text(url).await
+    }
+
+    async fn get_bytes(&self, url: &str) -> Result<Vec<u8>> {
+        self.inner().get_bytes(url).await
+    }
+
+    async fn get_page(&self, url: &str) -> Result<String> {
+        self.inner().get_page(url).await
+    }
+
+    async fn get_archived_page(&self, url: &str) -> Result<String> {
+        self.inner().get_archived_page(url).await
+    }
+}
+
+/// The headers asking the server to send the page only if it changed since the snapshot.
+fn conditional_headers(snapshot: Option<&PageSnapshot>) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    let Some(snapshot) = snapshot else {
+        return headers;
+    };
+
+    if let Some(etag) = snapshot.etag.as_ref().and_then(|v| v.parse().ok()) {
+        headers.insert(IF_NONE_MATCH, etag);
+    }
+    if let Some(last_modified) = snapshot.last_modified.as_ref().and_then(|v| v.parse().ok()) {
+        headers.insert(IF_MODIFIED_SINCE, last_modified);
+    }
+    headers
+}
+
+/// Whether the request may succeed when sent again later.
+fn is_retryable(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+/// The delay asked by the server in the `Retry-After` header, in seconds.
+fn retry_after(res: &Response) -> Option<Duration> {
+    res.headers()
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+/// The time to wait before the retry following the given attempt, counted from 0:
+/// the delay asked by the server if any, and an exponential backoff otherwise.
+fn retry_delay(attempt: u32, retry_after: Option<Duration>) -> Duration {
+    retry_after
+        .unwrap_or_else(|| RETRY_BASE_DELAY * 2u32.pow(attempt))
+        .min(MAX_RETRY_DELAY)
 }
 
 #[cfg(test)]
 pub mod tests {
-    use std::{fs, io::Write, path::PathBuf};
-
-    use crate::{calendar::Calendar, error::Error, scraper::wiki::scrape};
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
 
     use super::*;
 
-    #[cfg(test)]
-    pub struct MockClient;
+    /// Replays the responses of `tests/testdata`, registered with
+    /// [`ReplayClient::with_fixture`] or recorded with `--record-dir tests/testdata`.
+    pub type MockClient = ReplayClient;
 
-    #[cfg(test)]
-    impl MockClient {
+    impl ReplayClient {
         pub fn new() -> Self {
-            Self {}
-        }
-
-        pub async fn scrape(&self, year: i32) -> Result<Calendar> {
-            scrape(self, year).await
+            Self::open("./tests/testdata").unwrap()
         }
     }
 
-    #[cfg(test)]
-    #[async_trait]
-    impl Client for MockClient {
-        async fn get_calendar(&self, year: i32) -> Result<scraper::Html> {
-            let path = PathBuf::from(format!("./tests/testdata/test_{year}.html"));
-
-            let content = match fs::read_to_string(&path) {
-                Ok(content) => content,
-                Err(_) => {
-                    let url = format!("https://en.wikipedia.org/wiki/{year}_in_heavy_metal_music");
-                    match reqwest::get(url).await {
-                        Ok(res) => {
-                            let mut file = fs::File::create(path)?;
-                            let content = res.text().await?;
-                            if let Err(err) = file.write(&content.as_bytes()) {
-                                return Err(Error::Io(err));
-                            }
-                            content
-                        }
-                        Err(_) => return Err(Error::RequestFail),
-                    }
-                }
-            };
+    type TestResult<T> = core::result::Result<T, Box<dyn std::error::Error>>;
 
-            Ok(Html::parse_document(&content))
+    /// Serves a page that is unavailable twice before answering, and a page
+    /// that does not exist, on a local port. Returns the base URL and the
+    /// number of requests received.
+    async fn serve_flaky() -> TestResult<(String, Arc<AtomicUsize>)> {
+        use axum::{
+            extract::State, http::StatusCode, response::IntoResponse, routing::get, Router,
+        };
+
+        async fn flaky(State(hits): State<Arc<AtomicUsize>>) -> impl IntoResponse {
+            if hits.fetch_add(1, Ordering::SeqCst) < 2 {
+                (StatusCode::SERVICE_UNAVAILABLE, [("retry-after", "0")], "").into_response()
+            } else {
+                "Heavy metal".into_response()
+            }
         }
 
-        async fn get_bandcamp_link(&self, artist: String) -> Option<Url> {
-            let artist = artist
-                .to_lowercase()
-                .replace(":", "")
-                .split_whitespace()
-                .collect::<String>();
-            let url = format!("https://{artist}.bandcamp.com");
-            println!("{url}");
-            Some(Url::parse(&url).unwrap())
+        async fn missing(State(hits): State<Arc<AtomicUsize>>) -> StatusCode {
+            hits.fetch_add(1, Ordering::SeqCst);
+            StatusCode::NOT_FOUND
         }
+
+        let hits = Arc::new(AtomicUsize::new(0));
+        let router = Router::new()
+            .route("/flaky", get(flaky))
+            .route("/missing", get(missing))
+            .with_state(hits.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(async move { axum::serve(listener, router).await });
+        Ok((format!("http://{addr}"), hits))
+    }
+
+    #[tokio::test]
+    async fn test_get_text_retries_ok() -> TestResult<()> {
+        let (base_url, hits) = serve_flaky().await?;
+        let client = MainClient::with_proxy(None)?;
+
+        let got = client.get_text(&format!("{base_url}/flaky")).await?;
+
+        pretty_assertions::assert_eq!(got, "Heavy metal");
+        pretty_assertions::assert_eq!(hits.load(Ordering::SeqCst), 3);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_text_not_retried_ok() -> TestResult<()> {
+        let (base_url, hits) = serve_flaky().await?;
+        let client = MainClient::with_proxy(None)?;
+
+        let got = client.get_text(&format!("{base_url}/missing")).await;
+
+        assert!(got.is_err());
+        pretty_assertions::assert_eq!(hits.load(Ordering::SeqCst), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_conditional_headers_ok() -> TestResult<()> {
+        let snapshot = PageSnapshot {
+            id: 1,
+            url: "https://en.wikipedia.org/w/api.php".to_string(),
+            body: String::new(),
+            etag: Some("\"v1\"".to_string()),
+            last_modified: Some("Fri, 18 Oct 2026 21:00:00 GMT".to_string()),
+            fetched_at: 0,
+        };
+
+        let got = conditional_headers(Some(&snapshot));
+
+        pretty_assertions::assert_eq!(got[IF_NONE_MATCH], "\"v1\"");
+        pretty_assertions::assert_eq!(got[IF_MODIFIED_SINCE], "Fri, 18 Oct 2026 21:00:00 GMT");
+        assert!(conditional_headers(None).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_retry_delay_ok() -> TestResult<()> {
+        pretty_assertions::assert_eq!(retry_delay(0, None), Duration::from_secs(1));
+        pretty_assertions::assert_eq!(retry_delay(2, None), Duration::from_secs(4));
+        pretty_assertions::assert_eq!(
+            retry_delay(0, Some(Duration::from_secs(5))),
+            Duration::from_secs(5)
+        );
+        pretty_assertions::assert_eq!(
+            retry_delay(0, Some(Duration::from_secs(3600))),
+            MAX_RETRY_DELAY
+        );
+        Ok(())
     }
 }
@@ -0,0 +1,219 @@
+use std::path::Path;
+
+use image::ImageFormat;
+use scraper::{Html, Selector};
+use tracing::warn;
+
+use crate::{
+    calendar::{LinkKind, Release},
+    error::Result,
+};
+
+use super::client::Client;
+
+/// The maximum width and height of a thumbnail, in pixels.
+pub const THUMBNAIL_SIZE: u32 = 250;
+
+/// Fetches the cover art of the release and stores its thumbnail in the folder.
+///
+/// The cover comes from the [Cover Art Archive](https://coverartarchive.org) when the
+/// release is known to MusicBrainz, otherwise from the `og:image` of its Bandcamp page.
+/// Thumbnails already in the folder are not fetched again. Returns the file name
+/// of the thumbnail, or `None` when no cover could be found.
+pub async fn fetch_cover(
+    client: &impl Client,
+    release: &Release,
+    folder: &Path,
+) -> Result<Option<String>> {
+    let file_name = cover_file_name(release);
+    let path = folder.join(&file_name);
+    if path.exists() {
+        return Ok(Some(file_name));
+    }
+
+    for url in cover_urls(client, release).await {
+        let Ok(bytes) = client.get_bytes(&url).await else {
+            continue;
+        };
+
+        // Decoding and resizing the image keep the thread busy a while.
+        let thumbnail = tokio::task::spawn_blocking(move || {
+            image::load_from_memory(&bytes)
+                .map(|image| image.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE).to_rgb8())
+        })
+        .await?;
+        let thumbnail = match thumbnail {
+            Ok(thumbnail) => thumbnail,
+            Err(err) => {
+                warn!("Skipping the cover at {url}: {err}");
+                continue;
+            }
+        };
+        std::fs::create_dir_all(folder)?;
+        let path = path.clone();
+        tokio::task::spawn_blocking(move || thumbnail.save_with_format(path, ImageFormat::Jpeg))
+            .await??;
+        return Ok(Some(file_name));
+    }
+
+    Ok(None)
+}
+
+/// The file name of the release's thumbnail, e.g. `wintersun-time-ii.jpg`.
+///
+/// Releases are recreated on every calendar update, so the name is derived
+/// from the release group when known, and from the artist and album otherwise.
+pub fn cover_file_name(release: &Release) -> String {
+    let stem = match &release.musicbrainz {
+        Some(musicbrainz) => musicbrainz.release_group_id.clone(),
+        None => slug(&format!("{} {}", release.artist, release.album)),
+    };
+    format!("{stem}.jpg")
+}
+
+/// The URLs the cover of the release may be found at, in order of preference.
+async fn cover_urls(client: &impl Client, release: &Release) -> Vec<String> {
+    let mut urls = Vec::new();
+
+    if let Some(musicbrainz) = &release.musicbrainz {
+        urls.push(format!(
+            "https://coverartarchive.org/release-group/{}/front-500",
+            musicbrainz.release_group_id
+        ));
+    }
+
+    for link in release.links.iter() {
+        if link.kind == LinkKind::Bandcamp {
+            if let Ok(text) = client.get_text(link.url.as_str()).await {
+                urls.extend(og_image(&text));
+            }
+        }
+    }
+
+    urls
+}
+
+/// Extracts the `og:image` meta property of an HTML page.
+fn og_image(html: &str) -> Option<String> {
+    let document = Html::parse_document(html);
+    let selector = Selector::parse(r#"meta[property="og:image"]"#).unwrap();
+
+    document
+        .select(&selector)
+        .next()
+        .and_then(|meta| meta.attr("content"))
+        .map(str::to_string)
+}
+
+/// Reduces a name to lowercase letters and digits separated by dashes.
+fn slug(name: &str) -> String {
+    name.chars()
+        .flat_map(char::to_lowercase)
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use reqwest::Url;
+
+    use crate::{
+        calendar::{Link, MusicBrainzRelease},
+        scraper::client::tests::MockClient,
+    };
+
+    type Result<T> = core::result::Result<T, Box<dyn std::error::Error>>;
+
+    #[test]
+    fn test_cover_file_name_ok() -> Result<()> {
+        let release = Release::new("Guns N' Roses", "Hard Skool");
+        pretty_assertions::assert_eq!(cover_file_name(&release), "guns-n-roses-hard-skool.jpg");
+
+        let mut release = Release::new("Wintersun", "Time II");
+        release.musicbrainz = Some(MusicBrainzRelease {
+            release_group_id: "9d8b5c4e-3b1a-4b8e-9f6a-2d3c4b5a6f70".to_string(),
+            ..Default::default()
+        });
+        pretty_assertions::assert_eq!(
+            cover_file_name(&release),
+            "9d8b5c4e-3b1a-4b8e-9f6a-2d3c4b5a6f70.jpg"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_fetch_cover_from_bandcamp_ok() -> Result<()> {
+        let client = MockClient::new()
+            .with_fixture("https://wintersun.bandcamp.com/", "bandcamp_album.html")
+            .with_fixture("https://f4.bcbits.com/img/a0123456789_5.jpg", "cover.png");
+        let mut release = Release::new("Wintersun", "Time II");
+        release.musicbrainz = Some(MusicBrainzRelease {
+            release_group_id: "9d8b5c4e-3b1a-4b8e-9f6a-2d3c4b5a6f70".to_string(),
+            ..Default::default()
+        });
+        release.links.push(Link::new(
+            LinkKind::Bandcamp,
+            Url::parse("https://wintersun.bandcamp.com")?,
+        ));
+        let folder = std::env::temp_dir().join(format!("covers-{}", std::process::id()));
+
+        let got = fetch_cover(&client, &release, &folder).await?;
+
+        let file_name = "9d8b5c4e-3b1a-4b8e-9f6a-2d3c4b5a6f70.jpg";
+        pretty_assertions::assert_eq!(got, Some(file_name.to_string()));
+        let thumbnail = image::open(folder.join(file_name))?;
+        pretty_assertions::assert_eq!(
+            (thumbnail.width(), thumbnail.height()),
+            (THUMBNAIL_SIZE, 200)
+        );
+        std::fs::remove_dir_all(folder)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_fetch_cover_skips_undecodable_ok() -> Result<()> {
+        let client = MockClient::new()
+            .with_fixture(
+                "https://coverartarchive.org/release-group/9d8b5c4e-3b1a-4b8e-9f6a-2d3c4b5a6f70/front-500",
+                "bandcamp_other_band.html",
+            )
+            .with_fixture("https://wintersun.bandcamp.com/", "bandcamp_album.html")
+            .with_fixture("https://f4.bcbits.com/img/a0123456789_5.jpg", "cover.png");
+        let mut release = Release::new("Wintersun", "Time II");
+        release.musicbrainz = Some(MusicBrainzRelease {
+            release_group_id: "9d8b5c4e-3b1a-4b8e-9f6a-2d3c4b5a6f70".to_string(),
+            ..Default::default()
+        });
+        release.links.push(Link::new(
+            LinkKind::Bandcamp,
+            Url::parse("https://wintersun.bandcamp.com")?,
+        ));
+        let folder = std::env::temp_dir().join(format!("covers-skip-{}", std::process::id()));
+
+        let got = fetch_cover(&client, &release, &folder).await?;
+
+        pretty_assertions::assert_eq!(
+            got,
+            Some("9d8b5c4e-3b1a-4b8e-9f6a-2d3c4b5a6f70.jpg".to_string()),
+            "the Bandcamp cover is used when the archived one cannot be decoded"
+        );
+        std::fs::remove_dir_all(folder)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_fetch_cover_none_ok() -> Result<()> {
+        let client = MockClient::new();
+        let release = Release::new("Unknown", "Nothing");
+
+        let got = fetch_cover(&client, &release, &std::env::temp_dir()).await?;
+
+        pretty_assertions::assert_eq!(got, None);
+        Ok(())
+    }
+}
@@ -0,0 +1,118 @@
+use std::path::PathBuf;
+
+use axum::async_trait;
+use serde::Deserialize;
+use time::{Date, Month};
+use tracing::warn;
+
+use crate::{
+    calendar::{Calendar, Release, ReleaseType},
+    error::Result,
+};
+
+use super::source::ReleaseSource;
+
+/// Lists the releases of a CSV file maintained by hand, e.g. to add
+/// user-submitted releases missing from the other sources.
+///
+/// The file has a header row and the columns `date`, `artist`, `album` and
+/// an optional `type`. Dates are in the `YYYY-MM-DD` format.
+pub struct CsvSource {
+    path: PathBuf,
+}
+
+impl CsvSource {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+#[derive(Deserialize)]
+struct Row {
+    date: String,
+    artist: String,
+    album: String,
+    #[serde(rename = "type", default)]
+    release_type: Option<String>,
+}
+
+#[async_trait]
+impl ReleaseSource for CsvSource {
+    fn name(&self) -> &'static str {
+        "csv"
+    }
+
+    async fn calendar(&self, year: i32) -> Result<Calendar> {
+        let mut calendar = Calendar::new(year);
+        let mut reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_path(&self.path)?;
+
+        for row in reader.deserialize::<Row>() {
+            let row = row?;
+            let Some(date) = parse_date(&row.date) else {
+                warn!(
+                    "Skipping {} - {}: invalid date `{}`",
+                    row.artist, row.album, row.date
+                );
+                continue;
+            };
+            if date.year() != year {
+                continue;
+            }
+
+            let release_type = row
+                .release_type
+                .as_deref()
+                .filter(|s| !s.is_empty())
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(ReleaseType::Album);
+
+            calendar.add_release(
+                date.month(),
+                date.day(),
+                Release::new(row.artist, row.album).with_release_type(release_type),
+            );
+        }
+
+        Ok(calendar)
+    }
+}
+
+/// Parses a date in the `YYYY-MM-DD` format.
+fn parse_date(date: &str) -> Option<Date> {
+    let mut parts = date.splitn(3, '-');
+    let year = parts.next()?.parse().ok()?;
+    let month: u8 = parts.next()?.parse().ok()?;
+    let day = parts.next()?.parse().ok()?;
+    Date::from_calendar_date(year, Month::try_from(month).ok()?, day).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Result<T> = core::result::Result<T, Box<dyn std::error::Error>>;
+
+    #[tokio::test]
+    async fn test_csv_releases_ok() -> Result<()> {
+        let source = CsvSource::new("tests/testdata/releases.csv");
+
+        let got = source.calendar(2024).await?;
+
+        let mut want = Calendar::new(2024);
+        want.add_release(
+            Month::January,
+            12,
+            Release::new("Hanabie.", "Reborn Superstar!"),
+        );
+        want.add_release(
+            Month::June,
+            7,
+            Release::new("Grey Aura", "Waar de Wilde Mensen Wonen")
+                .with_release_type(ReleaseType::Ep),
+        );
+        pretty_assertions::assert_eq!(got, want);
+        Ok(())
+    }
+}
@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+
+use axum::async_trait;
+use reqwest::Url;
+use tracing::warn;
+
+use crate::{
+    calendar::{LinkKind, Release},
+    config::config,
+    model::{LinkBmc, ModelManager},
+};
+
+/// Generates a kind of link for releases, e.g. a Spotify search.
+#[async_trait]
+pub trait LinkProvider: Send + Sync {
+    fn kind(&self) -> LinkKind;
+
+    /// The link of the release, or `None` when the provider has none.
+    async fn link(&self, release: &Release) -> Option<Url>;
+}
+
+/// Creates the link providers enabled in the `LINK_PROVIDERS` configuration,
+/// in the order their links are shown.
+pub fn providers_from_config() -> Vec<Box<dyn LinkProvider>> {
+    let mut providers: Vec<Box<dyn LinkProvider>> = Vec::new();
+
+    for name in config().LINK_PROVIDERS.iter() {
+        match name.parse::<LinkKind>() {
+            Ok(LinkKind::Bandcamp) => providers.push(Box::new(BandcampProvider)),
+            Ok(LinkKind::LabelShop) => providers.push(Box::new(LabelShopProvider::new(
+                config().LABEL_SHOPS.clone(),
+            ))),
+            Ok(kind) => match SearchProvider::new(kind) {
+                Some(provider) => providers.push(Box::new(provider)),
+                None => warn!("No search provider for `{name}` links"),
+            },
+            Err(err) => warn!("Unknown link provider: {err}"),
+        }
+    }
+
+    providers
+}
+
+/// Links to the search page of a website, which needs no network access.
+pub struct SearchProvider {
+    kind: LinkKind,
+    /// The search URL, in which `{query}` is replaced by the encoded search terms.
+    template: &'static str,
+    /// Whether the album's title is part of the search terms.
+    with_album: bool,
+}
+
+impl SearchProvider {
+    pub fn new(kind: LinkKind) -> Option<Self> {
+        let (template, with_album) = match kind {
+            LinkKind::Youtube => (
+                "https://www.youtube.com/results?search_query={query}%20full%20album",
+                true,
+            ),
+            LinkKind::Spotify => ("https://open.spotify.com/search/{query}", true),
+            LinkKind::AppleMusic => ("https://music.apple.com/us/search?term={query}", true),
+            LinkKind::Deezer => ("https://www.deezer.com/search/{query}", true),
+            LinkKind::Tidal => ("https://listen.tidal.com/search?q={query}", true),
+            LinkKind::MetalArchives => (
+                "https://www.metal-archives.com/search?searchString={query}&type=band_name",
+                false,
+            ),
+            LinkKind::Bandcamp | LinkKind::LabelShop => return None,
+        };
+
+        Some(Self {
+            kind,
+            template,
+            with_album,
+        })
+    }
+}
+
+#[async_trait]
+impl LinkProvider for SearchProvider {
+    fn kind(&self) -> LinkKind {
+        self.kind
+    }
+
+    async fn link(&self, release: &Release) -> Option<Url> {
+        let terms = if self.with_album {
+            format!("{} {}", release.artist, release.album)
+        } else {
+            release.primary_artist().to_string()
+        };
+        Url::parse(&fill(self.template, &terms)).ok()
+    }
+}
+
+/// Links to the Bandcamp page of the primary artist, once verified by
+/// [`jobs::update_bandcamp_links`](crate::jobs::update_bandcamp_links).
+pub struct BandcampProvider;
+
+#[async_trait]
+impl LinkProvider for BandcampProvider {
+    fn kind(&self) -> LinkKind {
+        LinkKind::Bandcamp
+    }
+
+    async fn link(&self, release: &Release) -> Option<Url> {
+        let participant = release.artists.first()?.clone();
+        let url = tokio::task::spawn_blocking(move || {
+            let mm = &mut ModelManager::new();
+            LinkBmc::get_bandcamp_url(&mut mm.conn, &participant)
+        })
+        .await
+        .ok()??;
+        Url::parse(&url).ok()
+    }
+}
+
+/// Links to a search of the album in the shop of the label that published it.
+///
+/// The label is known from MusicBrainz, and only the labels whose shop is
+/// configured in `LABEL_SHOPS` get a link.
+pub struct LabelShopProvider {
+    /// The search URL of the shops, keyed by lowercase label name.
+    shops: HashMap<String, String>,
+}
+
+impl LabelShopProvider {
+    pub fn new(shops: HashMap<String, String>) -> Self {
+        Self {
+            shops: shops
+                .into_iter()
+                .map(|(label, template)| (label.to_lowercase(), template))
+                .collect(),
+        }
+    }
+}
+
+#[async_trait]
+impl LinkProvider for LabelShopProvider {
+    fn kind(&self) -> LinkKind {
+        LinkKind::LabelShop
+    }
+
+    async fn link(&self, release: &Release) -> Option<Url> {
+        let label = release.musicbrainz.as_ref()?.label.as_ref()?;
+        let template = self.shops.get(&label.to_lowercase())?;
+        Url::parse(&fill(
+            template,
+            &format!("{} {}", release.artist, release.album),
+        ))
+        .ok()
+    }
+}
+
+/// Replaces `{query}` in the template with the encoded search terms.
+fn fill(template: &str, terms: &str) -> String {
+    let mut query = String::new();
+    url_escape::encode_component_to_string(terms, &mut query);
+    template.replace("{query}", &query)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::calendar::MusicBrainzRelease;
+
+    type Result<T> = core::result::Result<T, Box<dyn std::error::Error>>;
+
+    #[tokio::test]
+    async fn test_search_providers_ok() -> Result<()> {
+        let release = Release::new("Guns N' Roses", "Hard Skool");
+
+        let mut got = Vec::new();
+        for kind in LinkKind::ALL {
+            if let Some(provider) = SearchProvider::new(kind) {
+                got.push(provider.link(&release).await.map(|url| url.to_string()));
+            }
+        }
+
+        pretty_assertions::assert_eq!(
+            got,
+            vec![
+                Some("https://www.youtube.com/results?search_query=Guns%20N%27%20Roses%20Hard%20Skool%20full%20album".to_string()),
+                Some("https://open.spotify.com/search/Guns%20N'%20Roses%20Hard%20Skool".to_string()),
+                Some("https://music.apple.com/us/search?term=Guns%20N%27%20Roses%20Hard%20Skool".to_string()),
+                Some("https://www.deezer.com/search/Guns%20N'%20Roses%20Hard%20Skool".to_string()),
+                Some("https://listen.tidal.com/search?q=Guns%20N%27%20Roses%20Hard%20Skool".to_string()),
+                Some("https://www.metal-archives.com/search?searchString=Guns%20N%27%20Roses&type=band_name".to_string()),
+            ]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_label_shop_provider_ok() -> Result<()> {
+        let provider = LabelShopProvider::new(HashMap::from([(
+            "Nuclear Blast".to_string(),
+            "https://shop.example.com/search?q={query}".to_string(),
+        )]));
+        let mut release = Release::new("Wintersun", "Time II");
+        release.musicbrainz = Some(MusicBrainzRelease {
+            label: Some("nuclear blast".to_string()),
+            ..Default::default()
+        });
+
+        let got = provider.link(&release).await;
+
+        pretty_assertions::assert_eq!(
+            got,
+            Some(Url::parse(
+                "https://shop.example.com/search?q=Wintersun%20Time%20II"
+            )?)
+        );
+        pretty_assertions::assert_eq!(
+            provider.link(&Release::new("Wintersun", "Time II")).await,
+            None
+        );
+        Ok(())
+    }
+}
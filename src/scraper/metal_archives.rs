@@ -0,0 +1,201 @@
+use axum::async_trait;
+use scraper::{Html, Selector};
+use serde::Deserialize;
+use time::Month;
+
+use crate::{
+    calendar::{Calendar, Participant, Release, ReleaseType},
+    error::Result,
+};
+
+use super::{client::Client, source::ReleaseSource};
+
+/// The number of releases per page of the upcoming releases list.
+const PAGE_SIZE: usize = 100;
+
+/// The maximum number of pages fetched in a run.
+const MAX_PAGES: usize = 20;
+
+/// Lists the upcoming releases of the [Metal Archives](https://www.metal-archives.com/release/upcoming).
+///
+/// Only releases from today onward are listed, so past releases of the year
+/// come from the other sources.
+pub struct MetalArchivesSource<'a, C> {
+    client: &'a C,
+}
+
+impl<'a, C: Client + Sync> MetalArchivesSource<'a, C> {
+    pub fn new(client: &'a C) -> Self {
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl<C: Client + Sync> ReleaseSource for MetalArchivesSource<'_, C> {
+    fn name(&self) -> &'static str {
+        "metal_archives"
+    }
+
+    async fn calendar(&self, year: i32) -> Result<Calendar> {
+        let mut calendar = Calendar::new(year);
+
+        for page in 0..MAX_PAGES {
+            let start = page * PAGE_SIZE;
+            let url = format!(
+                "https://www.metal-archives.com/release/ajax-upcoming/json/1?sEcho=1&iDisplayStart={start}&iDisplayLength={PAGE_SIZE}"
+            );
+            let upcoming: Upcoming = serde_json::from_str(&self.client.get_text(&url).await?)?;
+
+            let num_rows = upcoming.rows.len();
+            for row in upcoming.rows.iter() {
+                if let Some((month, day, release)) = parse_row(row, year) {
+                    calendar.add_release(month, day, release);
+                }
+            }
+
+            if num_rows < PAGE_SIZE || start + num_rows >= upcoming.total {
+                break;
+            }
+        }
+
+        Ok(calendar)
+    }
+}
+
+/// A page of the upcoming releases list.
+#[derive(Deserialize)]
+struct Upcoming {
+    #[serde(rename = "iTotalRecords")]
+    total: usize,
+    /// The rows of the list: band links, album link, type, genre,
+    /// release date and date added.
+    #[serde(rename = "aaData")]
+    rows: Vec<Vec<String>>,
+}
+
+fn parse_row(row: &[String], year: i32) -> Option<(Month, u8, Release)> {
+    let [bands, album, kind, genre, date, ..] = row else {
+        return None;
+    };
+
+    let release_type = match kind.as_str() {
+        "Full-length" | "Split" => ReleaseType::Album,
+        "EP" | "Split video" => ReleaseType::Ep,
+        "Single" => ReleaseType::Single,
+        "Live album" => ReleaseType::Live,
+        "Compilation" | "Collaboration" => ReleaseType::Compilation,
+        "Demo" => ReleaseType::Demo,
+        "Boxed set" => ReleaseType::BoxSet,
+        _ => return None,
+    };
+    let (release_year, month, day) = parse_date(date)?;
+    if release_year != year {
+        return None;
+    }
+
+    let participants = link_texts(bands);
+    let credit = participants.join(" / ");
+    let album = link_texts(album).into_iter().next()?;
+
+    let release = Release::new(credit, album)
+        .with_artists(
+            participants
+                .into_iter()
+                .map(|name| Participant::new(name, None))
+                .collect(),
+        )
+        .with_release_type(release_type)
+        .with_genre(Some(genre.trim().to_string()));
+
+    Some((month, day, release))
+}
+
+/// Collects the text of the links in a cell, e.g. the bands of a split.
+fn link_texts(cell: &str) -> Vec<String> {
+    let fragment = Html::parse_fragment(cell);
+    let selector = Selector::parse("a").unwrap();
+
+    fragment
+        .select(&selector)
+        .map(|a| a.text().collect::<String>().trim().to_string())
+        .filter(|text| !text.is_empty())
+        .collect()
+}
+
+/// Parses a date such as "October 18th, 2024". Dates without a day,
+/// e.g. "October 2024", are not precise enough to be used.
+fn parse_date(date: &str) -> Option<(i32, Month, u8)> {
+    let (month_day, year) = date.split_once(", ")?;
+    let (month, day) = month_day.split_once(' ')?;
+
+    let month = match month {
+        "January" => Month::January,
+        "February" => Month::February,
+        "March" => Month::March,
+        "April" => Month::April,
+        "May" => Month::May,
+        "June" => Month::June,
+        "July" => Month::July,
+        "August" => Month::August,
+        "September" => Month::September,
+        "October" => Month::October,
+        "November" => Month::November,
+        "December" => Month::December,
+        _ => return None,
+    };
+    let day = day
+        .trim_end_matches(|c: char| c.is_ascii_alphabetic())
+        .parse()
+        .ok()?;
+
+    Some((year.trim().parse().ok()?, month, day))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::scraper::client::tests::MockClient;
+
+    type Result<T> = core::result::Result<T, Box<dyn std::error::Error>>;
+
+    #[tokio::test]
+    async fn test_upcoming_releases_ok() -> Result<()> {
+        let client = MockClient::new().with_fixture(
+            "https://www.metal-archives.com/release/ajax-upcoming/json/1?sEcho=1&iDisplayStart=0&iDisplayLength=100",
+            "metal_archives_upcoming.json",
+        );
+
+        let got = MetalArchivesSource::new(&client).calendar(2024).await?;
+
+        let mut want = Calendar::new(2024);
+        want.add_release(
+            Month::August,
+            30,
+            Release::new("Wintersun", "Time II")
+                .with_genre(Some("Melodic Death Metal".to_string())),
+        );
+        want.add_release(
+            Month::October,
+            18,
+            Release::new(
+                "Ulthar / Pandiscordian Necrogenesis",
+                "Helleborus / Anthropophagic Lycanthropic Ceremony",
+            )
+            .with_artists(vec![
+                Participant::new("Ulthar", None),
+                Participant::new("Pandiscordian Necrogenesis", None),
+            ])
+            .with_genre(Some("Death Metal | Black Metal".to_string())),
+        );
+        want.add_release(
+            Month::October,
+            18,
+            Release::new("Blood Incantation", "Luminescent Bridge")
+                .with_release_type(ReleaseType::Ep)
+                .with_genre(Some("Death Metal".to_string())),
+        );
+        pretty_assertions::assert_eq!(got, want);
+        Ok(())
+    }
+}
@@ -1,2 +1,11 @@
+pub mod bandcamp;
 pub mod client;
+pub mod cover_art;
+pub mod csv_file;
+pub mod links;
+pub mod metal_archives;
+pub mod musicbrainz;
+pub mod rate_limit;
+pub mod replay;
+pub mod source;
 pub mod wiki;
@@ -0,0 +1,390 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use axum::async_trait;
+use serde::Deserialize;
+use time::Month;
+
+use crate::{
+    calendar::{Calendar, MusicBrainzRelease, Participant, Release, ReleaseType},
+    config::config,
+    error::Result,
+};
+
+use super::{
+    client::{AppClient, Client},
+    source::ReleaseSource,
+};
+
+/// The number of release groups per page of search results.
+const PAGE_SIZE: usize = 100;
+
+/// The maximum number of pages fetched in a run.
+const MAX_PAGES: usize = 10;
+
+/// The minimum search score, out of 100, for a release to be considered a match.
+const MIN_SCORE: u32 = 90;
+
+/// The time to wait between two requests to the web service.
+const REQUEST_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Looks up releases on MusicBrainz.
+#[async_trait]
+pub trait MusicBrainzApi: Send + Sync {
+    /// Finds the release of the album by the artist, if MusicBrainz knows of it.
+    async fn find_release(&self, artist: &str, album: &str) -> Result<Option<MusicBrainzRelease>>;
+}
+
+/// Queries the [MusicBrainz web service](https://musicbrainz.org/doc/MusicBrainz_API).
+pub struct MusicBrainzWebService {
+    base_url: String,
+    client: AppClient,
+    last_request: Mutex<Option<Instant>>,
+}
+
+impl MusicBrainzWebService {
+    /// Creates a client of the web service at `MUSICBRAINZ_URL`.
+    pub fn new() -> Result<Self> {
+        Ok(Self::with_client(
+            &config().MUSICBRAINZ_URL,
+            AppClient::new()?,
+        ))
+    }
+
+    pub fn with_client(base_url: &str, client: AppClient) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            client,
+            last_request: Mutex::new(None),
+        }
+    }
+
+    /// Waits until the web service accepts another request.
+    async fn throttle(&self) {
+        let wait = {
+            let mut last_request = self.last_request.lock().unwrap();
+            let now = Instant::now();
+            let next = last_request.map_or(now, |last| (last + REQUEST_INTERVAL).max(now));
+            *last_request = Some(next);
+            next - now
+        };
+
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+#[async_trait]
+impl MusicBrainzApi for MusicBrainzWebService {
+    async fn find_release(&self, artist: &str, album: &str) -> Result<Option<MusicBrainzRelease>> {
+        let query = format!("release:{} AND artist:{}", phrase(album), phrase(artist));
+        let mut query_encoded = String::new();
+        url_escape::encode_component_to_string(query, &mut query_encoded);
+        let url = format!(
+            "{}/ws/2/release?query={query_encoded}&fmt=json&limit=5",
+            self.base_url
+        );
+
+        self.throttle().await;
+        let text = self.client.get_text(&url).await?;
+        let results: ReleaseSearchResults = serde_json::from_str(&text)?;
+
+        Ok(results
+            .releases
+            .into_iter()
+            .find(|release| release.score >= MIN_SCORE)
+            .map(|release| MusicBrainzRelease {
+                release_group_id: release.release_group.id,
+                release_id: release.id,
+                track_count: release.track_count,
+                label: release
+                    .label_info
+                    .into_iter()
+                    .find_map(|info| info.label.map(|label| label.name)),
+            }))
+    }
+}
+
+/// Quotes a term of a search query, escaping the characters special to Lucene.
+fn phrase(term: &str) -> String {
+    format!("\"{}\"", term.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[derive(Deserialize)]
+struct ReleaseSearchResults {
+    releases: Vec<SearchedRelease>,
+}
+
+#[derive(Deserialize)]
+struct SearchedRelease {
+    id: String,
+    score: u32,
+    #[serde(rename = "track-count")]
+    track_count: Option<i32>,
+    #[serde(rename = "release-group")]
+    release_group: SearchedReleaseGroup,
+    #[serde(rename = "label-info", default)]
+    label_info: Vec<LabelInfo>,
+}
+
+#[derive(Deserialize)]
+struct LabelInfo {
+    label: Option<SearchedLabel>,
+}
+
+#[derive(Deserialize)]
+struct SearchedLabel {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct SearchedReleaseGroup {
+    id: String,
+}
+
+/// Lists the metal release groups first released during the year
+/// according to [MusicBrainz](https://musicbrainz.org).
+pub struct MusicBrainzSource<'a, C> {
+    base_url: String,
+    client: &'a C,
+}
+
+impl<'a, C: Client + Sync> MusicBrainzSource<'a, C> {
+    /// Creates a source that searches the web service at `MUSICBRAINZ_URL`.
+    pub fn new(client: &'a C) -> Self {
+        Self::with_base_url(&config().MUSICBRAINZ_URL, client)
+    }
+
+    pub fn with_base_url(base_url: &str, client: &'a C) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            client,
+        }
+    }
+}
+
+#[async_trait]
+impl<C: Client + Sync> ReleaseSource for MusicBrainzSource<'_, C> {
+    fn name(&self) -> &'static str {
+        "musicbrainz"
+    }
+
+    async fn calendar(&self, year: i32) -> Result<Calendar> {
+        let mut calendar = Calendar::new(year);
+
+        let query = format!("tag:metal AND firstreleasedate:[{year}-01-01 TO {year}-12-31]");
+        let mut query_encoded = String::new();
+        url_escape::encode_component_to_string(query, &mut query_encoded);
+
+        for page in 0..MAX_PAGES {
+            if page > 0 {
+                // MusicBrainz allows one request per second.
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+
+            let offset = page * PAGE_SIZE;
+            let url = format!(
+                "{}/ws/2/release-group?query={query_encoded}&fmt=json&limit={PAGE_SIZE}&offset={offset}",
+                self.base_url
+            );
+            let results: SearchResults = serde_json::from_str(&self.client.get_text(&url).await?)?;
+
+            let num_results = results.release_groups.len();
+            for group in results.release_groups {
+                if let Some((month, day, release)) = group.into_release(year) {
+                    calendar.add_release(month, day, release);
+                }
+            }
+
+            if num_results < PAGE_SIZE || offset + num_results >= results.count {
+                break;
+            }
+        }
+
+        Ok(calendar)
+    }
+}
+
+#[derive(Deserialize)]
+struct SearchResults {
+    count: usize,
+    #[serde(rename = "release-groups")]
+    release_groups: Vec<ReleaseGroup>,
+}
+
+#[derive(Deserialize)]
+struct ReleaseGroup {
+    title: String,
+    #[serde(rename = "first-release-date")]
+    first_release_date: Option<String>,
+    #[serde(rename = "primary-type")]
+    primary_type: Option<String>,
+    #[serde(rename = "secondary-types", default)]
+    secondary_types: Vec<String>,
+    #[serde(rename = "artist-credit", default)]
+    artist_credit: Vec<ArtistCredit>,
+}
+
+#[derive(Deserialize)]
+struct ArtistCredit {
+    name: String,
+    #[serde(default)]
+    joinphrase: String,
+    artist: CreditedArtist,
+}
+
+#[derive(Deserialize)]
+struct CreditedArtist {
+    name: String,
+}
+
+impl ReleaseGroup {
+    fn into_release(self, year: i32) -> Option<(Month, u8, Release)> {
+        let date = self.first_release_date?;
+        let mut parts = date.splitn(3, '-');
+        let release_year: i32 = parts.next()?.parse().ok()?;
+        let month: u8 = parts.next()?.parse().ok()?;
+        let day: u8 = parts.next()?.parse().ok()?;
+        if release_year != year {
+            return None;
+        }
+
+        let release_type = if let Some(kind) =
+            self.secondary_types.iter().find_map(|t| match t.as_str() {
+                "Live" => Some(ReleaseType::Live),
+                "Compilation" => Some(ReleaseType::Compilation),
+                "Demo" => Some(ReleaseType::Demo),
+                _ => None,
+            }) {
+            kind
+        } else {
+            match self.primary_type.as_deref() {
+                Some("EP") => ReleaseType::Ep,
+                Some("Single") => ReleaseType::Single,
+                _ => ReleaseType::Album,
+            }
+        };
+
+        let credit = self
+            .artist_credit
+            .iter()
+            .map(|credit| format!("{}{}", credit.name, credit.joinphrase))
+            .collect::<String>();
+        let participants = self
+            .artist_credit
+            .into_iter()
+            .map(|credit| Participant::new(credit.artist.name, None))
+            .collect();
+
+        let release = Release::new(credit, self.title)
+            .with_artists(participants)
+            .with_release_type(release_type);
+
+        Some((Month::try_from(month).ok()?, day, release))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::scraper::client::{tests::MockClient, MainClient};
+
+    use std::collections::HashMap;
+
+    use axum::{extract::Query, routing::get, Router};
+    use tokio::net::TcpListener;
+
+    type Result<T> = core::result::Result<T, Box<dyn std::error::Error>>;
+
+    /// Serves a stub of the release search of the web service on a local
+    /// port and returns its base URL. Only "Time II" by Wintersun is found.
+    async fn serve_stub() -> Result<String> {
+        async fn search(Query(params): Query<HashMap<String, String>>) -> String {
+            let name = if params["query"] == r#"release:"Time II" AND artist:"Wintersun""# {
+                "musicbrainz_release_search.json"
+            } else {
+                "musicbrainz_release_search_miss.json"
+            };
+            std::fs::read_to_string(format!("./tests/testdata/{name}")).unwrap()
+        }
+
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(async move {
+            axum::serve(listener, Router::new().route("/ws/2/release", get(search))).await
+        });
+        Ok(format!("http://{addr}/"))
+    }
+
+    #[tokio::test]
+    async fn test_find_release_ok() -> Result<()> {
+        let service = MusicBrainzWebService::with_client(
+            &serve_stub().await?,
+            AppClient::Live(MainClient::with_proxy(None)?),
+        );
+
+        let got = service.find_release("Wintersun", "Time II").await?;
+
+        pretty_assertions::assert_eq!(
+            got,
+            Some(MusicBrainzRelease {
+                release_group_id: "9d8b5c4e-3b1a-4b8e-9f6a-2d3c4b5a6f70".to_string(),
+                release_id: "5e1c7a2b-8d4f-4c3a-b6e9-0f1a2b3c4d5e".to_string(),
+                track_count: Some(6),
+                label: Some("Nuclear Blast".to_string()),
+            })
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_find_release_low_score_ok() -> Result<()> {
+        let service = MusicBrainzWebService::with_client(
+            &serve_stub().await?,
+            AppClient::Live(MainClient::with_proxy(None)?),
+        );
+
+        let got = service
+            .find_release("Unknown Band", "Some \"Demo\"")
+            .await?;
+
+        pretty_assertions::assert_eq!(got, None);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_release_groups_ok() -> Result<()> {
+        let client = MockClient::new().with_fixture(
+            "http://musicbrainz.test/ws/2/release-group?query=tag%3Ametal%20AND%20firstreleasedate%3A%5B2024-01-01%20TO%202024-12-31%5D&fmt=json&limit=100&offset=0",
+            "musicbrainz_release_groups.json",
+        );
+
+        let got = MusicBrainzSource::with_base_url("http://musicbrainz.test/", &client)
+            .calendar(2024)
+            .await?;
+
+        let mut want = Calendar::new(2024);
+        want.add_release(Month::August, 30, Release::new("Wintersun", "Time II"));
+        want.add_release(
+            Month::February,
+            9,
+            Release::new("Bell Witch & Aerial Ruin", "Stygian Bough: Volume I").with_artists(vec![
+                Participant::new("Bell Witch", None),
+                Participant::new("Aerial Ruin", None),
+            ]),
+        );
+        want.add_release(
+            Month::March,
+            15,
+            Release::new("Kiss", "Off the Soundboard")
+                .with_artists(vec![Participant::new("KISS", None)])
+                .with_release_type(ReleaseType::Live),
+        );
+        pretty_assertions::assert_eq!(got, want);
+        Ok(())
+    }
+}
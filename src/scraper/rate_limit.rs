@@ -0,0 +1,160 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Limits the rate of requests sent to each site with a token bucket.
+///
+/// The hosts of a site share its bucket, e.g. `bandcamp.com` and the
+/// `*.bandcamp.com` pages of the artists. Every site starts with a full
+/// bucket of `burst` tokens, which refills at `per_second` tokens per
+/// second. A request takes one token, and waits for the bucket to refill
+/// when it is empty.
+pub struct RateLimiter {
+    per_second: f64,
+    burst: f64,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+struct Bucket {
+    /// The tokens left, negative when requests are waiting for the bucket to refill.
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(per_second: f64, burst: u32) -> Self {
+        Self {
+            per_second,
+            burst: burst as f64,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Waits until a request can be sent to the site of the URL.
+    ///
+    /// URLs without a host, e.g. local files, are never limited.
+    pub async fn acquire(&self, url: &str) {
+        let Some(site) = reqwest::Url::parse(url)
+            .ok()
+            .and_then(|url| match url.domain() {
+                Some(domain) => Some(registrable_domain(domain).to_string()),
+                None => url.host_str().map(str::to_string),
+            })
+        else {
+            return;
+        };
+
+        let wait = self.reserve(site, Instant::now());
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Takes a token from the site's bucket and returns how long to wait
+    /// until the token is available.
+    fn reserve(&self, site: String, now: Instant) -> Duration {
+        let mut buckets = self.buckets.lock().unwrap();
+        let bucket = buckets.entry(site).or_insert(Bucket {
+            tokens: self.burst,
+            updated_at: now,
+        });
+
+        let elapsed = now.saturating_duration_since(bucket.updated_at);
+        bucket.tokens = (bucket.tokens + elapsed.as_secs_f64() * self.per_second).min(self.burst);
+        bucket.updated_at = now;
+        bucket.tokens -= 1.0;
+
+        if bucket.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-bucket.tokens / self.per_second)
+        }
+    }
+}
+
+/// The second-level labels under which country domains are registered,
+/// e.g. `co.uk`.
+const SECOND_LEVEL_LABELS: [&str; 6] = ["ac", "co", "com", "gov", "net", "org"];
+
+/// The domain a host was registered under, e.g. `bandcamp.com` for
+/// `wintersun.bandcamp.com` and `bbc.co.uk` for `www.bbc.co.uk`.
+fn registrable_domain(host: &str) -> &str {
+    let host = host.trim_end_matches('.');
+    let labels = host.split('.').collect::<Vec<_>>();
+    let len = match labels.as_slice() {
+        [.., second, tld] if tld.len() == 2 && SECOND_LEVEL_LABELS.contains(second) => 3,
+        _ => 2,
+    };
+    if labels.len() <= len {
+        return host;
+    }
+
+    let start = labels[labels.len() - len..]
+        .iter()
+        .map(|label| label.len() + 1)
+        .sum::<usize>();
+    &host[host.len() + 1 - start..]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Result<T> = core::result::Result<T, Box<dyn std::error::Error>>;
+
+    #[test]
+    fn test_reserve_ok() -> Result<()> {
+        let limiter = RateLimiter::new(2.0, 2);
+        let now = Instant::now();
+
+        let got = [
+            limiter.reserve("bandcamp.com".to_string(), now),
+            limiter.reserve("bandcamp.com".to_string(), now),
+            limiter.reserve("bandcamp.com".to_string(), now),
+            limiter.reserve("bandcamp.com".to_string(), now),
+            limiter.reserve("musicbrainz.org".to_string(), now),
+            limiter.reserve("bandcamp.com".to_string(), now + Duration::from_secs(2)),
+        ];
+
+        pretty_assertions::assert_eq!(
+            got,
+            [
+                Duration::ZERO,
+                Duration::ZERO,
+                Duration::from_millis(500),
+                Duration::from_secs(1),
+                Duration::ZERO,
+                Duration::ZERO,
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_registrable_domain_ok() -> Result<()> {
+        let got = [
+            "bandcamp.com",
+            "wintersun.bandcamp.com",
+            "f4.bcbits.com",
+            "en.wikipedia.org",
+            "www.bbc.co.uk",
+            "localhost",
+        ]
+        .map(registrable_domain);
+
+        pretty_assertions::assert_eq!(
+            got,
+            [
+                "bandcamp.com",
+                "bandcamp.com",
+                "bcbits.com",
+                "wikipedia.org",
+                "bbc.co.uk",
+                "localhost",
+            ]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,237 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use axum::async_trait;
+use tracing::error;
+
+use crate::error::{Error, Result};
+
+use super::{
+    client::{Client, MainClient},
+    wiki,
+};
+
+/// The file of a fixtures folder mapping every recorded URL to the file
+/// its response is stored in.
+pub const INDEX_FILE: &str = "fixtures.json";
+
+/// Serves the responses recorded in a fixtures folder instead of sending
+/// requests, so that the application and the tests run without network access.
+///
+/// Requesting a URL whose response was not recorded fails with
+/// [`Error::MissingFixture`]; the network is never used as a fallback.
+pub struct ReplayClient {
+    dir: PathBuf,
+    /// The files of the responses, relative to `dir`, keyed by URL.
+    fixtures: BTreeMap<String, String>,
+}
+
+impl ReplayClient {
+    /// Opens a fixtures folder, e.g. one written by a [`RecordingClient`].
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        let fixtures = read_index(&dir)?;
+        Ok(Self { dir, fixtures })
+    }
+
+    /// Serves the `{dir}/{name}` file when `url` is requested.
+    #[cfg(test)]
+    pub fn with_fixture(mut self, url: impl Into<String>, name: &str) -> Self {
+        self.fixtures.insert(url.into(), name.to_string());
+        self
+    }
+
+    fn read(&self, url: &str) -> Result<Vec<u8>> {
+        let Some(name) = self.fixtures.get(url) else {
+            error!(
+                "No fixture recorded for {url} in {}. Record it with --record-dir.",
+                self.dir.display()
+            );
+            return Err(Error::MissingFixture(url.to_string()));
+        };
+
+        Ok(fs::read(self.dir.join(name))?)
+    }
+}
+
+#[async_trait]
+impl Client for ReplayClient {
+    async fn get_calendar(&self, year: i32) -> Result<scraper::Html> {
+        let revision = wiki::latest_revision(self, year).await?;
+        wiki::parse_revision(self, revision).await
+    }
+
+    async fn get_text(&self, url: &str) -> Result<String> {
+        Ok(String::from_utf8_lossy(&self.read(url)?).into_owned())
+    }
+
+    async fn get_bytes(&self, url: &str) -> Result<Vec<u8>> {
+        self.read(url)
+    }
+}
+
+/// Sends requests through a [`MainClient`] and records the responses in a
+/// fixtures folder, to be served by a [`ReplayClient`] later.
+pub struct RecordingClient {
+    inner: MainClient,
+    dir: PathBuf,
+    fixtures: Mutex<BTreeMap<String, String>>,
+}
+
+impl RecordingClient {
+    /// Records in a fixtures folder, keeping the responses recorded before.
+    pub fn open(dir: impl Into<PathBuf>, inner: MainClient) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        let fixtures = Mutex::new(read_index(&dir)?);
+        Ok(Self {
+            inner,
+            dir,
+            fixtures,
+        })
+    }
+
+    fn record(&self, url: &str, body: &[u8]) -> Result<()> {
+        let mut fixtures = self.fixtures.lock().unwrap();
+        let name = match fixtures.get(url) {
+            Some(name) => name.clone(),
+            None => fixture_name(url, fixtures.len()),
+        };
+
+        fs::write(self.dir.join(&name), body)?;
+        fixtures.insert(url.to_string(), name);
+        fs::write(
+            self.dir.join(INDEX_FILE),
+            serde_json::to_string_pretty(&*fixtures)?,
+        )?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Client for RecordingClient {
+    async fn get_calendar(&self, year: i32) -> Result<scraper::Html> {
+        let revision = wiki::latest_revision(self, year).await?;
+        wiki::parse_revision(self, revision).await
+    }
+
+    async fn get_text(&self, url: &str) -> Result<String> {
+        let text = self.inner.get_text(url).await?;
+        self.record(url, text.as_bytes())?;
+        Ok(text)
+    }
+
+    async fn get_bytes(&self, url: &str) -> Result<Vec<u8>> {
+        let bytes = self.inner.get_bytes(url).await?;
+        self.record(url, &bytes)?;
+        Ok(bytes)
+    }
+
+    async fn get_page(&self, url: &str) -> Result<String> {
+        let text = self.inner.get_page(url).await?;
+        self.record(url, text.as_bytes())?;
+        Ok(text)
+    }
+
+    async fn get_archived_page(&self, url: &str) -> Result<String> {
+        let text = self.inner.get_archived_page(url).await?;
+        self.record(url, text.as_bytes())?;
+        Ok(text)
+    }
+}
+
+/// Reads the index of a fixtures folder, which is empty when nothing was recorded yet.
+fn read_index(dir: &Path) -> Result<BTreeMap<String, String>> {
+    match fs::read_to_string(dir.join(INDEX_FILE)) {
+        Ok(index) => Ok(serde_json::from_str(&index)?),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Names the file of a recorded response after its URL, e.g.
+/// `en-wikipedia-org-w-api-php-3`. The number keeps the names unique.
+fn fixture_name(url: &str, number: usize) -> String {
+    let stem = url
+        .split_once("://")
+        .map_or(url, |(_, rest)| rest)
+        .split('?')
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("-");
+    let stem = stem.chars().take(60).collect::<String>();
+    format!("{stem}-{number}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Result<T> = core::result::Result<T, Box<dyn std::error::Error>>;
+
+    #[tokio::test]
+    async fn test_replay_missing_fixture_ok() -> Result<()> {
+        let client = ReplayClient::open("./tests/testdata")?;
+
+        let got = client.get_text("https://example.com/not-recorded").await;
+
+        assert!(
+            matches!(got, Err(Error::MissingFixture(url)) if url == "https://example.com/not-recorded")
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_record_then_replay_ok() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("fixtures-{}", std::process::id()));
+        let recorder = RecordingClient::open(&dir, MainClient::with_proxy(None)?)?;
+        recorder.record(
+            "https://bandcamp.com/search?q=Wintersun&item_type=b",
+            b"Wintersun",
+        )?;
+        recorder.record("https://f4.bcbits.com/img/a0123456789_5.jpg", &[0, 1, 2])?;
+
+        let client = ReplayClient::open(&dir)?;
+
+        pretty_assertions::assert_eq!(
+            client
+                .get_text("https://bandcamp.com/search?q=Wintersun&item_type=b")
+                .await?,
+            "Wintersun"
+        );
+        pretty_assertions::assert_eq!(
+            client
+                .get_bytes("https://f4.bcbits.com/img/a0123456789_5.jpg")
+                .await?,
+            vec![0, 1, 2]
+        );
+        pretty_assertions::assert_eq!(
+            fs::read_dir(&dir)?.count(),
+            3,
+            "two responses and the index"
+        );
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_fixture_name_ok() -> Result<()> {
+        pretty_assertions::assert_eq!(
+            fixture_name(
+                "https://en.wikipedia.org/w/api.php?action=parse&oldid=1251234567",
+                3
+            ),
+            "en-wikipedia-org-w-api-php-3"
+        );
+        Ok(())
+    }
+}
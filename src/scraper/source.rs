@@ -0,0 +1,268 @@
+use std::collections::HashMap;
+
+use axum::async_trait;
+use time::Month;
+use tracing::{error, info, warn};
+
+use crate::{
+    calendar::{Calendar, Release},
+    config::config,
+    error::{Error, Result},
+};
+
+use super::{
+    client::Client, csv_file::CsvSource, metal_archives::MetalArchivesSource,
+    musicbrainz::MusicBrainzSource, wiki::WikipediaSource,
+};
+
+/// A provider of album releases, e.g. Wikipedia or Metal Archives.
+#[async_trait]
+pub trait ReleaseSource: Send + Sync {
+    /// The name recorded with every release the source contributes.
+    fn name(&self) -> &'static str;
+
+    /// Identifies the current content of the source's listing of the year,
+    /// e.g. a page revision, when the source can tell without fetching it.
+    async fn revision(&self, _year: i32) -> Result<Option<String>> {
+        Ok(None)
+    }
+
+    /// Fetches the releases of the given year.
+    async fn calendar(&self, year: i32) -> Result<Calendar>;
+}
+
+/// Creates the release sources enabled in the `RELEASE_SOURCES` configuration,
+/// in order of priority.
+pub fn sources_from_config<'a, C: Client + Sync>(
+    client: &'a C,
+) -> Vec<Box<dyn ReleaseSource + 'a>> {
+    let mut sources: Vec<Box<dyn ReleaseSource + 'a>> = Vec::new();
+
+    for name in config().RELEASE_SOURCES.iter() {
+        match name.as_str() {
+            "wikipedia" => sources.push(Box::new(WikipediaSource::new(client))),
+            "metal_archives" => sources.push(Box::new(MetalArchivesSource::new(client))),
+            "musicbrainz" => sources.push(Box::new(MusicBrainzSource::new(client))),
+            "csv" => match &config().RELEASE_SOURCES_CSV {
+                Some(path) => sources.push(Box::new(CsvSource::new(path))),
+                None => warn!("The csv release source requires RELEASE_SOURCES_CSV to be set"),
+            },
+            _ => warn!("Unknown release source `{name}`"),
+        }
+    }
+
+    sources
+}
+
+/// Whether one of the sources enabled in the `RELEASE_SOURCES` configuration
+/// gives the genre of the releases, which only the Metal Archives do.
+pub fn genres_enabled() -> bool {
+    config()
+        .RELEASE_SOURCES
+        .iter()
+        .any(|name| name == "metal_archives")
+}
+
+/// Fetches the revision of every source's listing of the year.
+///
+/// The returned vector is parallel to `sources`. The revision of a source
+/// is `None` when the source does not have any or when it could not be fetched.
+pub async fn fetch_revisions(
+    sources: &[Box<dyn ReleaseSource + '_>],
+    year: i32,
+) -> Vec<Option<String>> {
+    let mut revisions = Vec::with_capacity(sources.len());
+
+    for source in sources.iter() {
+        match source.revision(year).await {
+            Ok(revision) => revisions.push(revision),
+            Err(err) => {
+                error!("Error fetching the revision of {}: {err}", source.name());
+                revisions.push(None);
+            }
+        }
+    }
+
+    revisions
+}
+
+/// Fetches the calendar of the year from every source and merges them.
+///
+/// A source failing does not prevent the others from being used. An
+/// error is returned only when every source failed.
+pub async fn fetch_calendar(
+    sources: &[Box<dyn ReleaseSource + '_>],
+    year: i32,
+) -> Result<Calendar> {
+    let mut calendars = Vec::with_capacity(sources.len());
+
+    for source in sources.iter() {
+        match source.calendar(year).await {
+            Ok(mut calendar) => {
+                for release in calendar
+                    .data
+                    .values_mut()
+                    .flat_map(|days| days.values_mut())
+                    .flatten()
+                {
+                    release.providers = vec![source.name().to_string()];
+                }
+                info!(
+                    "Fetched {} releases from {}",
+                    count_releases(&calendar),
+                    source.name()
+                );
+                calendars.push(calendar);
+            }
+            Err(err) => error!("Error fetching releases from {}: {err}", source.name()),
+        }
+    }
+
+    if calendars.is_empty() {
+        return Err(Error::CalendarUpdateFail);
+    }
+    Ok(merge(calendars, year))
+}
+
+/// Merges the calendars of many sources into one.
+///
+/// A release listed by many sources is kept once, on the date given by the
+/// first calendar listing it. The details missing from that first listing
+/// are filled in from the others, and the providers of every listing are
+/// recorded. Releases are matched on their artist and album, ignoring case
+/// and punctuation.
+pub fn merge(calendars: Vec<Calendar>, year: i32) -> Calendar {
+    let mut merged = Calendar::new(year);
+    let mut seen: HashMap<(String, String), (Month, u8, usize)> = HashMap::new();
+
+    for calendar in calendars {
+        let mut days = calendar
+            .data
+            .into_iter()
+            .flat_map(|(month, days)| {
+                days.into_iter()
+                    .map(move |(day, releases)| (month, day, releases))
+            })
+            .collect::<Vec<_>>();
+        days.sort_by_key(|(month, day, _)| (*month as u8, *day));
+
+        for (month, day, releases) in days {
+            for release in releases {
+                let key = (normalize(&release.artist), normalize(&release.album));
+
+                match seen.get(&key) {
+                    Some(&(month, day, index)) => {
+                        let existing = merged
+                            .data
+                            .get_mut(&month)
+                            .and_then(|days| days.get_mut(&day))
+                            .and_then(|releases| releases.get_mut(index));
+                        if let Some(existing) = existing {
+                            merge_release(existing, release);
+                        }
+                    }
+                    None => {
+                        let index = merged.get_releases(month, day).map_or(0, |r| r.len());
+                        seen.insert(key, (month, day, index));
+                        merged.add_release(month, day, release);
+                    }
+                }
+            }
+        }
+    }
+
+    merged
+}
+
+fn merge_release(existing: &mut Release, other: Release) {
+    for provider in other.providers {
+        if !existing.providers.contains(&provider) {
+            existing.providers.push(provider);
+        }
+    }
+
+    if existing.wikipedia_artist.is_none() {
+        existing.wikipedia_artist = other.wikipedia_artist;
+    }
+    if existing.wikipedia_album.is_none() {
+        existing.wikipedia_album = other.wikipedia_album;
+    }
+    if existing.source_url.is_none() {
+        existing.source_url = other.source_url;
+    }
+    if existing.genre.is_none() {
+        existing.genre = other.genre;
+    }
+}
+
+/// Reduces a name to its lowercase letters and digits, so that
+/// "Guns N' Roses" and "Guns N Roses" are considered equal.
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn count_releases(calendar: &Calendar) -> usize {
+    calendar
+        .data
+        .values()
+        .flat_map(|days| days.values())
+        .map(|releases| releases.len())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Result<T> = core::result::Result<T, Box<dyn std::error::Error>>;
+
+    fn release(artist: &str, album: &str, provider: &str) -> Release {
+        let mut release = Release::new(artist, album);
+        release.providers = vec![provider.to_string()];
+        release
+    }
+
+    #[test]
+    fn test_merge_dedups_releases_ok() -> Result<()> {
+        let mut wikipedia = Calendar::new(2024);
+        wikipedia.add_release(
+            Month::March,
+            8,
+            release("Guns N' Roses", "Hard Skool (EP)", "wikipedia")
+                .with_source(Some("https://www.blabbermouth.net".to_string())),
+        );
+        wikipedia.add_release(Month::March, 8, release("Ghost", "Impera", "wikipedia"));
+        let mut metal_archives = Calendar::new(2024);
+        metal_archives.add_release(
+            Month::March,
+            9,
+            release("Guns N Roses", "Hard skool", "metal_archives")
+                .with_wikipedia(None, Some("Hard_Skool".to_string())),
+        );
+        metal_archives.add_release(
+            Month::April,
+            1,
+            release("Satan", "Earth Infernal", "metal_archives"),
+        );
+
+        let got = merge(vec![wikipedia, metal_archives], 2024);
+
+        let mut want_gnr = release("Guns N' Roses", "Hard Skool (EP)", "wikipedia")
+            .with_source(Some("https://www.blabbermouth.net".to_string()))
+            .with_wikipedia(None, Some("Hard_Skool".to_string()));
+        want_gnr.providers.push("metal_archives".to_string());
+        pretty_assertions::assert_eq!(
+            got.get_releases(Month::March, 8),
+            Some(&vec![want_gnr, release("Ghost", "Impera", "wikipedia")])
+        );
+        pretty_assertions::assert_eq!(got.get_releases(Month::March, 9), None);
+        pretty_assertions::assert_eq!(
+            got.get_releases(Month::April, 1),
+            Some(&vec![release("Satan", "Earth Infernal", "metal_archives")])
+        );
+        Ok(())
+    }
+}
@@ -1,25 +1,146 @@
 use std::collections::HashMap;
 
-use scraper::{ElementRef, Html, Selector};
+use axum::async_trait;
+use scraper::{ElementRef, Html, Node, Selector};
 use time::Month;
 
+use serde::Deserialize;
+
 use crate::{
-    calendar::{Calendar, Release},
-    error::Result,
+    calendar::{Calendar, Participant, Release},
+    error::{Error, Result},
 };
 
-use super::client::Client;
+use super::{client::Client, source::ReleaseSource};
+
+/// The MediaWiki API of the English Wikipedia.
+const API_URL: &str = "https://en.wikipedia.org/w/api.php";
+
+/// Lists the releases of Wikipedia's "{year} in heavy metal music" page.
+pub struct WikipediaSource<'a, C> {
+    client: &'a C,
+}
+
+impl<'a, C: Client + Sync> WikipediaSource<'a, C> {
+    pub fn new(client: &'a C) -> Self {
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl<C: Client + Sync> ReleaseSource for WikipediaSource<'_, C> {
+    fn name(&self) -> &'static str {
+        "wikipedia"
+    }
+
+    async fn revision(&self, year: i32) -> Result<Option<String>> {
+        Ok(Some(latest_revision(self.client, year).await?.to_string()))
+    }
+
+    async fn calendar(&self, year: i32) -> Result<Calendar> {
+        scrape(self.client, year).await
+    }
+}
 
 pub async fn scrape(client: &impl Client, year: i32) -> Result<Calendar> {
     let doc = client.get_calendar(year).await?;
     Ok(extract_calendar(doc, year))
 }
 
+/// The title of the page listing the releases of the year.
+pub fn page_title(year: i32) -> String {
+    format!("{year}_in_heavy_metal_music")
+}
+
+/// Fetches the id of the latest revision of the year's page.
+///
+/// The query is requested under the same URL every time, so that it is
+/// only fetched again when the page was edited.
+pub async fn latest_revision(client: &(impl Client + Sync), year: i32) -> Result<u64> {
+    let url = format!(
+        "{API_URL}?action=query&prop=revisions&rvprop=ids&titles={}&format=json&formatversion=2",
+        page_title(year)
+    );
+    let response: QueryResponse = serde_json::from_str(&client.get_page(&url).await?)?;
+
+    response
+        .query
+        .pages
+        .into_iter()
+        .flat_map(|page| page.revisions)
+        .map(|revision| revision.revid)
+        .next()
+        .ok_or(Error::RequestFail)
+}
+
+/// Fetches the HTML of a page's revision, as rendered by the MediaWiki parser.
+///
+/// A revision never changes, so it is only fetched the first time.
+pub async fn parse_revision(client: &(impl Client + Sync), revision: u64) -> Result<Html> {
+    let url =
+        format!("{API_URL}?action=parse&oldid={revision}&prop=text&format=json&formatversion=2");
+    let response: ParseResponse = serde_json::from_str(&client.get_archived_page(&url).await?)?;
+    Ok(Html::parse_document(&response.parse.text))
+}
+
+/// Extracts the calendar from a page fetched by [`parse_revision`] and
+/// archived, in order to reproduce a past scrape.
+///
+/// The year is taken from the title of the page, e.g. "2024 in heavy metal music".
+pub fn extract_snapshot(body: &str) -> Result<Calendar> {
+    let response: ParseResponse = serde_json::from_str(body)?;
+    let year = response
+        .parse
+        .title
+        .split_whitespace()
+        .next()
+        .and_then(|year| year.parse().ok())
+        .ok_or(Error::CalendarUpdateFail)?;
+    Ok(extract_calendar(
+        Html::parse_document(&response.parse.text),
+        year,
+    ))
+}
+
+#[derive(Deserialize)]
+struct QueryResponse {
+    query: Query,
+}
+
+#[derive(Deserialize)]
+struct Query {
+    pages: Vec<Page>,
+}
+
+#[derive(Deserialize)]
+struct Page {
+    /// Missing for pages that do not exist.
+    #[serde(default)]
+    revisions: Vec<Revision>,
+}
+
+#[derive(Deserialize)]
+struct Revision {
+    revid: u64,
+}
+
+#[derive(Deserialize)]
+struct ParseResponse {
+    parse: Parse,
+}
+
+#[derive(Deserialize)]
+struct Parse {
+    title: String,
+    text: String,
+}
+
 fn extract_calendar(doc: Html, year: i32) -> Calendar {
     let mut calendar = Calendar::new(year);
 
+    let citations = Citations::parse(&doc);
     let mut current_day: u8 = 1;
-    let mut current_artist = "".to_string();
+    let mut current_artist = ArtistCell::default();
 
     let tables: HashMap<&str, Month> = HashMap::from([
         ("#table_January", Month::January),
@@ -37,12 +158,13 @@ fn extract_calendar(doc: Html, year: i32) -> Calendar {
         ("#table_December", Month::December),
     ]);
     tables.iter().for_each(|(&table_id, &month)| {
-        let selector = &Selector::parse(&table_id).unwrap();
+        let selector = &Selector::parse(table_id).unwrap();
         let tables = doc.select(selector).collect::<Vec<_>>();
         match tables.len() {
             2 if month == Month::November => {
                 process_table(
                     tables[0],
+                    &citations,
                     &mut calendar,
                     Month::October,
                     &mut current_day,
@@ -50,6 +172,7 @@ fn extract_calendar(doc: Html, year: i32) -> Calendar {
                 );
                 process_table(
                     tables[1],
+                    &citations,
                     &mut calendar,
                     month,
                     &mut current_day,
@@ -58,6 +181,7 @@ fn extract_calendar(doc: Html, year: i32) -> Calendar {
             }
             1 => process_table(
                 tables[0],
+                &citations,
                 &mut calendar,
                 month,
                 &mut current_day,
@@ -70,51 +194,230 @@ fn extract_calendar(doc: Html, year: i32) -> Calendar {
     calendar
 }
 
+/// The content of an artist cell, which spans every row of the
+/// artist's releases on a given day.
+#[derive(Default)]
+struct ArtistCell {
+    /// The artist as credited, e.g. "Artist A / Artist B".
+    name: String,
+    /// The title of the artist's Wikipedia page when the cell links to a single page.
+    wikipedia: Option<String>,
+    /// The artists taking part in a split or a collaboration.
+    participants: Vec<Participant>,
+    /// The URL of the source cited in the cell.
+    source: Option<String>,
+}
+
+impl ArtistCell {
+    /// Parses an artist cell.
+    ///
+    /// The artists of a split are separated by " / ", and each of them is
+    /// given the link whose text is their name, if any. Otherwise, each
+    /// artist of a collaboration has their own link in the cell, whereas a
+    /// band like "Author & Punisher" has a single one.
+    fn parse(cell: ElementRef, citations: &Citations) -> Self {
+        let name = cell_text(cell);
+
+        let links = page_links(cell)
+            .into_iter()
+            .map(|(text, title)| Participant::new(text, title))
+            .collect::<Vec<_>>();
+
+        let (wikipedia, participants) = match links.len() {
+            _ if name.contains(" / ") => (
+                None,
+                name.split(" / ")
+                    .map(|part| {
+                        let part = part.trim();
+                        let wikipedia = links
+                            .iter()
+                            .find(|link| link.name == part)
+                            .and_then(|link| link.wikipedia.clone());
+                        Participant::new(part, wikipedia)
+                    })
+                    .collect(),
+            ),
+            0 => (None, vec![Participant::new(name.clone(), None)]),
+            1 => {
+                let wikipedia = links[0].wikipedia.clone();
+                (
+                    wikipedia.clone(),
+                    vec![Participant::new(name.clone(), wikipedia)],
+                )
+            }
+            _ => (None, links),
+        };
+
+        Self {
+            name,
+            wikipedia,
+            participants,
+            source: citations.sources(cell).into_iter().next(),
+        }
+    }
+
+    /// Creates the release of the album cell. The source cited for the
+    /// album takes precedence over the one cited for the artist.
+    fn release(&self, album_cell: ElementRef, citations: &Citations) -> Release {
+        let wikipedia_album = page_links(album_cell)
+            .into_iter()
+            .find_map(|(_, title)| title);
+        let source = citations
+            .sources(album_cell)
+            .into_iter()
+            .next()
+            .or_else(|| self.source.clone());
+
+        Release::new(self.name.clone(), cell_text(album_cell))
+            .with_artists(self.participants.clone())
+            .with_wikipedia(self.wikipedia.clone(), wikipedia_album)
+            .with_source(source)
+    }
+}
+
+/// The sources cited in the page's references, keyed by the id of
+/// their footnote, e.g. `cite_note-12`.
+struct Citations(HashMap<String, String>);
+
+impl Citations {
+    fn parse(doc: &Html) -> Self {
+        let note_selector = Selector::parse(r#"li[id^="cite_note-"]"#).unwrap();
+        let link_selector = Selector::parse("a.external").unwrap();
+
+        let sources = doc
+            .select(&note_selector)
+            .filter_map(|note| {
+                let id = note.value().id()?;
+                let url = note.select(&link_selector).next()?.value().attr("href")?;
+                Some((id.to_string(), url.to_string()))
+            })
+            .collect();
+
+        Self(sources)
+    }
+
+    /// Resolves the citation markers of a cell, e.g. `[12]`, to the
+    /// URLs of the sources they cite, in order of appearance.
+    fn sources(&self, cell: ElementRef) -> Vec<String> {
+        let selector = Selector::parse("sup.reference a").unwrap();
+
+        let mut sources = Vec::new();
+        for link in cell.select(&selector) {
+            let Some(id) = link.value().attr("href").and_then(|h| h.strip_prefix('#')) else {
+                continue;
+            };
+
+            if let Some(url) = self.0.get(url_escape::decode(id).as_ref()) {
+                if !sources.contains(url) {
+                    sources.push(url.clone());
+                }
+            }
+        }
+        sources
+    }
+}
+
+/// Collects the text of a cell, leaving out citation markers such as
+/// `[12]` and maintenance tags such as `[citation needed]`.
+fn cell_text(cell: ElementRef) -> String {
+    fn collect(element: ElementRef, text: &mut String) {
+        for child in element.children() {
+            match child.value() {
+                Node::Text(t) => text.push_str(t),
+                Node::Element(e) if e.name() == "sup" => {
+                    let is_note = e.classes().any(|c| c == "reference" || c == "noprint");
+                    if !is_note {
+                        if let Some(child) = ElementRef::wrap(child) {
+                            collect(child, text);
+                        }
+                    }
+                }
+                Node::Element(_) => {
+                    if let Some(child) = ElementRef::wrap(child) {
+                        collect(child, text);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let mut text = String::new();
+    collect(cell, &mut text);
+    text.trim().to_string()
+}
+
+/// Iterates over the text and the Wikipedia page title of the links in a
+/// cell, skipping citation links. The title is `None` for links to pages
+/// that do not exist yet.
+fn page_links(cell: ElementRef) -> Vec<(String, Option<String>)> {
+    let selector = Selector::parse("a").unwrap();
+    cell.select(&selector)
+        .filter_map(|a| {
+            let href = a.value().attr("href")?;
+            if href.starts_with('#') || a.value().classes().any(|c| c == "external") {
+                return None;
+            }
+
+            let text = a.text().collect::<String>().trim().to_string();
+            if text.is_empty() {
+                return None;
+            }
+
+            Some((text, wikipedia_title(href)))
+        })
+        .collect()
+}
+
+/// Extracts the page title from a link such as `/wiki/Ghost_(Swedish_band)`.
+fn wikipedia_title(href: &str) -> Option<String> {
+    let title = href.strip_prefix("/wiki/")?;
+    let title = title.split('#').next().unwrap_or(title);
+    if title.is_empty() {
+        return None;
+    }
+    Some(url_escape::decode(title).into_owned())
+}
+
 fn process_table(
     table: ElementRef,
+    citations: &Citations,
     calendar: &mut Calendar,
     month: Month,
     current_day: &mut u8,
-    current_artist: &mut String,
+    current_artist: &mut ArtistCell,
 ) {
     let selector = &Selector::parse("tbody tr").unwrap();
     table.select(selector).for_each(|row| {
         let cells = row.child_elements().collect::<Vec<_>>();
         match cells.len() {
-            1 => {
-                let album = cells[0].text().collect::<String>();
+            1 => calendar.add_release(
+                month,
+                *current_day,
+                current_artist.release(cells[0], citations),
+            ),
+            2 => {
+                *current_artist = ArtistCell::parse(cells[0], citations);
                 calendar.add_release(
                     month,
                     *current_day,
-                    Release::new(current_artist.clone(), album.trim()),
+                    current_artist.release(cells[1], citations),
                 )
             }
-            2 => {
-                let artist = cells[0].text().collect::<String>();
-                let artist = artist.trim();
-                *current_artist = String::from(artist);
-
-                let album = cells[1].text().collect::<String>();
-                let album = album.trim();
-
-                calendar.add_release(month, *current_day, Release::new(artist, album))
-            }
             3 => {
-                let day: core::result::Result<u8, _> =
-                    cells[0].text().collect::<String>().trim().parse();
+                let day: core::result::Result<u8, _> = cell_text(cells[0]).parse();
                 if let Ok(day) = day {
                     *current_day = day;
                 }
 
-                let artist = cells[1].text().collect::<String>();
-                let artist = artist.trim();
-                *current_artist = String::from(artist);
-
-                let album = cells[2].text().collect::<String>();
-                let album = album.trim();
+                *current_artist = ArtistCell::parse(cells[1], citations);
 
-                if artist != "Artist" {
-                    calendar.add_release(month, *current_day, Release::new(artist, album.trim()));
+                if current_artist.name != "Artist" {
+                    calendar.add_release(
+                        month,
+                        *current_day,
+                        current_artist.release(cells[2], citations),
+                    );
                 }
             }
             _ => {}
@@ -128,16 +431,19 @@ mod tests {
 
     use crate::{
         calendar::{CalendarData, Releases},
-        scraper::client::tests::MockClient,
+        scraper::client::{tests::MockClient, MainClient},
     };
 
     type Result<T> = core::result::Result<T, Box<dyn std::error::Error>>;
 
+    // The year tests scrape the live pages of Wikipedia, so they only run
+    // with `cargo test -- --ignored`.
     #[tokio::test]
+    #[ignore = "fetches the live Wikipedia page"]
     async fn test_2022_calendar_ok() -> Result<()> {
-        let client = MockClient::new();
+        let client = MainClient::with_proxy(None)?;
 
-        let got = client.scrape(2022).await?;
+        let got = scrape(&client, 2022).await?;
 
         let want = Calendar {
 			year: 2022,
@@ -153,7 +459,7 @@ mod tests {
 						Release::new("Fit for an Autopsy", "Oh What the Future Holds"),
 						Release::new("Ilium", "Quantum Evolution Event (EP)"),
 						Release::new("Shadow of Intent", "Elegy"),
-						Release::new("Skillet", "Dominion"),
+						Release::new("Skillet", "Dominion").with_wikipedia(Some("Skillet_(band)".to_string()), Some("Dominion_(Skillet_album)".to_string())),
 						Release::new("Tony Martin", "Thorns"),
 						Release::new("Underoath", "Voyeurist"),
                     ]),
@@ -188,7 +494,7 @@ mod tests {
 						Release::new("Abysmal Dawn", "Nightmare Frontier (EP)"),
 						Release::new("Bevar Sea", "The Timeless Zone"),
 						Release::new("Hed PE", "Califas Worldwide"),
-						Release::new("Korn", "Requiem"),
+						Release::new("Korn", "Requiem").with_wikipedia(Some("Korn".to_string()), Some("Requiem_(Korn_album)".to_string())),
 						Release::new("Mystic Circle", "Mystic Circle"),
 						Release::new("Persefone", "Metanoia"),
 						Release::new("Rolo Tomassi", "Where Myth Becomes Memory"),
@@ -196,7 +502,7 @@ mod tests {
 						Release::new("Venom Prison", "Erebos"),
 					]),
 					(11, vec![
-						Release::new("Amorphis", "Halo"),
+						Release::new("Amorphis", "Halo").with_wikipedia(Some("Amorphis".to_string()), Some("Halo_(Amorphis_album)".to_string())),
 						Release::new("Author & Punisher", "Krüller"),
 						Release::new("Cult of Luna", "The Long Road North"),
 						Release::new("Girish and The Chronicles", "Hail to the Heroes"),
@@ -853,15 +1159,51 @@ mod tests {
 				])),
             ])
         };
+        let linked = [
+            (
+                Month::January,
+                14,
+                "Skillet",
+                "Skillet_(band)",
+                "Dominion_(Skillet_album)",
+            ),
+            (Month::February, 4, "Korn", "Korn", "Requiem_(Korn_album)"),
+            (
+                Month::February,
+                11,
+                "Amorphis",
+                "Amorphis",
+                "Halo_(Amorphis_album)",
+            ),
+        ];
+        for (month, day, artist, wikipedia_artist, wikipedia_album) in linked {
+            let release = got
+                .get_releases(month, day)
+                .and_then(|releases| releases.iter().find(|release| release.artist == artist))
+                .unwrap_or_else(|| panic!("missing the release of {artist}"));
+            pretty_assertions::assert_eq!(
+                release.artists,
+                vec![Participant::new(artist, Some(wikipedia_artist.to_string()))]
+            );
+            pretty_assertions::assert_eq!(
+                release.wikipedia_artist.as_deref(),
+                Some(wikipedia_artist)
+            );
+            pretty_assertions::assert_eq!(
+                release.wikipedia_album.as_deref(),
+                Some(wikipedia_album)
+            );
+        }
         pretty_assertions::assert_eq!(got, want);
         Ok(())
     }
 
     #[tokio::test]
+    #[ignore = "fetches the live Wikipedia page"]
     async fn test_2023_calendar_ok() -> Result<()> {
-        let client = MockClient::new();
+        let client = MainClient::with_proxy(None)?;
 
-        let got = client.scrape(2023).await?;
+        let got = scrape(&client, 2023).await?;
 
         let want = Calendar {
 			year: 2023,
@@ -1491,10 +1833,11 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "fetches the live Wikipedia page"]
     async fn test_2024_calendar_ok() -> Result<()> {
-        let client = MockClient::new();
+        let client = MainClient::with_proxy(None)?;
 
-        let got = client.scrape(2024).await?;
+        let got = scrape(&client, 2024).await?;
 
         let want = Calendar {
 			year: 2024,
@@ -2078,6 +2421,194 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_latest_revision_ok() -> Result<()> {
+        let client = MockClient::new().with_fixture(
+            "https://en.wikipedia.org/w/api.php?action=query&prop=revisions&rvprop=ids&titles=2024_in_heavy_metal_music&format=json&formatversion=2",
+            "wiki_revision.json",
+        );
+
+        let got = latest_revision(&client, 2024).await?;
+
+        pretty_assertions::assert_eq!(got, 1251234567);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_parse_revision_ok() -> Result<()> {
+        let client = MockClient::new().with_fixture(
+            "https://en.wikipedia.org/w/api.php?action=parse&oldid=1251234567&prop=text&format=json&formatversion=2",
+            "wiki_parse.json",
+        );
+
+        let got = extract_calendar(parse_revision(&client, 1251234567).await?, 2024);
+
+        let mut want = Calendar::new(2024);
+        want.add_release(
+            Month::January,
+            12,
+            Release::new("Hanging Garden", "The Garden")
+                .with_artists(vec![Participant::new(
+                    "Hanging Garden",
+                    Some("Hanging_Garden_(band)".to_string()),
+                )])
+                .with_wikipedia(Some("Hanging_Garden_(band)".to_string()), None),
+        );
+        pretty_assertions::assert_eq!(got, want);
+        Ok(())
+    }
+
+    #[test]
+    fn test_extract_snapshot_ok() -> Result<()> {
+        let body = std::fs::read_to_string("./tests/testdata/wiki_parse.json")?;
+
+        let got = extract_snapshot(&body)?;
+
+        pretty_assertions::assert_eq!(got.year, 2024);
+        let releases = got.get_releases(Month::January, 12).unwrap();
+        pretty_assertions::assert_eq!(releases[0].artist, "Hanging Garden");
+        pretty_assertions::assert_eq!(releases[0].album, "The Garden");
+        Ok(())
+    }
+
+    #[test]
+    fn test_artist_cell_ok() -> Result<()> {
+        let test_cases = vec![
+            (
+                r#"<td><a href="/wiki/Author_%26_Punisher">Author &amp; Punisher</a></td>"#,
+                "Author & Punisher",
+                Some("Author_&_Punisher"),
+                vec![("Author & Punisher", Some("Author_&_Punisher"))],
+            ),
+            (
+                r#"<td><a href="/wiki/Ghost_(Swedish_band)">Ghost</a></td>"#,
+                "Ghost",
+                Some("Ghost_(Swedish_band)"),
+                vec![("Ghost", Some("Ghost_(Swedish_band)"))],
+            ),
+            (
+                r#"<td><a href="/wiki/Bell_Witch">Bell Witch</a> / <a href="/w/index.php?title=Aerial_Ruin&action=edit&redlink=1">Aerial Ruin</a></td>"#,
+                "Bell Witch / Aerial Ruin",
+                None,
+                vec![("Bell Witch", Some("Bell_Witch")), ("Aerial Ruin", None)],
+            ),
+            (
+                r#"<td><a href="/wiki/Bell_Witch">Bell Witch</a> / Aerial Ruin</td>"#,
+                "Bell Witch / Aerial Ruin",
+                None,
+                vec![("Bell Witch", Some("Bell_Witch")), ("Aerial Ruin", None)],
+            ),
+            (
+                r#"<td>Ulthar / <a href="/wiki/Pandiscordian_Necrogenesis">Pandiscordian Necrogenesis</a> / Spectral Voice</td>"#,
+                "Ulthar / Pandiscordian Necrogenesis / Spectral Voice",
+                None,
+                vec![
+                    ("Ulthar", None),
+                    (
+                        "Pandiscordian Necrogenesis",
+                        Some("Pandiscordian_Necrogenesis"),
+                    ),
+                    ("Spectral Voice", None),
+                ],
+            ),
+            (
+                r##"<td><a href="/wiki/Slash_(musician)">Slash</a> feat. <a href="/wiki/Myles_Kennedy">Myles Kennedy</a><sup class="reference"><a href="#cite_note-1">[1]</a></sup></td>"##,
+                "Slash feat. Myles Kennedy",
+                None,
+                vec![
+                    ("Slash", Some("Slash_(musician)")),
+                    ("Myles Kennedy", Some("Myles_Kennedy")),
+                ],
+            ),
+            (
+                "<td>Ulthar / Pandiscordian Necrogenesis</td>",
+                "Ulthar / Pandiscordian Necrogenesis",
+                None,
+                vec![("Ulthar", None), ("Pandiscordian Necrogenesis", None)],
+            ),
+        ];
+
+        for (html, want_name, want_wikipedia, want_participants) in test_cases {
+            let doc = Html::parse_fragment(&format!("<table><tr>{html}</tr></table>"));
+            let cell = doc.select(&Selector::parse("td").unwrap()).next().unwrap();
+
+            let got = ArtistCell::parse(cell, &Citations(HashMap::new()));
+
+            pretty_assertions::assert_eq!(got.name, want_name);
+            pretty_assertions::assert_eq!(got.wikipedia.as_deref(), want_wikipedia);
+            pretty_assertions::assert_eq!(
+                got.participants,
+                want_participants
+                    .into_iter()
+                    .map(|(name, title)| Participant::new(name, title.map(String::from)))
+                    .collect::<Vec<_>>()
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_album_cell_wikipedia_ok() -> Result<()> {
+        let doc = Html::parse_fragment(
+            r##"<table><tr>
+                <td><a href="/wiki/Ghost_(Swedish_band)">Ghost</a></td>
+                <td><i><a href="/wiki/Impera_(album)">Impera</a></i><sup class="reference"><a href="#cite_note-2">[2]</a></sup></td>
+            </tr></table>"##,
+        );
+        let selector = Selector::parse("td").unwrap();
+        let cells = doc.select(&selector).collect::<Vec<_>>();
+
+        let citations = Citations(HashMap::new());
+
+        let got = ArtistCell::parse(cells[0], &citations).release(cells[1], &citations);
+
+        pretty_assertions::assert_eq!(got.album, "Impera");
+        pretty_assertions::assert_eq!(
+            got.wikipedia_artist.as_deref(),
+            Some("Ghost_(Swedish_band)")
+        );
+        pretty_assertions::assert_eq!(got.wikipedia_album.as_deref(), Some("Impera_(album)"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_citations_ok() -> Result<()> {
+        let doc = Html::parse_document(
+            r##"<html><body>
+                <table id="table_March"><tbody>
+                    <tr><th>Day</th><th>Artist</th><th>Album</th></tr>
+                    <tr>
+                        <td rowspan="2">11</td>
+                        <td><a href="/wiki/Ghost_(Swedish_band)">Ghost</a><sup class="reference"><a href="#cite_note-%3A0-1">[1]</a></sup></td>
+                        <td><i>Impera</i><sup class="reference"><a href="#cite_note-2">[2]</a></sup></td>
+                    </tr>
+                    <tr>
+                        <td>Kiss</td>
+                        <td><i>Songs [From the Vault]</i><sup class="noprint Inline-Template">[<i>citation needed</i>]</sup></td>
+                    </tr>
+                </tbody></table>
+                <ol class="references">
+                    <li id="cite_note-:0-1"><span class="reference-text"><cite><a rel="nofollow" class="external text" href="https://www.blabbermouth.net/news/ghost">Ghost</a></cite></span></li>
+                    <li id="cite_note-2"><span class="reference-text"><cite><a rel="nofollow" class="external text" href="https://label.example/impera">Impera</a></cite></span></li>
+                </ol>
+            </body></html>"##,
+        );
+
+        let got = extract_calendar(doc, 2022);
+
+        let releases = got.get_releases(Month::March, 11).unwrap();
+        pretty_assertions::assert_eq!(releases[0].artist, "Ghost");
+        pretty_assertions::assert_eq!(releases[0].album, "Impera");
+        pretty_assertions::assert_eq!(
+            releases[0].source_url.as_deref(),
+            Some("https://label.example/impera")
+        );
+        pretty_assertions::assert_eq!(releases[1].artist, "Kiss");
+        pretty_assertions::assert_eq!(releases[1].album, "Songs [From the Vault]");
+        pretty_assertions::assert_eq!(releases[1].source_url, None);
+        Ok(())
+    }
+
     fn compare_calendars(got: Calendar, want: Calendar) {
         for (month, releases) in want.data.iter() {
             match got.data.get(month) {
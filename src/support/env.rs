@@ -7,3 +7,29 @@ pub fn get_env(name: &'static str) -> Result<String> {
         .map(|v| v.trim_matches('"').to_string())
         .map_err(|_| Error::MissingEnv(name))
 }
+
+pub fn get_env_or(name: &'static str, default: &str) -> String {
+    get_env(name).unwrap_or_else(|_| default.to_string())
+}
+
+/// Sets the variables the configuration requires for the tests, which run
+/// against a database of their own in the temporary folder.
+#[cfg(test)]
+pub fn set_test_env() {
+    use std::sync::Once;
+
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        let database = env::temp_dir().join(format!(
+            "heavy-metal-notifier-test-{}.db",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&database);
+
+        env::set_var("DATABASE_URL", database);
+        env::set_var("BASE_URL", "http://localhost:7125");
+        env::set_var("IS_PROD", "false");
+        env::set_var("SERVICE_WEB_FOLDER", "web/static");
+    });
+}
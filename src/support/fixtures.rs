@@ -0,0 +1,70 @@
+//! Builds the releases the tests work with.
+
+use crate::model::{Artist, Release, ReleaseLink, ReleaseWithArtists};
+
+/// Starts building an album of the artist released on October 18, 2024.
+///
+/// The artists of a credit like `Ulthar / Spectral Voice` are split, and
+/// the release links to the Bandcamp page of its first artist.
+pub fn release(id: i32, artist: &str, album: &str) -> ReleaseBuilder {
+    ReleaseBuilder {
+        release: Release {
+            id,
+            year: 2024,
+            month: 10,
+            day: 18,
+            artist_id: id,
+            album: album.to_string(),
+            release_type: "album".to_string(),
+            artist_credit: artist.to_string(),
+            wikipedia_album: None,
+            source_url: None,
+            providers: "wikipedia".to_string(),
+            musicbrainz_release_group_id: None,
+            musicbrainz_release_id: None,
+            track_count: None,
+            cover: None,
+            label: None,
+            genre: None,
+        },
+    }
+}
+
+pub struct ReleaseBuilder {
+    release: Release,
+}
+
+impl ReleaseBuilder {
+    pub fn date(mut self, month: i32, day: i32) -> Self {
+        self.release.month = month;
+        self.release.day = day;
+        self
+    }
+
+    pub fn genre(mut self, genre: &str) -> Self {
+        self.release.genre = Some(genre.to_string());
+        self
+    }
+
+    pub fn build(self) -> ReleaseWithArtists {
+        let id = self.release.id;
+        let artists = self
+            .release
+            .artist_credit
+            .split(" / ")
+            .map(|name| Artist {
+                id,
+                name: name.to_string(),
+                wikipedia: String::new(),
+            })
+            .collect();
+        let links = vec![ReleaseLink {
+            id,
+            release_id: id,
+            kind: "bandcamp".to_string(),
+            url: format!("https://band{id}.bandcamp.com"),
+        }];
+
+        (self.release, artists, links)
+    }
+}
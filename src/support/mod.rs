@@ -1 +1,6 @@
 pub mod env;
+#[cfg(test)]
+pub mod fixtures;
+pub mod names;
+pub mod signature;
+pub mod token;
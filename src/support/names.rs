@@ -0,0 +1,45 @@
+/// Measures how alike two names are, from 0 to 1, with the Sørensen–Dice
+/// coefficient of their letter pairs. Case and punctuation are ignored.
+pub fn similarity(a: &str, b: &str) -> f32 {
+    normalized_similarity(&normalize(a), &normalize(b))
+}
+
+/// The letters and digits of the name in lowercase, which is all
+/// [`similarity`] compares.
+pub fn normalize(name: &str) -> Vec<char> {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// The [`similarity`] of two names already normalized with [`normalize`].
+pub fn normalized_similarity(a: &[char], b: &[char]) -> f32 {
+    if a == b {
+        return 1.0;
+    } else if a.len() < 2 || b.len() < 2 {
+        return 0.0;
+    }
+
+    let mut pairs_b = b.windows(2).collect::<Vec<_>>();
+    let mut num_shared = 0;
+    for pair in a.windows(2) {
+        if let Some(index) = pairs_b.iter().position(|other| *other == pair) {
+            pairs_b.swap_remove(index);
+            num_shared += 1;
+        }
+    }
+
+    (2 * num_shared) as f32 / (a.len() + b.len() - 2) as f32
+}
+
+/// The highest [`similarity`] two normalized names of these lengths can
+/// have, as they share at most the letter pairs of the shorter one.
+pub fn max_similarity(len_a: usize, len_b: usize) -> f32 {
+    if len_a == len_b {
+        return 1.0;
+    } else if len_a < 2 || len_b < 2 {
+        return 0.0;
+    }
+    (2 * (len_a.min(len_b) - 1)) as f32 / (len_a + len_b - 2) as f32
+}
@@ -0,0 +1,44 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Signs the payload with HMAC-SHA256 and returns the hex-encoded signature.
+pub fn sign(secret: &[u8], payload: &[u8]) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC accepts keys of any size");
+    mac.update(payload);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Checks a hex-encoded signature of the payload in constant time.
+pub fn verify(secret: &[u8], payload: &[u8], signature: &str) -> bool {
+    let Ok(signature) = hex::decode(signature) else {
+        return false;
+    };
+
+    let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC accepts keys of any size");
+    mac.update(payload);
+    mac.verify_slice(&signature).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Result<T> = core::result::Result<T, Box<dyn std::error::Error>>;
+
+    #[test]
+    fn test_sign_ok() -> Result<()> {
+        // Test case 2 of RFC 4231.
+        let got = sign(b"Jefe", b"what do ya want for nothing?");
+
+        pretty_assertions::assert_eq!(
+            got,
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert!(verify(b"Jefe", b"what do ya want for nothing?", &got));
+        assert!(!verify(b"Jefe", b"what do ya want for something?", &got));
+        assert!(!verify(b"Jefe", b"what do ya want for nothing?", "not hex"));
+        Ok(())
+    }
+}
@@ -0,0 +1,13 @@
+use rand::{distributions::Alphanumeric, Rng};
+
+/// The number of characters of a token.
+const TOKEN_LEN: usize = 32;
+
+/// Generates a random token that cannot be guessed, e.g. for the links of emails.
+pub fn generate_token() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(TOKEN_LEN)
+        .map(char::from)
+        .collect()
+}
@@ -1,11 +1,17 @@
-use std::sync::OnceLock;
+use std::{net::IpAddr, sync::OnceLock};
 
-use crate::{error::Result, support::env::get_env};
+use crate::{
+    error::{Error, Result},
+    support::{env::get_env, token::generate_token},
+};
 
 pub fn web_config() -> &'static WebConfig {
     static INSTANCE: OnceLock<WebConfig> = OnceLock::new();
 
     INSTANCE.get_or_init(|| {
+        #[cfg(test)]
+        crate::support::env::set_test_env();
+
         WebConfig::load_from_env()
             .unwrap_or_else(|err| panic!("Fatal - Could not load configuration: {err:?}"))
     })
@@ -13,12 +19,30 @@ pub fn web_config() -> &'static WebConfig {
 
 #[allow(non_snake_case)]
 pub struct WebConfig {
+    /// The secret the form tokens are signed with. A random secret is
+    /// generated on startup when unset, which invalidates the forms loaded
+    /// before a restart.
+    pub FORM_SECRET: String,
+    /// The reverse proxies whose forwarded client addresses are trusted,
+    /// besides those on localhost.
+    pub TRUSTED_PROXIES: Vec<IpAddr>,
     pub WEB_FOLDER: String,
 }
 
 impl WebConfig {
     fn load_from_env() -> Result<WebConfig> {
         Ok(WebConfig {
+            FORM_SECRET: get_env("FORM_SECRET")
+                .ok()
+                .filter(|secret| !secret.is_empty())
+                .unwrap_or_else(generate_token),
+            TRUSTED_PROXIES: get_env("TRUSTED_PROXIES")
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|ip| !ip.is_empty())
+                .map(|ip| ip.parse().map_err(|_| Error::InvalidEnv("TRUSTED_PROXIES")))
+                .collect::<Result<_>>()?,
             WEB_FOLDER: get_env("SERVICE_WEB_FOLDER")?,
         })
     }
@@ -0,0 +1,455 @@
+use axum::{
+    async_trait,
+    extract::{DefaultBodyLimit, FromRequestParts, Multipart, Path},
+    http::{
+        header::{COOKIE, SET_COOKIE},
+        request::Parts,
+        HeaderMap, StatusCode,
+    },
+    middleware,
+    response::{IntoResponse, Redirect, Response},
+    routing::{get, post},
+    Form, Router,
+};
+use lettre::Address;
+use serde::Deserialize;
+use tracing::error;
+
+use crate::{
+    config::config,
+    error::Error,
+    imports::{self, ImportFormat},
+    mailer::Mailer,
+    model::{ArtistBmc, User, UserBmc, SESSION_TTL},
+    scraper::source::genres_enabled,
+};
+
+use super::{
+    spam::{limit_submissions, Guarded, GuardedForm},
+    templates::{self, account::LoginStatus},
+};
+
+/// The name of the cookie holding the session token.
+const SESSION_COOKIE: &str = "session";
+
+/// The maximum size of an uploaded export, since streaming histories
+/// exceed the default limit of 2 MB.
+const MAX_IMPORT_SIZE: usize = 20 * 1024 * 1024;
+
+pub fn routes_accounts() -> Router {
+    Router::new()
+        .route("/", get(settings))
+        .route(
+            "/login",
+            get(|| async { templates::account::login(LoginStatus::Blank) })
+                .post(send_login_link)
+                .route_layer(middleware::from_fn(limit_submissions)),
+        )
+        .route("/login/:token", get(login_page).post(login))
+        .route("/logout", post(logout))
+        .route("/feed", post(reset_feed_token))
+        .route("/artists", post(follow_artist))
+        .route("/artists/:id/remove", post(unfollow_artist))
+        .route("/genres", post(follow_genre))
+        .route("/genres/remove", post(unfollow_genre))
+        .route(
+            "/import",
+            get(import_page)
+                .post(import)
+                .layer(DefaultBodyLimit::max(MAX_IMPORT_SIZE)),
+        )
+        .route("/import/save", post(save_import))
+}
+
+/// The logged-in user. Requests without a valid session are redirected to
+/// the login page.
+///
+/// The session cookie is `SameSite=Lax`, so the forms of other sites cannot
+/// post to the account's pages on behalf of the user.
+pub struct CurrentUser(pub User);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for CurrentUser
+where
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Some(token) = session_token(&parts.headers) else {
+            return Err(Redirect::to("/account/login").into_response());
+        };
+
+        match UserBmc::get_by_session(&token) {
+            Ok(Some(user)) => Ok(CurrentUser(user)),
+            Ok(None) => Err(Redirect::to("/account/login").into_response()),
+            Err(err) => {
+                error!("getting user of session: {err}");
+                Err((
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Could not fetch the session.",
+                )
+                    .into_response())
+            }
+        }
+    }
+}
+
+/// The token of the session cookie, if any.
+fn session_token(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|cookie| cookie.trim().split_once('='))
+        .find(|(name, _)| *name == SESSION_COOKIE)
+        .map(|(_, token)| token.to_string())
+}
+
+/// The `Set-Cookie` header of the session cookie. An empty token removes the cookie.
+fn session_cookie(token: &str) -> String {
+    let max_age = if token.is_empty() {
+        0
+    } else {
+        SESSION_TTL.whole_seconds()
+    };
+    let secure = if config().IS_PROD { "; Secure" } else { "" };
+    format!("{SESSION_COOKIE}={token}; Path=/; Max-Age={max_age}; HttpOnly; SameSite=Lax{secure}")
+}
+
+#[derive(Deserialize)]
+struct LoginForm {
+    email: String,
+}
+
+impl GuardedForm for LoginForm {}
+
+async fn send_login_link(Guarded(form): Guarded<LoginForm>) -> Response {
+    let email = form.email.trim();
+    if email.parse::<Address>().is_err() {
+        return (
+            StatusCode::BAD_REQUEST,
+            templates::account::login(LoginStatus::Rejected {
+                email,
+                error: "The email address is invalid.",
+            }),
+        )
+            .into_response();
+    }
+
+    let mailer = match Mailer::from_config() {
+        Ok(Some(mailer)) => mailer,
+        Ok(None) => {
+            error!("Cannot send the login link: SMTP_HOST is not set");
+            return (
+                StatusCode::SERVICE_UNAVAILABLE,
+                templates::account::login(LoginStatus::Rejected {
+                    email,
+                    error: "Logging in is not available at the moment. Please try again later.",
+                }),
+            )
+                .into_response();
+        }
+        Err(err) => {
+            error!("creating mailer: {err}");
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Could not send the login link.",
+            )
+                .into_response();
+        }
+    };
+
+    // An address that was just sent a link gets no other for a while, so
+    // the form can't be used to flood an inbox.
+    let token = match UserBmc::create_login_token(email) {
+        Ok(Some(token)) => token,
+        Ok(None) => return login_link_sent(),
+        Err(err) => {
+            error!("creating login token: {err}");
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Could not send the login link.",
+            )
+                .into_response();
+        }
+    };
+    if let Err(err) = mailer
+        .send(templates::emails::login_link(email, &token))
+        .await
+    {
+        error!("sending login link: {err}");
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Could not send the login link.",
+        )
+            .into_response();
+    }
+
+    login_link_sent()
+}
+
+fn login_link_sent() -> Response {
+    templates::general::simple(
+        "Check Your Inbox",
+        "We sent you an email with a link to log in.",
+    )
+    .into_response()
+}
+
+async fn login_page(Path(token): Path<String>) -> Response {
+    templates::account::login_confirm(&token).into_response()
+}
+
+async fn login(Path(token): Path<String>) -> Response {
+    let user = match UserBmc::consume_login_token(&token) {
+        Ok(Some(user)) => user,
+        Ok(None) => {
+            return (
+                StatusCode::NOT_FOUND,
+                templates::general::simple(
+                    "Link Expired",
+                    "The login link expired or was already used. Please ask for a new one.",
+                ),
+            )
+                .into_response()
+        }
+        Err(err) => {
+            error!("consuming login token: {err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Could not log in.").into_response();
+        }
+    };
+
+    match UserBmc::create_session(user.id) {
+        Ok(session) => (
+            [(SET_COOKIE, session_cookie(&session))],
+            Redirect::to("/account"),
+        )
+            .into_response(),
+        Err(err) => {
+            error!("creating session: {err}");
+            (StatusCode::INTERNAL_SERVER_ERROR, "Could not log in.").into_response()
+        }
+    }
+}
+
+async fn logout(headers: HeaderMap) -> Response {
+    if let Some(token) = session_token(&headers) {
+        if let Err(err) = UserBmc::delete_session(&token) {
+            error!("deleting session: {err}");
+        }
+    }
+    ([(SET_COOKIE, session_cookie(""))], Redirect::to("/")).into_response()
+}
+
+/// Renders the settings, with the error of the last action if any.
+fn settings_page(user: &User, error: Option<&str>) -> Response {
+    match UserBmc::get_watchlist(user.id) {
+        Ok(watchlist) => {
+            let status = if error.is_some() {
+                StatusCode::BAD_REQUEST
+            } else {
+                StatusCode::OK
+            };
+            (
+                status,
+                templates::account::settings(user, &watchlist, error),
+            )
+                .into_response()
+        }
+        Err(err) => {
+            error!("getting watchlist of user {}: {err}", user.id);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Could not fetch the watchlist.",
+            )
+                .into_response()
+        }
+    }
+}
+
+async fn settings(CurrentUser(user): CurrentUser) -> Response {
+    settings_page(&user, None)
+}
+
+/// Redirects to the settings once an action succeeded, so that reloading
+/// the page does not submit the form again.
+fn saved(result: crate::error::Result<()>, action: &str) -> Response {
+    match result {
+        Ok(()) => Redirect::to("/account").into_response(),
+        Err(err) => {
+            error!("{action}: {err}");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Could not save the settings.",
+            )
+                .into_response()
+        }
+    }
+}
+
+async fn reset_feed_token(CurrentUser(user): CurrentUser) -> Response {
+    saved(
+        UserBmc::reset_feed_token(user.id).map(|_| ()),
+        "resetting feed token",
+    )
+}
+
+#[derive(Deserialize)]
+struct ArtistForm {
+    name: String,
+}
+
+/// Follows the artists whose name is the one entered, regardless of case.
+async fn follow_artist(CurrentUser(user): CurrentUser, Form(form): Form<ArtistForm>) -> Response {
+    let name = form.name.trim();
+    let ids = match ArtistBmc::get_by_name(name) {
+        Ok(artists) => artists
+            .into_iter()
+            .map(|artist| artist.id)
+            .collect::<Vec<_>>(),
+        Err(err) => {
+            error!("getting artists named {name}: {err}");
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Could not fetch the artists.",
+            )
+                .into_response();
+        }
+    };
+    if ids.is_empty() {
+        let error = format!("No artist named \"{name}\" is in the calendar.");
+        return settings_page(&user, Some(&error));
+    }
+
+    saved(
+        UserBmc::add_artists(user.id, &ids).map(|_| ()),
+        "following artist",
+    )
+}
+
+async fn unfollow_artist(CurrentUser(user): CurrentUser, Path(id): Path<i32>) -> Response {
+    saved(UserBmc::remove_artist(user.id, id), "unfollowing artist")
+}
+
+#[derive(Deserialize)]
+struct GenreForm {
+    genre: String,
+}
+
+async fn follow_genre(CurrentUser(user): CurrentUser, Form(form): Form<GenreForm>) -> Response {
+    if !genres_enabled() {
+        return settings_page(&user, Some("Genres cannot be followed at the moment."));
+    }
+    if form.genre.trim().is_empty() {
+        return settings_page(&user, Some("The genre is empty."));
+    }
+    saved(UserBmc::add_genre(user.id, &form.genre), "following genre")
+}
+
+async fn unfollow_genre(CurrentUser(user): CurrentUser, Form(form): Form<GenreForm>) -> Response {
+    saved(
+        UserBmc::remove_genre(user.id, &form.genre),
+        "unfollowing genre",
+    )
+}
+
+async fn import_page(CurrentUser(_): CurrentUser) -> Response {
+    templates::account::import(None).into_response()
+}
+
+/// Matches the artists of an uploaded export against the calendar, for the
+/// user to review them before following them.
+async fn import(CurrentUser(_): CurrentUser, mut multipart: Multipart) -> Response {
+    let mut format = ImportFormat::default();
+    let mut content = None;
+    loop {
+        match multipart.next_field().await {
+            Ok(Some(field)) => match field.name() {
+                Some("format") => match field.text().await {
+                    Ok(text) => match text.parse() {
+                        Ok(parsed) => format = parsed,
+                        Err(_) => return import_error("The format of the file is unknown."),
+                    },
+                    Err(err) => return import_error(&err.body_text()),
+                },
+                Some("file") => match field.bytes().await {
+                    Ok(bytes) => content = Some(String::from_utf8_lossy(&bytes).into_owned()),
+                    Err(err) => return import_error(&err.body_text()),
+                },
+                _ => {}
+            },
+            Ok(None) => break,
+            Err(err) => return import_error(&err.body_text()),
+        }
+    }
+    let Some(content) = content.filter(|content| !content.trim().is_empty()) else {
+        return import_error("The file is empty.");
+    };
+
+    let names = match imports::parse_names(format, &content) {
+        Ok(names) if names.is_empty() => return import_error("No artist was found in the file."),
+        Ok(names) => names,
+        Err(Error::InvalidImport(reason)) => {
+            return import_error(&format!("The file could not be imported: {reason}."))
+        }
+        Err(_) => {
+            let error = format!("The file could not be read as a {}.", format.label());
+            return import_error(&error);
+        }
+    };
+
+    let artists = match ArtistBmc::get_all() {
+        Ok(artists) => artists,
+        Err(err) => {
+            error!("getting artists: {err}");
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Could not fetch the artists.",
+            )
+                .into_response();
+        }
+    };
+    let review = tokio::task::spawn_blocking(move || {
+        templates::account::import_review(&imports::match_artists(&names, &artists))
+    })
+    .await;
+    match review {
+        Ok(review) => review.into_response(),
+        Err(err) => {
+            error!("matching imported artists: {err}");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Could not match the artists.",
+            )
+                .into_response()
+        }
+    }
+}
+
+fn import_error(error: &str) -> Response {
+    (
+        StatusCode::BAD_REQUEST,
+        templates::account::import(Some(error)),
+    )
+        .into_response()
+}
+
+/// Follows the artists checked on the review of an import. The form is read
+/// as pairs because every checked artist repeats the `artist` field.
+async fn save_import(
+    CurrentUser(user): CurrentUser,
+    Form(fields): Form<Vec<(String, String)>>,
+) -> Response {
+    let ids = fields
+        .into_iter()
+        .filter(|(name, _)| name == "artist")
+        .filter_map(|(_, id)| id.parse().ok())
+        .collect::<Vec<i32>>();
+
+    saved(
+        UserBmc::add_artists(user.id, &ids).map(|_| ()),
+        "following imported artists",
+    )
+}
@@ -0,0 +1,51 @@
+use axum::{
+    extract::Path,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use tracing::error;
+
+use crate::error::Error;
+use crate::model::ArtistBmc;
+
+use super::templates;
+
+pub fn routes_artists() -> Router {
+    Router::new().route("/:id", get(artist))
+}
+
+async fn artist(Path(id): Path<i32>) -> Response {
+    let artist = match ArtistBmc::get(id) {
+        Ok(artist) => artist,
+        Err(Error::EntityNotFound { .. }) => return (
+            StatusCode::NOT_FOUND,
+            templates::general::simple(
+                "Artist Not Found",
+                "The artist you requested to view is not found. Please go back to the main page.",
+            ),
+        )
+            .into_response(),
+        Err(err) => {
+            error!("getting artist {id}: {err}");
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Could not fetch the artist.",
+            )
+                .into_response();
+        }
+    };
+
+    match ArtistBmc::get_releases(id) {
+        Ok(releases) => templates::artists::artist(&artist, &releases).into_response(),
+        Err(err) => {
+            error!("getting releases of artist {id}: {err}");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Could not fetch the artist's releases.",
+            )
+                .into_response()
+        }
+    }
+}
@@ -1,18 +1,166 @@
-use axum::{response::IntoResponse, routing::get, Router};
+use std::{collections::BTreeMap, path::Path};
+
+use axum::{
+    extract::{Path as UrlPath, Query},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
 use reqwest::{header::CONTENT_TYPE, StatusCode};
-use rss::{Channel, ChannelBuilder, Guid, Item, ItemBuilder};
-use time::OffsetDateTime;
+use rss::{
+    extension::{Extension, ExtensionMap},
+    Channel, ChannelBuilder, Enclosure, Guid, Item, ItemBuilder,
+};
+use serde::Deserialize;
+use time::{Duration, OffsetDateTime};
 use tracing::error;
 
+use crate::calendar::ReleaseType;
 use crate::config::config;
 use crate::error::Result;
-use crate::model::{CalendarBmc, FeedBmc, FeedForCreate};
+use crate::model::{
+    CalendarBmc, FeedBmc, FeedForCreate, Release, ReleaseFilter, ReleaseWithArtists, UserBmc,
+};
+
+use super::templates;
+
+/// The number of days covered by a feed.
+const FEED_NUM_DAYS: i64 = 12;
+
+/// The namespace of the Media RSS extension, used for the thumbnails of the cover art.
+const MEDIA_NAMESPACE: &str = "http://search.yahoo.com/mrss/";
 
 pub fn routes_calendar() -> Router {
-    Router::new().route("/feed.xml", get(feed))
+    Router::new()
+        .route("/feed.xml", get(feed))
+        .route("/:date", get(day))
 }
 
-async fn feed() -> impl IntoResponse {
+pub fn routes_feed() -> Router {
+    Router::new().route("/:file", get(personal_feed))
+}
+
+/// The feed of the releases of a user's watchlist, e.g. `/feed/{token}.xml`.
+async fn personal_feed(UrlPath(file): UrlPath<String>) -> Response {
+    let not_found = || (StatusCode::NOT_FOUND, "Feed not found.").into_response();
+
+    let Some(token) = file.strip_suffix(".xml") else {
+        return not_found();
+    };
+    let user = match UserBmc::get_by_feed_token(token) {
+        Ok(Some(user)) => user,
+        Ok(None) => return not_found(),
+        Err(err) => {
+            error!("getting user of feed: {err}");
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Could not fetch the feed.",
+            )
+                .into_response();
+        }
+    };
+
+    let today = OffsetDateTime::now_utc().date();
+    let releases = UserBmc::get_watchlist(user.id).and_then(|watchlist| {
+        let releases = CalendarBmc::get_between(
+            today - Duration::days(FEED_NUM_DAYS - 1),
+            today,
+            &ReleaseFilter::default(),
+        )?;
+        Ok(releases
+            .into_iter()
+            .filter(|release| watchlist.matches(release))
+            .collect::<Vec<_>>())
+    });
+
+    match releases {
+        Ok(releases) => releases_feed(&releases, &format!("/feed/{token}.xml")),
+        Err(err) => {
+            error!(
+                "getting releases of the watchlist of user {}: {err}",
+                user.id
+            );
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Could not fetch the releases.",
+            )
+                .into_response()
+        }
+    }
+}
+
+/// The page of the releases of a day, e.g. `/calendar/2024-10-18`.
+async fn day(UrlPath(date): UrlPath<String>) -> Response {
+    let Some(date) = Release::parse_date(&date) else {
+        return (
+            StatusCode::NOT_FOUND,
+            templates::general::simple(
+                "Day Not Found",
+                "The day you requested is not a valid date. Please go back to the main page.",
+            ),
+        )
+            .into_response();
+    };
+
+    match CalendarBmc::get_day(date) {
+        Ok(releases) => templates::calendar::day(date, &releases).into_response(),
+        Err(err) => {
+            error!("getting releases of {date}: {err}");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Could not fetch the releases.",
+            )
+                .into_response()
+        }
+    }
+}
+
+/// The query parameters accepted by the feed, e.g. `/calendar/feed.xml?types=album,ep`.
+#[derive(Deserialize)]
+struct FeedParams {
+    /// A comma-separated list of release types to keep.
+    types: Option<String>,
+    /// A comma-separated list of artist ids whose releases to keep.
+    artists: Option<String>,
+}
+
+impl FeedParams {
+    fn filter(&self) -> core::result::Result<ReleaseFilter, String> {
+        let types = match &self.types {
+            Some(types) => types
+                .split(',')
+                .filter(|t| !t.trim().is_empty())
+                .map(str::parse::<ReleaseType>)
+                .collect::<core::result::Result<Vec<_>, _>>()?,
+            None => Vec::new(),
+        };
+
+        let artist_ids = match &self.artists {
+            Some(ids) => ids
+                .split(',')
+                .filter(|id| !id.trim().is_empty())
+                .map(|id| {
+                    id.trim()
+                        .parse::<i32>()
+                        .map_err(|_| format!("invalid artist id `{id}`"))
+                })
+                .collect::<core::result::Result<Vec<_>, _>>()?,
+            None => Vec::new(),
+        };
+
+        Ok(ReleaseFilter { types, artist_ids })
+    }
+}
+
+async fn feed(Query(params): Query<FeedParams>) -> impl IntoResponse {
+    let filter = match params.filter() {
+        Ok(filter) => filter,
+        Err(err) => return (StatusCode::BAD_REQUEST, err).into_response(),
+    };
+    if !filter.is_empty() {
+        return filtered_feed(&filter).into_response();
+    }
+
     let now = OffsetDateTime::now_utc();
     let date_int = match format!("{}{}{}", now.year(), now.month() as u8, now.day(),).parse::<i32>()
     {
@@ -25,20 +173,19 @@ async fn feed() -> impl IntoResponse {
                 .into_response()
         }
     };
-    let pub_date = match now.format(&time::format_description::well_known::Rfc2822) {
-        Ok(date) => date,
-        Err(_) => String::new(),
-    };
+    let pub_date = now
+        .format(&time::format_description::well_known::Rfc2822)
+        .unwrap_or_default();
     let date = format!("{} {}, {}", now.month(), now.day(), now.year());
 
-    match FeedBmc::get(12) {
+    match FeedBmc::get(FEED_NUM_DAYS) {
         Ok(feeds) => {
             let mut items = feeds
                 .iter()
                 .map(|f| {
                     match Channel::read_from(f.feed.as_bytes()) {
                         Ok(channel) => {
-                            channel.items.get(0).unwrap().clone() // Unwrap used here because a successful channel read always contains an item
+                            channel.items.first().unwrap().clone() // Unwrap used here because a successful channel read always contains an item
                         }
                         Err(err) => {
                             error!("Error reading channel item: {err}");
@@ -52,7 +199,7 @@ async fn feed() -> impl IntoResponse {
                 .link(format!("{}/static/favicon.png", config().BASE_URL))
                 .build();
 
-            let channel = match feeds.get(0) {
+            let channel = match feeds.first() {
                 Some(feed) => {
                     if feed.date == date_int {
                         ChannelBuilder::default()
@@ -60,13 +207,14 @@ async fn feed() -> impl IntoResponse {
                             .description("A feed for the latest heavy metal album releases.")
                             .pub_date(pub_date)
                             .link("/calendar/feed.xml")
+                            .namespaces(namespaces())
                             .image(image)
                             .items(items)
                             .build()
                     } else {
                         match create_new_feed(pub_date.clone(), date, date_int) {
                             Ok(channel) => {
-                                if let Some(item) = channel.items.get(0) {
+                                if let Some(item) = channel.items.first() {
                                     items.insert(0, item.clone());
                                 }
 
@@ -77,6 +225,7 @@ async fn feed() -> impl IntoResponse {
                                     )
                                     .pub_date(pub_date)
                                     .link("/calendar/feed.xml")
+                                    .namespaces(namespaces())
                                     .image(image)
                                     .items(items)
                                     .build()
@@ -108,35 +257,207 @@ async fn feed() -> impl IntoResponse {
                 "getting releases today {}: {err}",
                 OffsetDateTime::now_utc()
             );
-            return (
+            (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "Could not fetch today's releases.",
             )
-                .into_response();
+                .into_response()
+        }
+    }
+}
+
+/// Builds a feed containing only the releases matching the filter.
+///
+/// Filtered feeds are not cached in the `feeds` table because there are
+/// as many of them as there are filter combinations. Each day with
+/// matching releases over the last [`FEED_NUM_DAYS`] days becomes an item.
+fn filtered_feed(filter: &ReleaseFilter) -> impl IntoResponse {
+    let today = OffsetDateTime::now_utc().date();
+    match CalendarBmc::get_between(today - Duration::days(FEED_NUM_DAYS - 1), today, filter) {
+        Ok(releases) => releases_feed(&releases, "/calendar/feed.xml"),
+        Err(err) => {
+            error!("getting filtered releases {filter:?}: {err}");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Could not fetch the releases.",
+            )
+                .into_response()
         }
     }
 }
 
+/// Builds a feed of the releases, each day becoming an item.
+fn releases_feed(releases: &[ReleaseWithArtists], link: &str) -> Response {
+    let pub_date = OffsetDateTime::now_utc()
+        .format(&time::format_description::well_known::Rfc2822)
+        .unwrap_or_default();
+
+    let mut items = Vec::new();
+    for chunk in releases
+        .chunk_by(|(a, _, _), (b, _, _)| (a.year, a.month, a.day) == (b.year, b.month, b.day))
+    {
+        let (release, _, _) = &chunk[0];
+        let date = match release.date() {
+            Some(date) => date,
+            None => continue,
+        };
+        let date = format!("{} {}, {}", date.month(), date.day(), date.year());
+
+        let mut guid = Guid::default();
+        guid.set_value(date.clone());
+
+        items.push(
+            ItemBuilder::default()
+                .title(date)
+                .pub_date(pub_date.clone())
+                .content(render_releases(chunk))
+                .enclosure(cover_enclosure(chunk))
+                .extensions(cover_thumbnails(chunk))
+                .guid(guid)
+                .build(),
+        );
+    }
+    items.reverse();
+
+    let channel = ChannelBuilder::default()
+        .title("Heavy Metal Releases")
+        .description("A feed for the latest heavy metal album releases.")
+        .pub_date(pub_date.clone())
+        .last_build_date(pub_date)
+        .language("en-US".to_string())
+        .link(link)
+        .namespaces(namespaces())
+        .items(items)
+        .build();
+
+    (
+        [(CONTENT_TYPE, "text/xml;charset=UTF-8")],
+        channel.to_string(),
+    )
+        .into_response()
+}
+
+/// Renders the releases as the HTML content of a feed item.
+fn render_releases(releases: &[ReleaseWithArtists]) -> String {
+    releases
+        .iter()
+        .fold("".to_string(), |mut acc, (release, artists, links)| {
+            if let Some(url) = release.cover_url() {
+                acc.push_str(&format!(
+                    "<img src=\"{url}\" alt=\"{} - {}\" width=\"125\"/><br/>",
+                    release.artist_credit, release.album
+                ));
+            }
+
+            match release.kind() {
+                ReleaseType::Album => acc.push_str(&format!(
+                    "{} - {}<br/>",
+                    release.artist_credit, release.album
+                )),
+                kind => acc.push_str(&format!(
+                    "{} - {} ({kind})<br/>",
+                    release.artist_credit, release.album
+                )),
+            }
+
+            if artists.len() > 1 {
+                let names = artists
+                    .iter()
+                    .map(|artist| {
+                        format!(
+                            "<a href=\"{}/artists/{}\">{}</a>",
+                            config().BASE_URL,
+                            artist.id,
+                            artist.name
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                acc.push_str(&format!("&emsp;• Artists: {names}<br/>"));
+            }
+
+            for link in links.iter() {
+                if let Some(kind) = link.kind() {
+                    acc.push_str(&format!("&emsp;• <a href=\"{}\">{kind}</a><br/>", link.url));
+                }
+            }
+
+            let wikipedia_url = release
+                .wikipedia_url()
+                .or_else(|| match artists.as_slice() {
+                    [artist] => artist.wikipedia_url(),
+                    _ => None,
+                });
+            if let Some(url) = wikipedia_url {
+                acc.push_str(&format!(
+                    "&emsp;• <a href=\"{}\">Read on Wikipedia</a><br/>",
+                    url
+                ));
+            }
+            if let Some(url) = release.musicbrainz_url() {
+                match release.track_count {
+                    Some(n) => acc.push_str(&format!(
+                        "&emsp;• <a href=\"{}\">MusicBrainz</a> ({n} tracks)<br/>",
+                        url
+                    )),
+                    None => {
+                        acc.push_str(&format!("&emsp;• <a href=\"{}\">MusicBrainz</a><br/>", url))
+                    }
+                }
+            }
+            acc.push_str("<br/>");
+
+            acc
+        })
+}
+
+/// The namespaces declared by the feed's channels.
+fn namespaces() -> BTreeMap<String, String> {
+    BTreeMap::from([("media".to_string(), MEDIA_NAMESPACE.to_string())])
+}
+
+/// Attaches the cover of the first release that has one to a feed item.
+fn cover_enclosure(releases: &[ReleaseWithArtists]) -> Option<Enclosure> {
+    let (release, _, _) = releases
+        .iter()
+        .find(|(release, _, _)| release.cover.is_some())?;
+    let cover = release.cover.as_ref()?;
+    let length = std::fs::metadata(Path::new(&config().COVERS_FOLDER).join(cover))
+        .map(|metadata| metadata.len())
+        .unwrap_or_default();
+
+    Some(Enclosure {
+        url: release.cover_url()?,
+        length: length.to_string(),
+        mime_type: "image/jpeg".to_string(),
+    })
+}
+
+/// Builds a `media:thumbnail` element for the cover of every release of a feed item.
+fn cover_thumbnails(releases: &[ReleaseWithArtists]) -> ExtensionMap {
+    let thumbnails = releases
+        .iter()
+        .filter_map(|(release, _, _)| release.cover_url())
+        .map(|url| Extension {
+            name: "media:thumbnail".to_string(),
+            attrs: BTreeMap::from([("url".to_string(), url)]),
+            ..Default::default()
+        })
+        .collect::<Vec<_>>();
+
+    if thumbnails.is_empty() {
+        return ExtensionMap::new();
+    }
+    ExtensionMap::from([(
+        "media".to_string(),
+        BTreeMap::from([("thumbnail".to_string(), thumbnails)]),
+    )])
+}
+
 fn create_new_feed(pub_date: String, date: String, date_int: i32) -> Result<Channel> {
     match CalendarBmc::get() {
         Ok(releases) => {
-            let content = releases.iter().fold(
-                "".to_string(),
-                |mut acc, (release, artist, (url_youtube, url_bandcamp))| {
-                    acc.push_str(&format!("{} - {}<br/>", artist.name, release.album));
-
-                    acc.push_str(&format!(
-                        "&emsp;• <a href=\"{}\">Youtube</a><br/>",
-                        url_youtube
-                    ));
-                    if let Some(url) = url_bandcamp {
-                        acc.push_str(&format!("&emsp;• <a href=\"{}\">Bandcamp</a><br/>", url));
-                    }
-                    acc.push_str("<br/>");
-
-                    acc
-                },
-            );
+            let content = render_releases(&releases);
 
             let channel = if content.is_empty() {
                 ChannelBuilder::default()
@@ -146,15 +467,18 @@ fn create_new_feed(pub_date: String, date: String, date_int: i32) -> Result<Chan
                     .last_build_date(pub_date)
                     .language("en-US".to_string())
                     .link("/calendar/feed.xml")
+                    .namespaces(namespaces())
                     .build()
             } else {
                 let mut guid = Guid::default();
-                guid.set_value(&format!("{}", date));
+                guid.set_value(&date);
 
                 let item = ItemBuilder::default()
                     .title(date.clone())
                     .pub_date(pub_date.clone())
                     .content(content)
+                    .enclosure(cover_enclosure(&releases))
+                    .extensions(cover_thumbnails(&releases))
                     .guid(guid)
                     .build();
 
@@ -163,6 +487,7 @@ fn create_new_feed(pub_date: String, date: String, date_int: i32) -> Result<Chan
                     .description("A feed for the latest heavy metal album releases.")
                     .pub_date(pub_date)
                     .link("/calendar/feed.xml")
+                    .namespaces(namespaces())
                     .item(item)
                     .build();
 
@@ -194,5 +519,6 @@ fn build_default_channel(pub_date: String) -> Channel {
         .last_build_date(pub_date)
         .language("en-US".to_string())
         .link("/calendar/feed.xml")
+        .namespaces(namespaces())
         .build()
 }
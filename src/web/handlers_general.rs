@@ -1,24 +1,113 @@
 use axum::{
     handler::HandlerWithoutStateExt,
     http::StatusCode,
+    middleware,
+    response::{IntoResponse, Response},
     routing::{any_service, get, MethodRouter},
     Router,
 };
+use lettre::Address;
 use maud::Markup;
+use serde::Deserialize;
 use tower_http::services::ServeDir;
+use tracing::error;
 
-use crate::web::templates;
+use crate::{config::config, mailer::Mailer, model::ContactMessageBmc, web::templates};
 
-use super::{config::web_config, templates::main::*};
+use super::{
+    config::web_config,
+    spam::{limit_submissions, Guarded, GuardedForm},
+    templates::main::*,
+};
 
 pub fn routes_general() -> Router {
     Router::new()
-        .route("/", get(index()))
+        // The forms of these pages carry a token telling when they were loaded,
+        // so they are rendered on every request.
+        .route("/", get(|| async { index() }))
         .route("/about", get(about()))
-        .route("/contact", get(contact(false)).post(contact(true)))
+        .route(
+            "/contact",
+            get(|| async { contact(ContactStatus::Blank) }).post(send_contact_message),
+        )
+        .route_layer(middleware::from_fn(limit_submissions))
         .route("/privacy", get(privacy()))
         .route("/tos", get(tos()))
         .nest_service("/static", ServeDir::new(&web_config().WEB_FOLDER))
+        .nest_service("/covers", ServeDir::new(&config().COVERS_FOLDER))
+}
+
+#[derive(Deserialize)]
+struct ContactForm {
+    email: String,
+    message: String,
+}
+
+impl GuardedForm for ContactForm {
+    fn content(&self) -> &str {
+        &self.message
+    }
+}
+
+impl ContactForm {
+    /// Checks the form, returning the error shown to the visitor when it is invalid.
+    fn validate(&self) -> core::result::Result<(), &'static str> {
+        if self.email.trim().parse::<Address>().is_err() {
+            return Err("The email address is invalid.");
+        }
+
+        let message = self.message.trim();
+        if message.is_empty() {
+            return Err("The message is empty.");
+        }
+        if message.chars().count() > MAX_CONTACT_MESSAGE_LEN {
+            return Err("The message is too long.");
+        }
+        Ok(())
+    }
+}
+
+/// Stores the message of the contact form and forwards it to `CONTACT_EMAIL`
+/// if set. The message is kept even when forwarding fails.
+async fn send_contact_message(Guarded(form): Guarded<ContactForm>) -> Response {
+    let rejected = |error| ContactStatus::Rejected {
+        email: &form.email,
+        message: &form.message,
+        error,
+    };
+
+    if let Err(error) = form.validate() {
+        return (StatusCode::BAD_REQUEST, contact(rejected(error))).into_response();
+    }
+
+    let message = match ContactMessageBmc::create(form.email.trim(), form.message.trim()) {
+        Ok(message) => message,
+        Err(err) => {
+            error!("storing contact message: {err}");
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                contact(rejected(
+                    "Your message could not be sent. Please try again later.",
+                )),
+            )
+                .into_response();
+        }
+    };
+
+    if let Some(to) = &config().CONTACT_EMAIL {
+        match Mailer::from_config() {
+            Ok(Some(mailer)) => {
+                let email = templates::emails::contact_message(to, &message);
+                if let Err(err) = mailer.send(email).await {
+                    error!("forwarding contact message {}: {err}", message.id);
+                }
+            }
+            Ok(None) => error!("Cannot forward the contact message: SMTP_HOST is not set"),
+            Err(err) => error!("creating mailer: {err}"),
+        }
+    }
+
+    contact(ContactStatus::Sent).into_response()
 }
 
 pub fn serve_dir(web_folder: &'static String) -> MethodRouter {
@@ -0,0 +1,179 @@
+use axum::{
+    extract::Path,
+    http::StatusCode,
+    middleware,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Router,
+};
+use lettre::Address;
+use serde::Deserialize;
+use tracing::error;
+
+use crate::{mailer::Mailer, model::SubscriberBmc};
+
+use super::{
+    spam::{limit_submissions, Guarded, GuardedForm},
+    templates,
+};
+
+pub fn routes_subscribers() -> Router {
+    Router::new()
+        .route(
+            "/",
+            post(subscribe).route_layer(middleware::from_fn(limit_submissions)),
+        )
+        .route("/confirm/:token", get(confirm))
+        .route(
+            "/unsubscribe/:token",
+            get(unsubscribe_page).post(unsubscribe),
+        )
+}
+
+#[derive(Deserialize)]
+struct SubscribeForm {
+    email: String,
+}
+
+impl GuardedForm for SubscribeForm {}
+
+async fn subscribe(Guarded(form): Guarded<SubscribeForm>) -> Response {
+    let email = form.email.trim();
+    if email.parse::<Address>().is_err() {
+        return (
+            StatusCode::BAD_REQUEST,
+            templates::general::simple(
+                "Invalid Email",
+                "The email address you entered is invalid. Please go back and try again.",
+            ),
+        )
+            .into_response();
+    }
+
+    let subscriber = match SubscriberBmc::create(email) {
+        Ok(subscriber) => subscriber,
+        Err(err) => {
+            error!("creating subscriber: {err}");
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Could not subscribe.").into_response();
+        }
+    };
+
+    // The same page is shown whether or not the address was already
+    // subscribed, so the form can't be used to find out who subscribed.
+    // Subscribing again only sends another link once in a while, so the
+    // form can't be used to flood an inbox either.
+    let is_claimed = SubscriberBmc::claim_confirmation(subscriber.id).unwrap_or_else(|err| {
+        error!("claiming confirmation email: {err}");
+        false
+    });
+    if is_claimed {
+        match Mailer::from_config() {
+            Ok(Some(mailer)) => {
+                if let Err(err) = mailer
+                    .send(templates::emails::confirmation(&subscriber))
+                    .await
+                {
+                    error!("sending confirmation email: {err}");
+                }
+            }
+            Ok(None) => error!("Cannot send the confirmation email: SMTP_HOST is not set"),
+            Err(err) => error!("creating mailer: {err}"),
+        }
+    }
+
+    templates::general::simple(
+        "Check Your Inbox",
+        "We sent you an email with a link to confirm your subscription.",
+    )
+    .into_response()
+}
+
+async fn confirm(Path(token): Path<String>) -> Response {
+    match SubscriberBmc::confirm(&token) {
+        Ok(Some(_)) => templates::general::simple(
+            "Subscription Confirmed",
+            "You will receive an email on the days heavy metal albums are released.",
+        )
+        .into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            templates::general::simple(
+                "Subscription Not Found",
+                "The subscription you want to confirm does not exist. Please subscribe again.",
+            ),
+        )
+            .into_response(),
+        Err(err) => {
+            error!("confirming subscriber: {err}");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Could not confirm the subscription.",
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Asks for confirmation rather than unsubscribing right away, because
+/// some mail clients open the links of emails to preview them.
+async fn unsubscribe_page(Path(token): Path<String>) -> Response {
+    templates::main::unsubscribe(&token).into_response()
+}
+
+/// Unsubscribes, either from the page or from the one-click button of mail clients.
+async fn unsubscribe(Path(token): Path<String>) -> Response {
+    match SubscriberBmc::delete_by_token(&token) {
+        Ok(_) => templates::general::simple(
+            "Unsubscribed",
+            "You will not receive any more emails from us.",
+        )
+        .into_response(),
+        Err(err) => {
+            error!("deleting subscriber: {err}");
+            (StatusCode::INTERNAL_SERVER_ERROR, "Could not unsubscribe.").into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Result<T> = core::result::Result<T, Box<dyn std::error::Error>>;
+
+    fn form(email: &str) -> Guarded<SubscribeForm> {
+        Guarded(SubscribeForm {
+            email: email.to_string(),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_confirm_unsubscribe_ok() -> Result<()> {
+        let subscribed = subscribe(form(" Flow@Example.COM ")).await;
+        let subscriber = SubscriberBmc::create("flow@example.com")?;
+        let resubscribed = subscribe(form("flow@example.com")).await;
+        let is_resent = SubscriberBmc::claim_confirmation(subscriber.id)?;
+        let invalid = subscribe(form("not an address")).await;
+
+        pretty_assertions::assert_eq!(subscribed.status(), StatusCode::OK);
+        pretty_assertions::assert_eq!(resubscribed.status(), StatusCode::OK);
+        pretty_assertions::assert_eq!(invalid.status(), StatusCode::BAD_REQUEST);
+        pretty_assertions::assert_eq!(subscriber.email, "flow@example.com");
+        assert!(subscriber.confirmation_sent_at.is_some());
+        assert!(!is_resent, "one confirmation per interval");
+
+        let confirmed = confirm(Path(subscriber.token.clone())).await;
+        let unknown = confirm(Path("unknown".to_string())).await;
+
+        pretty_assertions::assert_eq!(confirmed.status(), StatusCode::OK);
+        pretty_assertions::assert_eq!(unknown.status(), StatusCode::NOT_FOUND);
+        assert!(SubscriberBmc::get(subscriber.id)?.is_some_and(|s| s.is_confirmed()));
+        assert!(!SubscriberBmc::claim_confirmation(subscriber.id)?);
+
+        let unsubscribed = unsubscribe(Path(subscriber.token.clone())).await;
+
+        pretty_assertions::assert_eq!(unsubscribed.status(), StatusCode::OK);
+        pretty_assertions::assert_eq!(SubscriberBmc::get(subscriber.id)?, None);
+        Ok(())
+    }
+}
@@ -1,22 +1,33 @@
 //! The `web` module exposes the handlers for the web server.
 
 mod config;
+mod handlers_accounts;
+mod handlers_artists;
 mod handlers_calendar;
 mod handlers_general;
-mod templates;
+mod handlers_subscribers;
+mod spam;
+pub(crate) mod templates;
 
 use axum::Router;
 
 use crate::error::Result;
 use config::web_config;
-use handlers_calendar::routes_calendar;
+use handlers_accounts::routes_accounts;
+use handlers_artists::routes_artists;
+use handlers_calendar::{routes_calendar, routes_feed};
 use handlers_general::{routes_general, serve_dir};
+use handlers_subscribers::routes_subscribers;
 
 /// Creates the Router for the web server.
 pub async fn routes() -> Result<Router> {
     let router = Router::new()
         .merge(routes_general())
+        .nest("/account", routes_accounts())
+        .nest("/artists", routes_artists())
         .nest("/calendar", routes_calendar())
+        .nest("/feed", routes_feed())
+        .nest("/subscribers", routes_subscribers())
         .fallback_service(serve_dir(&web_config().WEB_FOLDER));
 
     Ok(router)
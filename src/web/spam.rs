@@ -0,0 +1,454 @@
+//! The `spam` module protects the public forms against spam bots.
+//!
+//! Every form guarded by the [`Guarded`] extractor carries a honeypot field,
+//! which people never fill in, and a signed token telling when the form was
+//! loaded, so that forms submitted too quickly or too late are rejected. The
+//! free text of the form is checked for links as well. The submissions of
+//! each IP address are rate limited by the [`limit_submissions`] middleware.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    net::{IpAddr, SocketAddr},
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    async_trait,
+    body::Bytes,
+    extract::{ConnectInfo, FromRequest, Request},
+    http::{HeaderMap, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::{de::DeserializeOwned, Deserialize};
+use time::OffsetDateTime;
+use tracing::{info, warn};
+
+use crate::support::signature::{sign, verify};
+
+use super::{config::web_config, templates};
+
+/// The name of the field hidden from people. Bots fill in every field.
+pub const HONEYPOT_FIELD: &str = "website";
+
+/// The name of the field holding the form token.
+pub const TOKEN_FIELD: &str = "form_token";
+
+/// The minimum number of seconds between loading and submitting a form.
+const MIN_SUBMIT_SECS: i64 = 3;
+
+/// The maximum number of seconds between loading and submitting a form.
+const MAX_SUBMIT_SECS: i64 = 24 * 60 * 60;
+
+/// The maximum number of forms an IP address submits in [`SUBMISSION_WINDOW`].
+const MAX_SUBMISSIONS: usize = 5;
+
+const SUBMISSION_WINDOW: Duration = Duration::from_secs(10 * 60);
+
+/// The maximum number of links in the free text of a form.
+const MAX_LINKS: usize = 2;
+
+/// Creates the token of a form loaded now.
+pub fn form_token() -> String {
+    form_token_at(
+        web_config().FORM_SECRET.as_bytes(),
+        OffsetDateTime::now_utc().unix_timestamp(),
+    )
+}
+
+/// The token of a form loaded at the Unix timestamp, e.g. `1729209600.3f2a…`.
+fn form_token_at(secret: &[u8], issued_at: i64) -> String {
+    format!(
+        "{issued_at}.{}",
+        sign(secret, issued_at.to_string().as_bytes())
+    )
+}
+
+/// A form whose submissions are checked for spam.
+pub trait GuardedForm: DeserializeOwned {
+    /// The free text of the form checked by the content heuristic.
+    fn content(&self) -> &str {
+        ""
+    }
+}
+
+/// The reason a submission was taken for spam.
+#[derive(Debug, PartialEq)]
+enum SpamReason {
+    Honeypot,
+    MissingToken,
+    InvalidToken,
+    TooFast,
+    Expired,
+    Links,
+}
+
+impl fmt::Display for SpamReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            SpamReason::Honeypot => "the honeypot field is filled in",
+            SpamReason::MissingToken => "the form token is missing",
+            SpamReason::InvalidToken => "the form token is invalid",
+            SpamReason::TooFast => "the form was submitted too quickly",
+            SpamReason::Expired => "the form token expired",
+            SpamReason::Links => "the content has too many links",
+        };
+        write!(f, "{reason}")
+    }
+}
+
+/// The fields every guarded form carries along with its own.
+#[derive(Deserialize)]
+struct FormGuard {
+    #[serde(default, rename = "website")]
+    honeypot: String,
+    #[serde(default)]
+    form_token: String,
+}
+
+fn check(guard: &FormGuard, content: &str, secret: &[u8], now: i64) -> Result<(), SpamReason> {
+    if !guard.honeypot.is_empty() {
+        return Err(SpamReason::Honeypot);
+    }
+
+    if guard.form_token.is_empty() {
+        return Err(SpamReason::MissingToken);
+    }
+    let Some((issued_at, signature)) = guard.form_token.split_once('.') else {
+        return Err(SpamReason::InvalidToken);
+    };
+    if !verify(secret, issued_at.as_bytes(), signature) {
+        return Err(SpamReason::InvalidToken);
+    }
+    let issued_at: i64 = issued_at.parse().map_err(|_| SpamReason::InvalidToken)?;
+    if now - issued_at < MIN_SUBMIT_SECS {
+        return Err(SpamReason::TooFast);
+    }
+    if now - issued_at > MAX_SUBMIT_SECS {
+        return Err(SpamReason::Expired);
+    }
+
+    let content = content.to_lowercase();
+    if content.contains("[url") || content.contains("<a ") {
+        return Err(SpamReason::Links);
+    }
+    let num_links = content.matches("http://").count()
+        + content.matches("https://").count()
+        + content.matches("www.").count();
+    if num_links > MAX_LINKS {
+        return Err(SpamReason::Links);
+    }
+
+    Ok(())
+}
+
+/// Extracts a form of type `T` after checking it is not spam.
+pub struct Guarded<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for Guarded<T>
+where
+    T: GuardedForm,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let bytes = Bytes::from_request(req, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+
+        let invalid_form = |err: serde_urlencoded::de::Error| {
+            (StatusCode::UNPROCESSABLE_ENTITY, err.to_string()).into_response()
+        };
+        let guard: FormGuard = serde_urlencoded::from_bytes(&bytes).map_err(invalid_form)?;
+        let form: T = serde_urlencoded::from_bytes(&bytes).map_err(invalid_form)?;
+
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        if let Err(reason) = check(
+            &guard,
+            form.content(),
+            web_config().FORM_SECRET.as_bytes(),
+            now,
+        ) {
+            info!("Rejected a form submission because {reason}");
+            return Err((
+                StatusCode::BAD_REQUEST,
+                templates::general::simple(
+                    "Submission Rejected",
+                    "Your submission looks like spam. Please reload the page, wait a few seconds and try again.",
+                ),
+            )
+                .into_response());
+        }
+
+        Ok(Guarded(form))
+    }
+}
+
+/// Counts the recent submissions of each IP address.
+struct SubmissionLimiter {
+    max: usize,
+    window: Duration,
+    submissions: Mutex<HashMap<IpAddr, VecDeque<Instant>>>,
+}
+
+impl SubmissionLimiter {
+    fn new(max: usize, window: Duration) -> Self {
+        Self {
+            max,
+            window,
+            submissions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Records a submission from the address, unless it submitted too many recently.
+    fn allow(&self, ip: IpAddr, now: Instant) -> bool {
+        let mut submissions = self.submissions.lock().unwrap();
+        submissions.retain(|_, times| {
+            while times
+                .front()
+                .is_some_and(|t| now.saturating_duration_since(*t) >= self.window)
+            {
+                times.pop_front();
+            }
+            !times.is_empty()
+        });
+
+        let times = submissions.entry(ip).or_default();
+        if times.len() >= self.max {
+            return false;
+        }
+        times.push_back(now);
+        true
+    }
+}
+
+/// Rate limits the forms submitted by each IP address. Only `POST` requests are counted.
+pub async fn limit_submissions(req: Request, next: Next) -> Response {
+    static LIMITER: OnceLock<SubmissionLimiter> = OnceLock::new();
+
+    if req.method() != Method::POST {
+        return next.run(req).await;
+    }
+
+    let ip = client_ip(
+        req.headers(),
+        req.extensions().get::<ConnectInfo<SocketAddr>>(),
+        &web_config().TRUSTED_PROXIES,
+    );
+    let Some(ip) = ip else {
+        return next.run(req).await;
+    };
+
+    let limiter =
+        LIMITER.get_or_init(|| SubmissionLimiter::new(MAX_SUBMISSIONS, SUBMISSION_WINDOW));
+    if !limiter.allow(ip, Instant::now()) {
+        warn!("Rate limited the form submissions of {ip}");
+        return (
+            StatusCode::TOO_MANY_REQUESTS,
+            templates::general::simple(
+                "Too Many Submissions",
+                "You submitted too many forms recently. Please try again later.",
+            ),
+        )
+            .into_response();
+    }
+
+    next.run(req).await
+}
+
+/// The address of the client.
+///
+/// The web server listens behind a reverse proxy, so the addresses
+/// forwarded by the peer are read when the peer is a trusted proxy. Each
+/// proxy appends the address it received the request from to the
+/// `X-Forwarded-For` header, so the client is the right-most address that
+/// is not one of the trusted proxies; the addresses on its left may be
+/// made up by the client.
+fn client_ip(
+    headers: &HeaderMap,
+    connect_info: Option<&ConnectInfo<SocketAddr>>,
+    trusted_proxies: &[IpAddr],
+) -> Option<IpAddr> {
+    let is_trusted = |ip: &IpAddr| ip.is_loopback() || trusted_proxies.contains(ip);
+
+    let peer = connect_info.map(|ConnectInfo(addr)| addr.ip())?;
+    if !is_trusted(&peer) {
+        return Some(peer);
+    }
+
+    let header = |name| headers.get(name).and_then(|value| value.to_str().ok());
+    if let Some(forwarded) = header("x-forwarded-for") {
+        let mut client = peer;
+        for hop in forwarded.split(',').rev() {
+            match hop.trim().parse() {
+                Ok(ip) if is_trusted(&ip) => client = ip,
+                Ok(ip) => return Some(ip),
+                Err(_) => break,
+            }
+        }
+        return Some(client);
+    }
+
+    header("x-real-ip")
+        .and_then(|ip| ip.trim().parse().ok())
+        .or(Some(peer))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Result<T> = core::result::Result<T, Box<dyn std::error::Error>>;
+
+    const SECRET: &[u8] = b"secret";
+
+    fn guard(honeypot: &str, form_token: String) -> FormGuard {
+        FormGuard {
+            honeypot: honeypot.to_string(),
+            form_token,
+        }
+    }
+
+    #[test]
+    fn test_check_ok() -> Result<()> {
+        let now = 1_729_209_600;
+        let token = form_token_at(SECRET, now - 10);
+
+        let got = [
+            check(&guard("", token.clone()), "Hello", SECRET, now),
+            check(
+                &guard("https://spam.example", token.clone()),
+                "Hello",
+                SECRET,
+                now,
+            ),
+            check(&guard("", String::new()), "Hello", SECRET, now),
+            check(
+                &guard("", form_token_at(b"other", now - 10)),
+                "Hello",
+                SECRET,
+                now,
+            ),
+            check(
+                &guard(
+                    "",
+                    format!("{}.{}", now - 100, token.split_once('.').unwrap().1),
+                ),
+                "Hello",
+                SECRET,
+                now,
+            ),
+            check(
+                &guard("", form_token_at(SECRET, now - 1)),
+                "Hello",
+                SECRET,
+                now,
+            ),
+            check(
+                &guard("", form_token_at(SECRET, now - MAX_SUBMIT_SECS - 1)),
+                "Hello",
+                SECRET,
+                now,
+            ),
+            check(
+                &guard("", token.clone()),
+                "See https://a.example and https://b.example",
+                SECRET,
+                now,
+            ),
+            check(
+                &guard("", token.clone()),
+                "https://a.example https://b.example www.c.example",
+                SECRET,
+                now,
+            ),
+            check(
+                &guard("", token),
+                "[URL=https://a.example]cheap[/URL]",
+                SECRET,
+                now,
+            ),
+        ];
+
+        pretty_assertions::assert_eq!(
+            got,
+            [
+                Ok(()),
+                Err(SpamReason::Honeypot),
+                Err(SpamReason::MissingToken),
+                Err(SpamReason::InvalidToken),
+                Err(SpamReason::InvalidToken),
+                Err(SpamReason::TooFast),
+                Err(SpamReason::Expired),
+                Ok(()),
+                Err(SpamReason::Links),
+                Err(SpamReason::Links),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_allow_ok() -> Result<()> {
+        let limiter = SubmissionLimiter::new(2, Duration::from_secs(60));
+        let now = Instant::now();
+        let ip: IpAddr = "203.0.113.7".parse()?;
+        let other: IpAddr = "198.51.100.1".parse()?;
+
+        let got = [
+            limiter.allow(ip, now),
+            limiter.allow(ip, now + Duration::from_secs(1)),
+            limiter.allow(ip, now + Duration::from_secs(2)),
+            limiter.allow(other, now + Duration::from_secs(2)),
+            limiter.allow(ip, now + Duration::from_secs(60)),
+        ];
+
+        pretty_assertions::assert_eq!(got, [true, true, false, true, true]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_client_ip_ok() -> Result<()> {
+        let proxy = ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 7125)));
+        let remote_proxy = ConnectInfo(SocketAddr::from(([10, 0, 0, 1], 7125)));
+        let stranger = ConnectInfo(SocketAddr::from(([198, 51, 100, 1], 7125)));
+        let trusted = ["10.0.0.1".parse()?];
+        let headers = |forwarded: &str| -> Result<HeaderMap> {
+            let mut headers = HeaderMap::new();
+            headers.insert("x-forwarded-for", forwarded.parse()?);
+            Ok(headers)
+        };
+
+        let got = [
+            client_ip(&HeaderMap::new(), Some(&proxy), &trusted),
+            client_ip(&headers("203.0.113.7")?, Some(&proxy), &trusted),
+            client_ip(&headers("1.2.3.4, 203.0.113.7")?, Some(&proxy), &trusted),
+            client_ip(
+                &headers("203.0.113.7, 10.0.0.1")?,
+                Some(&remote_proxy),
+                &trusted,
+            ),
+            client_ip(&headers("203.0.113.7")?, Some(&stranger), &trusted),
+            client_ip(&headers("203.0.113.7")?, Some(&remote_proxy), &[]),
+            client_ip(&headers("garbage")?, Some(&proxy), &trusted),
+        ];
+
+        pretty_assertions::assert_eq!(
+            got,
+            [
+                Some("127.0.0.1".parse()?),
+                Some("203.0.113.7".parse()?),
+                Some("203.0.113.7".parse()?),
+                Some("203.0.113.7".parse()?),
+                Some("198.51.100.1".parse()?),
+                Some("10.0.0.1".parse()?),
+                Some("127.0.0.1".parse()?),
+            ]
+        );
+        Ok(())
+    }
+}
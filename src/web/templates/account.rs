@@ -0,0 +1,343 @@
+use maud::{html, Markup};
+
+use crate::{
+    config::config,
+    imports::{ArtistMatches, ImportFormat},
+    model::{User, Watchlist},
+    scraper::source::genres_enabled,
+};
+
+use super::{
+    core::{form_guard, layout},
+    Page,
+};
+
+/// The state of the login form.
+pub enum LoginStatus<'a> {
+    /// The form was not submitted yet.
+    Blank,
+    /// No link was sent. The submitted address is filled in again.
+    Rejected { email: &'a str, error: &'a str },
+}
+
+pub fn login(status: LoginStatus) -> Markup {
+    let (email, error) = match status {
+        LoginStatus::Rejected { email, error } => (email, Some(error)),
+        LoginStatus::Blank => ("", None),
+    };
+
+    layout(
+        "Log in",
+        true,
+        Page::Account,
+        html!(
+            section class="col-span-12 py-20" style="background: linear-gradient(90deg, #D73737 0%, #3D3D3D 100%)" {}
+            section class="col-span-12 container mx-auto px-6 p-10" {
+                div class="w-full md:w-1/2" {
+                    h4 class="text-3xl font-bold mb-3" {
+                        "Log in"
+                    }
+                    p class="mb-4" {
+                        "Follow your favorite artists and genres in a personal feed. Enter your email address and we "
+                        "will send you a link to log in. An account is created the first time you log in."
+                    }
+                    @if let Some(error) = error {
+                        div role="alert" class="alert alert-error w-full md:w-3/4 mb-4" {
+                            (error)
+                        }
+                    }
+                    form class="w-full md:w-3/4 bg-white p-6 rounded-lg shadow-md mb-8" method="post" action="/account/login" {
+                        (form_guard())
+                        div class="mb-4" {
+                            label class="block font-bold mb-2" for="email" {
+                                "Email"
+                            }
+                            input
+                                class="border border-gray-400 p-2 w-full"
+                                type="email"
+                                id="email"
+                                name="email"
+                                placeholder="your@email.com"
+                                value=(email)
+                                required;
+                        }
+                        button
+                            class="w-full bg-indigo-500 text-white py-2 px-4 rounded-full hover:bg-indigo-600"
+                            type="submit"
+                        {
+                            "Email me a login link"
+                        }
+                    }
+                }
+            }
+        ),
+    )
+}
+
+/// Asks to log in rather than logging in right away, because some mail
+/// clients open the links of emails to preview them.
+pub fn login_confirm(token: &str) -> Markup {
+    layout(
+        "Log in",
+        false,
+        Page::Other,
+        html!(
+            div class="card w-80 sm:w-96 bg-base-100 shadow-xl" {
+                div class="card-body" {
+                    h2 class="card-title underline self-center" {
+                        "Log in"
+                    }
+                    p {
+                        "Do you want to log in to Heavy Metal Releases on this device?"
+                    }
+                    form class="card-actions justify-end" method="post" action=(format!("/account/login/{token}")) {
+                        button class="btn btn-primary btn-block btn-sm" type="submit" {
+                            "Log in"
+                        }
+                    }
+                }
+            }
+        ),
+    )
+}
+
+/// The settings of the account: the personal feed and the watchlist.
+pub fn settings(user: &User, watchlist: &Watchlist, error: Option<&str>) -> Markup {
+    let feed_url = format!("{}/feed/{}.xml", config().BASE_URL, user.feed_token);
+
+    layout(
+        "Your Account",
+        true,
+        Page::Account,
+        html!(
+            section class="col-span-12 py-20" style="background: linear-gradient(90deg, #D73737 0%, #3D3D3D 100%)" {
+                div class="container mx-auto px-6" {
+                    h2 class="text-4xl font-bold mb-2 text-white" {
+                        "Your Account"
+                    }
+                    p class="mb-8 text-white" {
+                        "Logged in as " (user.email)
+                    }
+                    form method="post" action="/account/logout" {
+                        button class="btn btn-sm" type="submit" { "Log out" }
+                    }
+                }
+            }
+            section class="col-span-12 container mx-auto px-6 p-10" {
+                @if let Some(error) = error {
+                    div role="alert" class="alert alert-error w-full md:w-3/4 mb-4" {
+                        (error)
+                    }
+                }
+                h4 class="text-3xl font-bold mb-3" {
+                    "Personal feed"
+                }
+                p class="mb-4" {
+                    "Your feed lists the releases of the artists and genres you follow. Keep its URL to yourself, "
+                    "anyone who knows it can read your feed."
+                }
+                div class="flex flex-wrap gap-2 mb-4 w-full md:w-3/4" {
+                    input class="border border-gray-400 p-2 rounded flex-grow" type="text" value=(feed_url) readonly aria-label="Feed URL";
+                    a href=(feed_url) {
+                        img src="https://upload.wikimedia.org/wikipedia/commons/thumb/4/43/Feed-icon.svg/128px-Feed-icon.svg.png" height="42px" width="42px" alt="rss icon";
+                    }
+                }
+                form class="mb-10" method="post" action="/account/feed" {
+                    button class="btn btn-sm" type="submit" { "Reset the URL" }
+                }
+
+                h4 class="text-3xl font-bold mb-3" {
+                    "Artists"
+                }
+                @if watchlist.artists.is_empty() {
+                    p class="mb-4" { "You do not follow any artist yet." }
+                } @else {
+                    ul class="mb-4" {
+                        @for artist in &watchlist.artists {
+                            li class="flex items-center gap-2 mb-1" {
+                                a class="text-blue-600 visited:text-purple-600" href=(format!("/artists/{}", artist.id)) {
+                                    (artist.name)
+                                }
+                                form method="post" action=(format!("/account/artists/{}/remove", artist.id)) {
+                                    button class="btn btn-xs" type="submit" { "Remove" }
+                                }
+                            }
+                        }
+                    }
+                }
+                form class="flex flex-wrap gap-2 mb-4 w-full md:w-3/4" method="post" action="/account/artists" {
+                    input class="border border-gray-400 p-2 rounded flex-grow" type="text" name="name" placeholder="Artist name, e.g. Opeth" aria-label="Artist name" required;
+                    button class="bg-indigo-500 text-white py-2 px-4 rounded-full hover:bg-indigo-600" type="submit" {
+                        "Follow"
+                    }
+                }
+                p class="mb-10" {
+                    a class="text-blue-600 visited:text-purple-600" href="/account/import" {
+                        "Import the artists you listen to"
+                    }
+                    " from a Spotify data export, a Last.fm library export or a CSV file."
+                }
+
+                h4 class="text-3xl font-bold mb-3" {
+                    "Genres"
+                }
+                @if watchlist.genres.is_empty() {
+                    p class="mb-4" { "You do not follow any genre yet." }
+                } @else {
+                    ul class="mb-4" {
+                        @for genre in &watchlist.genres {
+                            li class="flex items-center gap-2 mb-1" {
+                                (genre)
+                                form method="post" action="/account/genres/remove" {
+                                    input type="hidden" name="genre" value=(genre);
+                                    button class="btn btn-xs" type="submit" { "Remove" }
+                                }
+                            }
+                        }
+                    }
+                }
+                @if genres_enabled() {
+                    p class="mb-4" {
+                        "A genre matches every release whose genre contains it, e.g. \"black\" matches \"Atmospheric Black Metal\"."
+                    }
+                    form class="flex flex-wrap gap-2 mb-10 w-full md:w-3/4" method="post" action="/account/genres" {
+                        input class="border border-gray-400 p-2 rounded flex-grow" type="text" name="genre" placeholder="Genre, e.g. doom" aria-label="Genre" required;
+                        button class="bg-indigo-500 text-white py-2 px-4 rounded-full hover:bg-indigo-600" type="submit" {
+                            "Follow"
+                        }
+                    }
+                } @else {
+                    p class="mb-10" {
+                        "Genres cannot be followed at the moment: the genre of the releases is only known from the Metal Archives, which are not a source of the calendar."
+                    }
+                }
+            }
+        ),
+    )
+}
+
+/// The form to upload the export of the artists to follow.
+pub fn import(error: Option<&str>) -> Markup {
+    layout(
+        "Import Artists",
+        true,
+        Page::Account,
+        html!(
+            section class="col-span-12 py-20" style="background: linear-gradient(90deg, #D73737 0%, #3D3D3D 100%)" {}
+            section class="col-span-12 container mx-auto px-6 p-10" {
+                div class="w-full md:w-3/4" {
+                    h4 class="text-3xl font-bold mb-3" {
+                        "Import artists"
+                    }
+                    p class="mb-4" {
+                        "Upload the artists you listen to and pick the ones to follow among those found in the calendar. "
+                        "Names are matched regardless of case and punctuation, along with names spelled slightly differently."
+                    }
+                    ul class="list-disc pl-6 mb-4" {
+                        li { "A CSV file lists an artist per line in its first column, with or without an " code { "artist" } " header." }
+                        li { "A Spotify data export is a zip file. Upload the " code { "YourLibrary.json" } " or a " code { "StreamingHistory" } " file it contains." }
+                        li { "A Last.fm library export is either a CSV file of scrobbles or the JSON of the " code { "library.getArtists" } " API method." }
+                    }
+                    @if let Some(error) = error {
+                        div role="alert" class="alert alert-error mb-4" {
+                            (error)
+                        }
+                    }
+                    form class="bg-white p-6 rounded-lg shadow-md mb-8" method="post" action="/account/import" enctype="multipart/form-data" {
+                        div class="mb-4" {
+                            label class="block font-bold mb-2" for="format" {
+                                "Format"
+                            }
+                            select class="border border-gray-400 p-2 w-full" id="format" name="format" {
+                                @for format in ImportFormat::ALL {
+                                    option value=(format.as_str()) { (format.label()) }
+                                }
+                            }
+                        }
+                        div class="mb-4" {
+                            label class="block font-bold mb-2" for="file" {
+                                "File"
+                            }
+                            input class="w-full" type="file" id="file" name="file" accept=".csv,.json,.txt" required;
+                        }
+                        button
+                            class="w-full bg-indigo-500 text-white py-2 px-4 rounded-full hover:bg-indigo-600"
+                            type="submit"
+                        {
+                            "Find the artists"
+                        }
+                    }
+                }
+            }
+        ),
+    )
+}
+
+/// The artists found in an uploaded export, to choose the ones to follow.
+pub fn import_review(matches: &ArtistMatches) -> Markup {
+    let feed_url = format!(
+        "/calendar/feed.xml?artists={}",
+        matches
+            .artist_ids()
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>()
+            .join(",")
+    );
+
+    layout(
+        "Import Artists",
+        true,
+        Page::Account,
+        html!(
+            section class="col-span-12 py-20" style="background: linear-gradient(90deg, #D73737 0%, #3D3D3D 100%)" {}
+            section class="col-span-12 container mx-auto px-6 p-10" {
+                div class="w-full md:w-3/4" {
+                    h4 class="text-3xl font-bold mb-3" {
+                        "Artists found"
+                    }
+                    @if matches.matched.is_empty() {
+                        p class="mb-4" { "None of the imported artists are in the calendar." }
+                    } @else {
+                        p class="mb-4" {
+                            "Uncheck the artists you do not want to follow, then add the others to your watchlist. "
+                            "You can also subscribe to "
+                            a class="text-blue-600 visited:text-purple-600" href=(feed_url) { "the feed of all these artists" }
+                            " without following them."
+                        }
+                        form class="mb-10" method="post" action="/account/import/save" {
+                            ul class="mb-4" {
+                                @for (name, artist) in &matches.matched {
+                                    li class="mb-1" {
+                                        label class="flex items-center gap-2" {
+                                            input class="checkbox checkbox-sm" type="checkbox" name="artist" value=(artist.id) checked;
+                                            (artist.name)
+                                            @if name != &artist.name {
+                                                span class="text-gray-500" { "(imported as " (name) ")" }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                            button class="bg-indigo-500 text-white py-2 px-4 rounded-full hover:bg-indigo-600" type="submit" {
+                                "Follow the checked artists"
+                            }
+                        }
+                    }
+                    @if !matches.unmatched.is_empty() {
+                        h4 class="text-2xl font-bold mb-3" {
+                            "Not in the calendar (" (matches.unmatched.len()) ")"
+                        }
+                        p class="mb-4" {
+                            "These artists have no release in the calendar yet, or are listed under a different name."
+                        }
+                        p class="mb-10 text-gray-600" {
+                            (matches.unmatched.join(", "))
+                        }
+                    }
+                    a class="btn btn-sm" href="/account" { "Back to your account" }
+                }
+            }
+        ),
+    )
+}
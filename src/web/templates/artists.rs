@@ -0,0 +1,108 @@
+use maud::{html, Markup};
+use reqwest::Url;
+
+use crate::{
+    config::config,
+    model::{Artist, ReleaseWithArtists},
+};
+
+use super::{core::layout, Page};
+
+pub fn artist(artist: &Artist, releases: &[ReleaseWithArtists]) -> Markup {
+    layout(
+        &artist.name,
+        true,
+        Page::Other,
+        html!(
+            section class="col-span-12 py-20" style="background: linear-gradient(90deg, #D73737 0%, #3D3D3D 100%)" {
+                div class="container mx-auto px-6" {
+                    h2 class="text-4xl font-bold mb-2 text-white" {
+                        (artist.name)
+                    }
+                    @if let Some(url) = artist.wikipedia_url() {
+                        p class="mb-8" {
+                            a class="text-white underline" href=(url) target="_blank" { "Read on Wikipedia" }
+                        }
+                    }
+                    p class="flex bg-white font-bold rounded-full py-4 px-8 shadow-lg uppercase tracking-wider w-fit" {
+                        "Subscribe to this artist via"
+                        a href=(format!("{}/calendar/feed.xml?artists={}", config().BASE_URL, artist.id)) style="padding-left: 12px" {
+                            img src="https://upload.wikimedia.org/wikipedia/commons/thumb/4/43/Feed-icon.svg/128px-Feed-icon.svg.png" height="32px" width="32px" alt="rss icon";
+                        }
+                    }
+                }
+            }
+            section class="col-span-12 container mx-auto px-6 p-10" {
+                h4 class="text-3xl font-bold mb-3" {
+                    "Releases"
+                }
+                @if releases.is_empty() {
+                    p { "No releases are known for this artist." }
+                } @else {
+                    table class="table" {
+                        thead {
+                            tr {
+                                th { "Date" }
+                                th { "Album" }
+                                th { "Type" }
+                                th { "Artists" }
+                                th { "Links" }
+                                th { "Source" }
+                            }
+                        }
+                        tbody {
+                            @for (release, artists, links) in releases {
+                                tr {
+                                    td { (format!("{}-{:02}-{:02}", release.year, release.month, release.day)) }
+                                    td {
+                                        @if let Some(url) = release.wikipedia_url() {
+                                            a class="text-blue-600 visited:text-purple-600" href=(url) target="_blank" { (release.album) }
+                                        } @else {
+                                            (release.album)
+                                        }
+                                    }
+                                    td { (release.kind()) }
+                                    td {
+                                        @for (i, participant) in artists.iter().enumerate() {
+                                            @if i > 0 { ", " }
+                                            @if participant.id == artist.id {
+                                                (participant.name)
+                                            } @else {
+                                                a class="text-blue-600 visited:text-purple-600" href=(format!("/artists/{}", participant.id)) {
+                                                    (participant.name)
+                                                }
+                                            }
+                                        }
+                                    }
+                                    td {
+                                        @for (i, link) in links.iter().enumerate() {
+                                            @if i > 0 { " · " }
+                                            a class="text-blue-600 visited:text-purple-600" href=(link.url) target="_blank" rel="nofollow" {
+                                                (link.kind().map_or(link.kind.clone(), |kind| kind.to_string()))
+                                            }
+                                        }
+                                    }
+                                    td {
+                                        @if let Some(url) = &release.source_url {
+                                            a class="text-blue-600 visited:text-purple-600" href=(url) target="_blank" rel="nofollow" {
+                                                (source_name(url))
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        ),
+    )
+}
+
+/// Shortens a source's URL to its host, e.g. `www.blabbermouth.net`.
+fn source_name(url: &str) -> String {
+    Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(String::from))
+        .unwrap_or_else(|| url.to_string())
+}
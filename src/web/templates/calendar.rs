@@ -0,0 +1,80 @@
+use maud::{html, Markup};
+use time::Date;
+
+use crate::model::ReleaseWithArtists;
+
+use super::{core::layout, Page};
+
+/// The page of the releases of a day, which push notifications link to.
+pub fn day(date: Date, releases: &[ReleaseWithArtists]) -> Markup {
+    let title = format!("{} {}, {}", date.month(), date.day(), date.year());
+
+    layout(
+        &format!("Releases of {title}"),
+        true,
+        Page::Other,
+        html!(
+            section class="col-span-12 py-20" style="background: linear-gradient(90deg, #D73737 0%, #3D3D3D 100%)" {
+                div class="container mx-auto px-6" {
+                    h2 class="text-4xl font-bold mb-2 text-white" {
+                        "Releases of " (title)
+                    }
+                }
+            }
+            section class="col-span-12 container mx-auto px-6 p-10" {
+                @if releases.is_empty() {
+                    p { "Nothing is released on this day." }
+                } @else {
+                    table class="table" {
+                        thead {
+                            tr {
+                                th { "Cover" }
+                                th { "Album" }
+                                th { "Type" }
+                                th { "Genre" }
+                                th { "Artists" }
+                                th { "Links" }
+                            }
+                        }
+                        tbody {
+                            @for (release, artists, links) in releases {
+                                tr {
+                                    td {
+                                        @if let Some(url) = release.cover_url() {
+                                            img src=(url) alt=(format!("{} - {}", release.artist_credit, release.album)) width="64" height="64" loading="lazy";
+                                        }
+                                    }
+                                    td {
+                                        @if let Some(url) = release.wikipedia_url() {
+                                            a class="text-blue-600 visited:text-purple-600" href=(url) target="_blank" { (release.album) }
+                                        } @else {
+                                            (release.album)
+                                        }
+                                    }
+                                    td { (release.kind()) }
+                                    td { (release.genre.as_deref().unwrap_or_default()) }
+                                    td {
+                                        @for (i, artist) in artists.iter().enumerate() {
+                                            @if i > 0 { ", " }
+                                            a class="text-blue-600 visited:text-purple-600" href=(format!("/artists/{}", artist.id)) {
+                                                (artist.name)
+                                            }
+                                        }
+                                    }
+                                    td {
+                                        @for (i, link) in links.iter().enumerate() {
+                                            @if i > 0 { " · " }
+                                            a class="text-blue-600 visited:text-purple-600" href=(link.url) target="_blank" rel="nofollow" {
+                                                (link.kind().map_or(link.kind.clone(), |kind| kind.to_string()))
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        ),
+    )
+}
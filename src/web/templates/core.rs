@@ -1,6 +1,12 @@
 use maud::{html, Markup, DOCTYPE};
 
-use crate::{config::config, web::templates::Page};
+use crate::{
+    config::config,
+    web::{
+        spam::{form_token, HONEYPOT_FIELD, TOKEN_FIELD},
+        templates::Page,
+    },
+};
 
 pub fn layout(title: &str, is_show_nav: bool, page: Page, content: Markup) -> Markup {
     html!(
@@ -23,6 +29,17 @@ pub fn layout(title: &str, is_show_nav: bool, page: Page, content: Markup) -> Ma
     )
 }
 
+/// The hidden fields of the forms protected against spam.
+pub fn form_guard() -> Markup {
+    html!(
+        div style="display: none" aria-hidden="true" {
+            label for=(HONEYPOT_FIELD) { "Leave this field empty" }
+            input type="text" id=(HONEYPOT_FIELD) name=(HONEYPOT_FIELD) tabindex="-1" autocomplete="off";
+        }
+        input type="hidden" name=(TOKEN_FIELD) value=(form_token());
+    )
+}
+
 fn head(title: &str) -> Markup {
     html!(
         head {
@@ -107,6 +124,12 @@ fn nav_items(page: Page) -> Markup {
                 @if page != Page::Home { " hover:text-gray-800" }
             } { "Contact" }
         }
+        li {
+            a href="/account" class={
+                @if page == Page::Account { "font-bold"}
+                @if page != Page::Home { " hover:text-gray-800" }
+            } { "Account" }
+        }
     )
 }
 
@@ -0,0 +1,164 @@
+use maud::{html, Markup, DOCTYPE};
+
+use crate::{
+    calendar::ReleaseType,
+    config::config,
+    mailer::Email,
+    model::{ContactMessage, ReleaseWithArtists, Subscriber, LOGIN_TOKEN_TTL},
+};
+
+/// The email forwarding a message of the contact form, to be replied to directly.
+pub fn contact_message(to: &str, message: &ContactMessage) -> Email {
+    Email {
+        to: to.to_string(),
+        subject: format!(
+            "Contact form message #{} from {}",
+            message.id, message.email
+        ),
+        html: email_layout(html!(
+            p { "From: " (message.email) }
+            p style="white-space: pre-wrap" { (message.message) }
+        ))
+        .into_string(),
+        text: format!("From: {}\n\n{}\n", message.email, message.message),
+        reply_to: Some(message.email.clone()),
+        unsubscribe_url: None,
+    }
+}
+
+/// The email asking a new subscriber to confirm their address.
+pub fn confirmation(subscriber: &Subscriber) -> Email {
+    let confirm_url = format!(
+        "{}/subscribers/confirm/{}",
+        config().BASE_URL,
+        subscriber.token
+    );
+
+    Email {
+        to: subscriber.email.clone(),
+        subject: "Confirm your subscription to Heavy Metal Releases".to_string(),
+        html: email_layout(html!(
+            p { "Thank you for subscribing to the releases of the day." }
+            p {
+                "Please confirm your email address by clicking "
+                a href=(confirm_url) { "this link" }
+                ". You can ignore this email if you did not subscribe."
+            }
+        ))
+        .into_string(),
+        text: format!(
+            "Thank you for subscribing to the releases of the day.\n\n\
+             Please confirm your email address by visiting {confirm_url}\n\n\
+             You can ignore this email if you did not subscribe.\n"
+        ),
+        reply_to: None,
+        unsubscribe_url: None,
+    }
+}
+
+/// The email of a link to log in to an account.
+pub fn login_link(email: &str, token: &str) -> Email {
+    let login_url = format!("{}/account/login/{token}", config().BASE_URL);
+    let minutes = LOGIN_TOKEN_TTL.whole_minutes();
+
+    Email {
+        to: email.to_string(),
+        subject: "Log in to Heavy Metal Releases".to_string(),
+        html: email_layout(html!(
+            p {
+                "Log in to your account by clicking "
+                a href=(login_url) { "this link" }
+                ". The link expires in " (minutes) " minutes and can only be used once."
+            }
+            p { "You can ignore this email if you did not ask to log in." }
+        ))
+        .into_string(),
+        text: format!(
+            "Log in to your account by visiting {login_url}\n\n\
+             The link expires in {minutes} minutes and can only be used once. \
+             You can ignore this email if you did not ask to log in.\n"
+        ),
+        reply_to: None,
+        unsubscribe_url: None,
+    }
+}
+
+/// The email listing the releases of the day.
+pub fn daily_releases(
+    subscriber: &Subscriber,
+    date: &str,
+    releases: &[&ReleaseWithArtists],
+) -> Email {
+    let unsubscribe_url = format!(
+        "{}/subscribers/unsubscribe/{}",
+        config().BASE_URL,
+        subscriber.token
+    );
+
+    let html = email_layout(html!(
+        h2 { "Releases of " (date) }
+        @for (release, _, links) in releases {
+            p {
+                @if let Some(url) = release.cover_url() {
+                    img src=(url) alt=(format!("{} - {}", release.artist_credit, release.album)) width="125";
+                    br;
+                }
+                b { (release.artist_credit) " - " (release.album) }
+                @if release.kind() != ReleaseType::Album {
+                    " (" (release.kind()) ")"
+                }
+                @for link in links {
+                    @if let Some(kind) = link.kind() {
+                        br;
+                        "• " a href=(link.url) { (kind) }
+                    }
+                }
+            }
+        }
+        hr;
+        p style="font-size: small" {
+            "You receive this email because you subscribed to Heavy Metal Releases. "
+            a href=(unsubscribe_url) { "Unsubscribe" }
+        }
+    ));
+
+    let mut text = format!("Releases of {date}\n\n");
+    for (release, _, links) in releases {
+        text.push_str(&format!("{} - {}", release.artist_credit, release.album));
+        if release.kind() != ReleaseType::Album {
+            text.push_str(&format!(" ({})", release.kind()));
+        }
+        text.push('\n');
+        for link in links {
+            if let Some(kind) = link.kind() {
+                text.push_str(&format!("  • {kind}: {}\n", link.url));
+            }
+        }
+    }
+    text.push_str(&format!("\nUnsubscribe: {unsubscribe_url}\n"));
+
+    Email {
+        to: subscriber.email.clone(),
+        subject: format!("Heavy metal releases of {date}"),
+        html: html.into_string(),
+        text,
+        reply_to: None,
+        unsubscribe_url: Some(unsubscribe_url),
+    }
+}
+
+/// Emails are read without the site's stylesheets, so they only use inline styles.
+fn email_layout(content: Markup) -> Markup {
+    html!(
+        (DOCTYPE)
+        html lang="en" {
+            head {
+                meta charset="UTF-8";
+                title { "Heavy Metal Releases" }
+            }
+            body style="font-family: sans-serif" {
+                (content)
+            }
+        }
+    )
+}
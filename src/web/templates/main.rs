@@ -2,7 +2,13 @@ use maud::{html, Markup, PreEscaped};
 
 use crate::config::config;
 
-use super::{core::layout, Page};
+use super::{
+    core::{form_guard, layout},
+    Page,
+};
+
+/// The maximum number of characters of a message sent from the contact form.
+pub const MAX_CONTACT_MESSAGE_LEN: usize = 5000;
 
 pub fn index() -> Markup {
     layout(
@@ -24,6 +30,21 @@ pub fn index() -> Markup {
                             img src="https://upload.wikimedia.org/wikipedia/commons/thumb/4/43/Feed-icon.svg/128px-Feed-icon.svg.png" height="32px" width="32px" alt="rss icon";
                         }
                     }
+                    form class="flex flex-wrap gap-2 mt-4" method="post" action="/subscribers" {
+                        (form_guard())
+                        input
+                            class="border border-gray-400 p-2 rounded-full flex-grow"
+                            type="email"
+                            name="email"
+                            placeholder="Or get an email: your@email.com"
+                            required;
+                        button
+                            class="bg-indigo-500 text-white py-2 px-4 rounded-full hover:bg-indigo-600"
+                            type="submit"
+                        {
+                            "Subscribe"
+                        }
+                    }
                 }
             }
             section class="col-span-12 container mx-auto px-6 p-10" {
@@ -189,7 +210,26 @@ pub fn about() -> Markup {
     )
 }
 
-pub fn contact(is_message_sent: bool) -> Markup {
+/// The state of the contact form shown on the contact page.
+pub enum ContactStatus<'a> {
+    /// The form was not submitted yet.
+    Blank,
+    /// The message was received.
+    Sent,
+    /// The message was not received. The submitted values are filled in again.
+    Rejected {
+        email: &'a str,
+        message: &'a str,
+        error: &'a str,
+    },
+}
+
+pub fn contact(status: ContactStatus) -> Markup {
+    let (email, message) = match &status {
+        ContactStatus::Rejected { email, message, .. } => (*email, *message),
+        _ => ("", ""),
+    };
+
     layout(
         "Contact us",
         true,
@@ -205,7 +245,21 @@ pub fn contact(is_message_sent: bool) -> Markup {
                         p class="mb-4" {
                             "To address any inquiries, please send a message to us directly from the form below."
                         }
+                        @match status {
+                            ContactStatus::Sent => {
+                                div role="alert" class="alert alert-success w-full md:w-3/4 mb-4" {
+                                    "Message sent. We will get back to you soon."
+                                }
+                            }
+                            ContactStatus::Rejected { error, .. } => {
+                                div role="alert" class="alert alert-error w-full md:w-3/4 mb-4" {
+                                    (error)
+                                }
+                            }
+                            ContactStatus::Blank => {}
+                        }
                         form class="w-full md:w-3/4 bg-white p-6 rounded-lg shadow-md mb-8" method="post" action="/contact" {
+                            (form_guard())
                             div class="mb-4" {
                                 label class="block font-bold mb-2" for="email" {
                                     "Email"
@@ -216,6 +270,7 @@ pub fn contact(is_message_sent: bool) -> Markup {
                                     id="email"
                                     name="email"
                                     placeholder="your@email.com"
+                                    value=(email)
                                     required;
                             }
                             div class="mb-4" {
@@ -225,7 +280,8 @@ pub fn contact(is_message_sent: bool) -> Markup {
                                     id="message"
                                     name="message"
                                     placeholder="Hello Metal Releases, I have something to say."
-                                    required {}
+                                    maxlength=(MAX_CONTACT_MESSAGE_LEN)
+                                    required { (message) }
                             }
                             div class="text-right" {
                                 button
@@ -242,9 +298,29 @@ pub fn contact(is_message_sent: bool) -> Markup {
                     }
                 }
             }
-            @if is_message_sent {
-                script defer {
-                    (PreEscaped("alert(\"Message sent. We will get back to you soon.\")"))
+        ),
+    )
+}
+
+pub fn unsubscribe(token: &str) -> Markup {
+    layout(
+        "Unsubscribe",
+        false,
+        Page::Other,
+        html!(
+            div class="card w-80 sm:w-96 bg-base-100 shadow-xl" {
+                div class="card-body" {
+                    h2 class="card-title underline self-center" {
+                        "Unsubscribe"
+                    }
+                    p {
+                        "Do you want to stop receiving the releases of the day by email?"
+                    }
+                    form class="card-actions justify-end" method="post" action=(format!("/subscribers/unsubscribe/{token}")) {
+                        button class="btn btn-primary btn-block btn-sm" type="submit" {
+                            "Unsubscribe"
+                        }
+                    }
                 }
             }
         ),
@@ -1,10 +1,15 @@
+pub mod account;
+pub mod artists;
+pub mod calendar;
 mod core;
+pub mod emails;
 pub mod general;
 pub mod main;
 
 #[derive(PartialEq)]
 pub enum Page {
     About,
+    Account,
     Contact,
     Home,
     Other,
@@ -0,0 +1,571 @@
+//! The `webhooks` module posts the releases to the webhooks of the configuration.
+//!
+//! Every payload is a JSON object signed with the webhook's secret. The
+//! HMAC-SHA256 signature of the body is sent in the `X-Webhook-Signature`
+//! header as `sha256=<hex>`, and the event type in the `X-Webhook-Event` header.
+
+use std::{collections::HashMap, fmt, str::FromStr, time::Duration};
+
+use reqwest::{header::CONTENT_TYPE, StatusCode};
+use serde::Serialize;
+use time::OffsetDateTime;
+use tracing::{error, info, warn};
+
+use crate::{
+    config::config,
+    error::Result,
+    model::{ReleaseWithArtists, WebhookDeliveryBmc, WebhookDeliveryForCreate},
+    scraper::client::USER_AGENT,
+    support::signature::sign,
+};
+
+/// The number of requests sent for an event before giving up.
+const MAX_ATTEMPTS: u32 = 5;
+
+/// The delay before the first retry, doubled after every attempt.
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(2);
+
+const TIMEOUT: Duration = Duration::from_secs(15);
+
+/// The types of events posted to the webhooks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EventKind {
+    /// The releases of the day, posted every morning.
+    DailyReleases,
+    /// A release that was not in the calendar before its last update.
+    ReleaseAdded,
+    /// A release whose date changed during the last update of the calendar.
+    ReleaseMoved,
+}
+
+impl EventKind {
+    pub const ALL: [EventKind; 3] = [
+        EventKind::DailyReleases,
+        EventKind::ReleaseAdded,
+        EventKind::ReleaseMoved,
+    ];
+
+    /// The identifier used in the configuration and in the payloads.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventKind::DailyReleases => "daily_releases",
+            EventKind::ReleaseAdded => "release_added",
+            EventKind::ReleaseMoved => "release_moved",
+        }
+    }
+}
+
+impl fmt::Display for EventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for EventKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> core::result::Result<Self, Self::Err> {
+        EventKind::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == s)
+            .ok_or_else(|| format!("unknown webhook event `{s}`"))
+    }
+}
+
+/// An event, along with the JSON body posted to the webhooks.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Event {
+    pub kind: EventKind,
+    pub body: String,
+}
+
+impl Event {
+    fn new(kind: EventKind, data: impl Serialize) -> Result<Self> {
+        #[derive(Serialize)]
+        struct Body<T> {
+            event: &'static str,
+            created_at: i64,
+            data: T,
+        }
+
+        let body = serde_json::to_string(&Body {
+            event: kind.as_str(),
+            created_at: OffsetDateTime::now_utc().unix_timestamp(),
+            data,
+        })?;
+        Ok(Self { kind, body })
+    }
+}
+
+#[derive(Serialize)]
+struct ReleasePayload<'a> {
+    /// Identifies the release across the updates of the calendar, which
+    /// change the ids of the releases, e.g. `wintersun - time ii`.
+    key: String,
+    /// The release date, e.g. `2024-08-30`.
+    date: String,
+    artist: &'a str,
+    album: &'a str,
+    release_type: &'static str,
+    artists: Vec<&'a str>,
+    label: Option<&'a str>,
+    genre: Option<&'a str>,
+    cover_url: Option<String>,
+    wikipedia_url: Option<String>,
+    musicbrainz_url: Option<String>,
+    links: Vec<LinkPayload<'a>>,
+}
+
+#[derive(Serialize)]
+struct LinkPayload<'a> {
+    kind: &'a str,
+    url: &'a str,
+}
+
+impl<'a> ReleasePayload<'a> {
+    fn new((release, artists, links): &'a ReleaseWithArtists) -> Self {
+        Self {
+            key: release.notification_key(),
+            date: release.date_string(),
+            artist: &release.artist_credit,
+            album: &release.album,
+            release_type: release.kind().as_str(),
+            artists: artists.iter().map(|artist| artist.name.as_str()).collect(),
+            label: release.label.as_deref(),
+            genre: release.genre.as_deref(),
+            cover_url: release.cover_url(),
+            wikipedia_url: release.wikipedia_url(),
+            musicbrainz_url: release.musicbrainz_url(),
+            links: links
+                .iter()
+                .map(|link| LinkPayload {
+                    kind: &link.kind,
+                    url: &link.url,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The `daily_releases` event listing the releases of the date.
+pub fn daily_releases(date: &str, releases: &[&ReleaseWithArtists]) -> Result<Event> {
+    #[derive(Serialize)]
+    struct Data<'a> {
+        date: &'a str,
+        releases: Vec<ReleasePayload<'a>>,
+    }
+
+    Event::new(
+        EventKind::DailyReleases,
+        Data {
+            date,
+            releases: releases
+                .iter()
+                .map(|release| ReleasePayload::new(release))
+                .collect(),
+        },
+    )
+}
+
+/// The `release_added` and `release_moved` events of an update of the
+/// calendar, found by comparing the releases before and after the update.
+///
+/// Releases are matched on their artist and album, regardless of case.
+pub fn release_changes(
+    before: &[ReleaseWithArtists],
+    after: &[ReleaseWithArtists],
+) -> Result<Vec<Event>> {
+    #[derive(Serialize)]
+    struct Added<'a> {
+        release: ReleasePayload<'a>,
+    }
+
+    #[derive(Serialize)]
+    struct Moved<'a> {
+        release: ReleasePayload<'a>,
+        previous_date: String,
+    }
+
+    let previous_dates = before
+        .iter()
+        .map(|(release, _, _)| (release.notification_key(), release.date_string()))
+        .collect::<HashMap<_, _>>();
+
+    let mut events = Vec::new();
+    for release in after {
+        let date = release.0.date_string();
+        match previous_dates.get(&release.0.notification_key()) {
+            None => events.push(Event::new(
+                EventKind::ReleaseAdded,
+                Added {
+                    release: ReleasePayload::new(release),
+                },
+            )?),
+            Some(previous_date) if *previous_date != date => events.push(Event::new(
+                EventKind::ReleaseMoved,
+                Moved {
+                    release: ReleasePayload::new(release),
+                    previous_date: previous_date.clone(),
+                },
+            )?),
+            Some(_) => {}
+        }
+    }
+    Ok(events)
+}
+
+/// A webhook events are posted to.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Webhook {
+    pub url: String,
+    pub events: Vec<EventKind>,
+    pub secret: String,
+}
+
+/// The outcome of the delivery of an event to a webhook.
+#[derive(Debug, PartialEq)]
+pub struct Delivery {
+    pub attempts: u32,
+    /// The status code of the last response, if any was received.
+    pub status_code: Option<u16>,
+    /// Why the delivery failed, or `None` when it succeeded.
+    pub error: Option<String>,
+}
+
+/// Posts the events to the webhooks subscribed to them.
+pub struct Dispatcher {
+    client: reqwest::Client,
+    webhooks: Vec<Webhook>,
+    retry_base_delay: Duration,
+}
+
+impl Dispatcher {
+    /// Creates the dispatcher of the webhooks of `WEBHOOKS`. Unknown events are ignored.
+    pub fn from_config() -> Result<Self> {
+        let webhooks = config()
+            .WEBHOOKS
+            .iter()
+            .map(|webhook| Webhook {
+                url: webhook.url.clone(),
+                events: webhook
+                    .events
+                    .iter()
+                    .filter_map(|event| match event.parse() {
+                        Ok(kind) => Some(kind),
+                        Err(err) => {
+                            warn!("Ignoring an event of webhook {}: {err}", webhook.url);
+                            None
+                        }
+                    })
+                    .collect(),
+                secret: webhook.secret.clone(),
+            })
+            .collect();
+
+        Self::new(webhooks, RETRY_BASE_DELAY)
+    }
+
+    pub fn new(webhooks: Vec<Webhook>, retry_base_delay: Duration) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .user_agent(USER_AGENT)
+            .timeout(TIMEOUT)
+            .build()?;
+
+        Ok(Self {
+            client,
+            webhooks,
+            retry_base_delay,
+        })
+    }
+
+    /// Whether a webhook is subscribed to the events of the kind.
+    pub fn wants(&self, kind: EventKind) -> bool {
+        self.webhooks
+            .iter()
+            .any(|webhook| webhook.events.contains(&kind))
+    }
+
+    /// Posts every event to the webhooks subscribed to it and logs the
+    /// deliveries in the `webhook_deliveries` table.
+    pub async fn dispatch(&self, events: &[Event]) -> Result<()> {
+        for webhook in self.webhooks.iter() {
+            let events = events
+                .iter()
+                .filter(|event| webhook.events.contains(&event.kind))
+                .collect::<Vec<_>>();
+            if events.is_empty() {
+                continue;
+            }
+            info!("Posting {} events to webhook {}", events.len(), webhook.url);
+
+            for event in events {
+                self.deliver_and_log(webhook, event).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The URLs of the webhooks subscribed to the events of the kind.
+    pub fn urls(&self, kind: EventKind) -> Vec<&str> {
+        self.webhooks
+            .iter()
+            .filter(|webhook| webhook.events.contains(&kind))
+            .map(|webhook| webhook.url.as_str())
+            .collect()
+    }
+
+    /// Posts the event to the webhook at the URL and logs the delivery.
+    /// Returns `None` when no webhook at the URL is subscribed to the event.
+    pub async fn send(&self, url: &str, event: &Event) -> Result<Option<Delivery>> {
+        let webhook = self
+            .webhooks
+            .iter()
+            .find(|webhook| webhook.url == url && webhook.events.contains(&event.kind));
+        match webhook {
+            Some(webhook) => Ok(Some(self.deliver_and_log(webhook, event).await?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn deliver_and_log(&self, webhook: &Webhook, event: &Event) -> Result<Delivery> {
+        let delivery = self.deliver(webhook, event).await;
+        if let Some(err) = &delivery.error {
+            error!(
+                "Error posting {} to webhook {}: {err}",
+                event.kind, webhook.url
+            );
+        }
+
+        WebhookDeliveryBmc::create(WebhookDeliveryForCreate {
+            url: webhook.url.clone(),
+            event: event.kind.as_str().to_string(),
+            payload: event.body.clone(),
+            attempts: delivery.attempts as i32,
+            status_code: delivery.status_code.map(i32::from),
+            error: delivery.error.clone(),
+        })?;
+        Ok(delivery)
+    }
+
+    /// Posts the event to the webhook, retrying with an exponential backoff
+    /// when the request fails or the server responds with an error that may
+    /// be temporary.
+    async fn deliver(&self, webhook: &Webhook, event: &Event) -> Delivery {
+        let signature = format!(
+            "sha256={}",
+            sign(webhook.secret.as_bytes(), event.body.as_bytes())
+        );
+
+        let mut delivery = Delivery {
+            attempts: 0,
+            status_code: None,
+            error: None,
+        };
+        while delivery.attempts < MAX_ATTEMPTS {
+            if delivery.attempts > 0 {
+                tokio::time::sleep(self.retry_base_delay * 2u32.pow(delivery.attempts - 1)).await;
+            }
+            delivery.attempts += 1;
+
+            let result = self
+                .client
+                .post(&webhook.url)
+                .header(CONTENT_TYPE, "application/json")
+                .header("X-Webhook-Event", event.kind.as_str())
+                .header("X-Webhook-Signature", &signature)
+                .body(event.body.clone())
+                .send()
+                .await;
+
+            match result {
+                Ok(res) => {
+                    let status = res.status();
+                    delivery.status_code = Some(status.as_u16());
+                    if status.is_success() {
+                        delivery.error = None;
+                        return delivery;
+                    }
+
+                    delivery.error = Some(format!("the webhook responded with {status}"));
+                    if !is_retryable(status) {
+                        return delivery;
+                    }
+                }
+                Err(err) => {
+                    delivery.status_code = None;
+                    delivery.error = Some(err.to_string());
+                }
+            }
+        }
+        delivery
+    }
+}
+
+/// Client errors other than timeouts and rate limits are not worth retrying.
+fn is_retryable(status: StatusCode) -> bool {
+    status.is_server_error()
+        || status == StatusCode::REQUEST_TIMEOUT
+        || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    };
+
+    use axum::{extract::State, http::HeaderMap, routing::post, Router};
+    use tokio::net::TcpListener;
+
+    use crate::support::{fixtures::release, signature::verify};
+
+    type Result<T> = core::result::Result<T, Box<dyn std::error::Error>>;
+
+    #[derive(Clone, Default)]
+    struct Receiver {
+        /// The number of requests answered with the status code before answering 200.
+        failures: usize,
+        failure_status: u16,
+        received: Arc<Mutex<Vec<(HeaderMap, String)>>>,
+        count: Arc<AtomicUsize>,
+    }
+
+    /// Serves a webhook receiver on a local port and returns its URL.
+    async fn serve_receiver(receiver: Receiver) -> Result<String> {
+        async fn receive(
+            State(receiver): State<Receiver>,
+            headers: HeaderMap,
+            body: String,
+        ) -> StatusCode {
+            receiver.received.lock().unwrap().push((headers, body));
+            if receiver.count.fetch_add(1, Ordering::SeqCst) < receiver.failures {
+                StatusCode::from_u16(receiver.failure_status).unwrap()
+            } else {
+                StatusCode::OK
+            }
+        }
+
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let app = Router::new()
+            .route("/hook", post(receive))
+            .with_state(receiver);
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        Ok(format!("http://{addr}/hook"))
+    }
+
+    fn webhook(url: String) -> Webhook {
+        Webhook {
+            url,
+            events: vec![EventKind::ReleaseAdded],
+            secret: "s3cr3t".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_deliver_retries_ok() -> Result<()> {
+        let receiver = Receiver {
+            failures: 2,
+            failure_status: 503,
+            ..Default::default()
+        };
+        let url = serve_receiver(receiver.clone()).await?;
+        let dispatcher = Dispatcher::new(vec![], Duration::from_millis(10))?;
+        let event = Event {
+            kind: EventKind::ReleaseAdded,
+            body: r#"{"event":"release_added"}"#.to_string(),
+        };
+
+        let got = dispatcher.deliver(&webhook(url), &event).await;
+
+        pretty_assertions::assert_eq!(
+            got,
+            Delivery {
+                attempts: 3,
+                status_code: Some(200),
+                error: None,
+            }
+        );
+        let received = receiver.received.lock().unwrap();
+        let (headers, body) = received.last().unwrap();
+        pretty_assertions::assert_eq!(body, r#"{"event":"release_added"}"#);
+        pretty_assertions::assert_eq!(headers["x-webhook-event"], "release_added");
+        let signature = headers["x-webhook-signature"].to_str()?;
+        assert!(verify(
+            b"s3cr3t",
+            body.as_bytes(),
+            signature.strip_prefix("sha256=").unwrap()
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_deliver_client_error_ok() -> Result<()> {
+        let receiver = Receiver {
+            failures: 1,
+            failure_status: 410,
+            ..Default::default()
+        };
+        let url = serve_receiver(receiver.clone()).await?;
+        let dispatcher = Dispatcher::new(vec![], Duration::from_millis(10))?;
+        let event = Event {
+            kind: EventKind::ReleaseAdded,
+            body: "{}".to_string(),
+        };
+
+        let got = dispatcher.deliver(&webhook(url), &event).await;
+
+        pretty_assertions::assert_eq!(
+            got,
+            Delivery {
+                attempts: 1,
+                status_code: Some(410),
+                error: Some("the webhook responded with 410 Gone".to_string()),
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_release_changes_ok() -> Result<()> {
+        let before = vec![
+            release(1, "Wintersun", "Time II").date(8, 30).build(),
+            release(2, "Opeth", "The Last Will and Testament")
+                .date(9, 6)
+                .build(),
+        ];
+        let after = vec![
+            release(3, "Wintersun", "Time II").date(8, 30).build(),
+            release(4, "Opeth", "The Last Will and Testament")
+                .date(11, 22)
+                .build(),
+            release(5, "Blood Incantation", "Absolute Elsewhere")
+                .date(10, 4)
+                .build(),
+        ];
+
+        let got = release_changes(&before, &after)?
+            .into_iter()
+            .map(|event| {
+                let body: serde_json::Value = serde_json::from_str(&event.body).unwrap();
+                (event.kind, body["data"].clone())
+            })
+            .collect::<Vec<_>>();
+
+        pretty_assertions::assert_eq!(got.len(), 2);
+        pretty_assertions::assert_eq!(got[0].0, EventKind::ReleaseMoved);
+        pretty_assertions::assert_eq!(
+            got[0].1["release"]["key"],
+            "opeth - the last will and testament"
+        );
+        pretty_assertions::assert_eq!(got[0].1["release"]["date"], "2024-11-22");
+        pretty_assertions::assert_eq!(got[0].1["previous_date"], "2024-09-06");
+        pretty_assertions::assert_eq!(got[1].0, EventKind::ReleaseAdded);
+        pretty_assertions::assert_eq!(got[1].1["release"]["album"], "Absolute Elsewhere");
+        pretty_assertions::assert_eq!(got[1].1["release"]["links"][0]["kind"], "bandcamp");
+        Ok(())
+    }
+}